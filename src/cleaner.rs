@@ -1,16 +1,31 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use console::style;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
 
-use crate::config::Config;
-use crate::project::{CleanTarget, Project, ProjectDetector, TargetType};
-use crate::utils::fs_utils::remove_directory;
+use crate::config::{Config, OutputFormat, Verbosity};
+use crate::confirm::{CleaningSummary, Confirmer, StdinConfirmer, apply_selection, select_targets};
+use crate::journal::{Journal, JournalEvent};
+use crate::manifest::Manifest;
+use crate::plugins::{self, HookContext, HookType, PluginRegistry};
+use crate::project::{CleanTarget, Project, TargetType};
+use crate::utils::fs_utils::{
+    DirectoryRemover, FreeSpaceProbe, FsRemover, SystemFreeSpace, calculate_directory_size,
+    format_size, volume_id,
+};
 
 /// 清理结果数据
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CleanResults {
     pub total_projects: usize,
     pub cleaned_projects: usize,
@@ -19,13 +34,263 @@ pub struct CleanResults {
     pub cleaned_targets: usize,
     pub failed_targets: usize,
     pub total_bytes_removed: u64,
+    /// 移到恢复清单暂存位置的字节数，不计入释放的空间
+    pub staged_bytes: u64,
+    /// 已删除但大小未知（未统计大小且未边删边统计）的目标数，这些目标未计入释放的空间
+    pub unmeasured_targets: usize,
+    /// 按目标类型统计的释放字节数（dry run 时为将要释放的字节数）
+    pub node_modules_bytes: u64,
+    pub build_bytes: u64,
+    pub cache_bytes: u64,
+    pub coverage_bytes: u64,
+    pub custom_bytes: u64,
+    /// 超过最长运行时间后未开始处理的目标数
+    pub not_started_targets: usize,
+    /// 开启 `--measure-free-delta` 时，清理前后目标所在卷的可用空间合计
+    pub free_space_before: Option<u64>,
+    pub free_space_after: Option<u64>,
+    /// 每个目标的处理结果，按完成顺序排列
+    pub outcomes: Vec<TargetOutcome>,
+    /// `--dry-run --list` 时将会删除的目标的绝对路径
+    pub would_delete: Vec<PathBuf>,
+    /// dry run 时超过 `confirm_above`、实际清理时需要单独确认的目标及其大小
+    pub would_confirm: Vec<(PathBuf, u64)>,
+    /// 预览之后、确认完成之前消失的目标，这些目标不再处理（`strict_missing` 时记为失败）
+    pub vanished_since_preview: Vec<PathBuf>,
+    /// 预览之后、确认完成之前新出现的目标，只有再次确认后才会清理
+    pub appeared_since_preview: Vec<PathBuf>,
+    /// 用户拒绝确认或在交互选择中未勾选任何目标，没有执行清理
+    pub cancelled: bool,
 }
 
+/// 单个目标的处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutcomeStatus {
+    /// 已删除（dry run 时表示将会删除）
+    Cleaned,
+    /// 删除前目标已不存在
+    Missing,
+    /// 删除失败
+    Failed,
+    /// 已移到恢复清单的暂存位置，空间尚未释放
+    Staged,
+}
+
+/// 单个目标的处理结果
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetOutcome {
+    pub project_path: PathBuf,
+    pub target_path: PathBuf,
+    pub target_type: TargetType,
+    /// 扫描时估计的大小，删除失败时表示仍被占用的空间
+    pub size: Option<u64>,
+    pub bytes_removed: u64,
+    pub status: OutcomeStatus,
+    /// 失败原因，仅在失败时存在
+    pub error: Option<String>,
+}
+
+impl CleanResults {
+    /// 创建空的清理结果
+    fn new(total_projects: usize) -> Self {
+        Self {
+            total_projects,
+            cleaned_projects: 0,
+            failed_projects: 0,
+            total_targets: 0,
+            cleaned_targets: 0,
+            failed_targets: 0,
+            total_bytes_removed: 0,
+            staged_bytes: 0,
+            unmeasured_targets: 0,
+            node_modules_bytes: 0,
+            build_bytes: 0,
+            cache_bytes: 0,
+            coverage_bytes: 0,
+            custom_bytes: 0,
+            not_started_targets: 0,
+            free_space_before: None,
+            free_space_after: None,
+            outcomes: Vec::new(),
+            would_delete: Vec::new(),
+            would_confirm: Vec::new(),
+            vanished_since_preview: Vec::new(),
+            appeared_since_preview: Vec::new(),
+            cancelled: false,
+        }
+    }
+
+    /// 创建用户取消清理时的结果
+    fn cancelled(total_projects: usize) -> Self {
+        Self {
+            cancelled: true,
+            ..Self::new(total_projects)
+        }
+    }
+
+    /// 实际测得的可用空间变化，正数表示空间增加；任一次查询失败时返回 None
+    pub fn free_space_delta(&self) -> Option<i128> {
+        Some(i128::from(self.free_space_after?) - i128::from(self.free_space_before?))
+    }
+
+    /// 记录一个目标的处理结果并更新统计
+    fn record(
+        &mut self,
+        project: &Project,
+        target: &CleanTarget,
+        status: OutcomeStatus,
+        bytes_removed: u64,
+    ) {
+        if status == OutcomeStatus::Failed {
+            self.failed_targets += 1;
+        } else {
+            self.cleaned_targets += 1;
+            self.total_bytes_removed += bytes_removed;
+            *self.bytes_for_type_mut(&target.target_type) += bytes_removed;
+        }
+
+        self.outcomes.push(TargetOutcome {
+            project_path: project.path.clone(),
+            target_path: target.path.clone(),
+            target_type: target.target_type.clone(),
+            size: target.size,
+            bytes_removed,
+            status,
+            error: None,
+        });
+    }
+
+    /// 某类目标对应的字节计数器
+    fn bytes_for_type_mut(&mut self, target_type: &TargetType) -> &mut u64 {
+        match target_type {
+            TargetType::NodeModules => &mut self.node_modules_bytes,
+            TargetType::BuildDir => &mut self.build_bytes,
+            TargetType::CacheDir => &mut self.cache_bytes,
+            TargetType::Coverage => &mut self.coverage_bytes,
+            TargetType::Custom(_) => &mut self.custom_bytes,
+        }
+    }
+
+    /// 记录一个移到暂存位置的目标，其大小只计入 `staged_bytes`
+    fn record_staged(&mut self, project: &Project, target: &CleanTarget) {
+        self.record(project, target, OutcomeStatus::Staged, 0);
+        self.staged_bytes += target.size.unwrap_or(0);
+    }
+
+    /// 记录一个失败的目标及失败原因
+    fn record_failure(&mut self, project: &Project, target: &CleanTarget, error: &anyhow::Error) {
+        self.record(project, target, OutcomeStatus::Failed, 0);
+        if let Some(outcome) = self.outcomes.last_mut() {
+            outcome.error = Some(format!("{:#}", error));
+        }
+    }
+
+    /// 删除失败的目标
+    pub fn failures(&self) -> impl Iterator<Item = &TargetOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.status == OutcomeStatus::Failed)
+    }
+}
+
+/// 返回已经不存在的目标的路径，`drop` 时同时把它们从项目中去掉
+fn vanished_targets(projects: &mut [Project], drop: bool) -> Vec<PathBuf> {
+    let mut vanished = Vec::new();
+    for project in projects {
+        project.detected_targets.retain(|target| {
+            let exists = fs::symlink_metadata(&target.path).is_ok();
+            if !exists {
+                vanished.push(target.path.clone());
+            }
+            exists || !drop
+        });
+    }
+    vanished
+}
+
+/// 错误链中是否包含"文件不存在"的 I/O 错误
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .any(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
+/// 项目中已知的目标路径（包括被过滤规则跳过的目标）
+fn known_target_paths(projects: &[Project]) -> HashSet<PathBuf> {
+    projects
+        .iter()
+        .flat_map(|project| {
+            project
+                .detected_targets
+                .iter()
+                .chain(
+                    project
+                        .skipped_targets
+                        .iter()
+                        .map(|skipped| &skipped.target),
+                )
+                .map(|target| target.path.clone())
+        })
+        .collect()
+}
+
+/// 说明预览之后计划的变化：消失的目标被跳过（`strict_missing` 时记为失败），
+/// 新出现的目标需要再次确认
+fn write_changes_since_preview(
+    out: &mut impl Write,
+    vanished: &[PathBuf],
+    appeared: &[PathBuf],
+    strict_missing: bool,
+) -> io::Result<()> {
+    if !vanished.is_empty() {
+        writeln!(
+            out,
+            "\n{} {} targets no longer exist and will be {}:",
+            style("Changes since preview:").yellow().bold(),
+            vanished.len(),
+            if strict_missing {
+                "reported as failures"
+            } else {
+                "skipped"
+            }
+        )?;
+        for path in vanished {
+            writeln!(out, "  {}", path.display())?;
+        }
+    }
+    if !appeared.is_empty() {
+        writeln!(
+            out,
+            "\n{} {} new targets appeared and need confirmation:",
+            style("Changes since preview:").yellow().bold(),
+            appeared.len()
+        )?;
+        for path in appeared {
+            writeln!(out, "  {}", path.display())?;
+        }
+    }
+    Ok(())
+}
+
+/// 重新分析单个项目目录，用于发现预览之后新出现的目标
+pub type Rescan<'a> = dyn Fn(&Path) -> Option<Project> + Sync + 'a;
+
 /// 清理器，用于执行清理操作
 pub struct Cleaner<'a> {
     config: &'a Config,
     multi_progress: MultiProgress,
-    additional_detectors: Vec<Box<dyn ProjectDetector>>,
+    plugins: Option<&'a PluginRegistry>,
+    journal: Option<&'a Journal>,
+    manifest: Option<&'a Manifest>,
+    confirmer: Option<&'a dyn Confirmer>,
+    rescan: Option<&'a Rescan<'a>>,
+    remover: Arc<dyn DirectoryRemover>,
+    /// 计算 `max_runtime` 的起点
+    started: Instant,
+    /// 并行清理时保证大目标的确认提示逐个显示
+    prompt_lock: Mutex<()>,
 }
 
 impl<'a> Cleaner<'a> {
@@ -33,46 +298,209 @@ impl<'a> Cleaner<'a> {
         Self {
             config,
             multi_progress: MultiProgress::new(),
-            additional_detectors: Vec::new(),
+            plugins: None,
+            journal: None,
+            manifest: None,
+            confirmer: None,
+            rescan: None,
+            remover: Arc::new(FsRemover {
+                two_phase: config.two_phase_delete,
+                trash_suffix: config.trash_suffix.clone(),
+            }),
+            started: Instant::now(),
+            prompt_lock: Mutex::new(()),
         }
     }
 
-    /// 添加额外的项目检测器（来自插件）
-    pub fn add_detectors(&mut self, detectors: Vec<Box<dyn ProjectDetector>>) {
-        self.additional_detectors.extend(detectors);
+    /// 设置插件注册表，用于在清理每个项目和目标时执行钩子
+    pub fn with_plugins(mut self, plugins: &'a PluginRegistry) -> Self {
+        self.plugins = Some(plugins);
+        self
     }
 
-    /// 清理项目列表
-    pub fn clean(&self, projects: Vec<Project>) -> Result<CleanResults> {
-        let results = CleanResults {
-            total_projects: projects.len(),
-            cleaned_projects: 0,
-            failed_projects: 0,
-            total_targets: 0,
-            cleaned_targets: 0,
-            failed_targets: 0,
-            total_bytes_removed: 0,
-        };
+    /// 设置删除日志，每次删除前后都会写入记录
+    pub fn with_journal(mut self, journal: &'a Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
 
-        let results = Arc::new(Mutex::new(results));
+    /// 设置恢复清单，目标移到暂存位置而不是删除
+    pub fn with_manifest(mut self, manifest: &'a Manifest) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
 
+    /// 设置清理前的确认方式，未设置时从标准输入读取
+    pub fn with_confirmer(mut self, confirmer: &'a dyn Confirmer) -> Self {
+        self.confirmer = Some(confirmer);
+        self
+    }
+
+    /// 设置重新分析项目的方式，确认之后用它查找预览之后新出现的目标；未设置时不查找
+    pub fn with_rescan(mut self, rescan: &'a Rescan<'a>) -> Self {
+        self.rescan = Some(rescan);
+        self
+    }
+
+    /// 设置计算最长运行时间的起点，默认是创建清理器的时间
+    pub fn with_start_time(mut self, started: Instant) -> Self {
+        self.started = started;
+        self
+    }
+
+    /// 设置删除目录的方式，默认直接在文件系统上删除
+    #[allow(dead_code)]
+    pub fn with_remover(mut self, remover: Arc<dyn DirectoryRemover>) -> Self {
+        self.remover = remover;
+        self
+    }
+
+    /// 清理项目列表
+    pub fn clean(&self, projects: Vec<Project>) -> Result<CleanResults> {
         // 如果没有找到项目
         if projects.is_empty() {
             info!("No projects found to clean");
-            println!("No projects found to clean");
-            return Ok(Arc::try_unwrap(results).unwrap().into_inner().unwrap());
+            if self.config.format != OutputFormat::Json {
+                println!("No projects found to clean");
+            }
+            return Ok(CleanResults::new(0));
         }
 
-        // 显示清理前统计
-        self.display_cleaning_preview(&projects)?;
+        // 两阶段删除依赖目标所在卷的状态，先检查可用空间能否查询
+        let mut projects = if self.config.two_phase_delete && !self.config.dry_run {
+            self.check_free_space(projects, &SystemFreeSpace)
+        } else {
+            projects
+        };
+
+        // 显示清理前统计；JSON 输出时标准输出只保留 JSON 文档，quiet 模式下不显示
+        if self.config.format != OutputFormat::Json && self.config.verbosity > Verbosity::Quiet {
+            self.display_cleaning_preview(&projects)?;
+        }
+
+        let needs_confirmation = !self.config.force && !self.config.dry_run;
+        let previewed = known_target_paths(&projects);
+
+        // 交互模式下由用户勾选要删除的目标，勾选本身即为确认
+        let mut user_selected = false;
+        if needs_confirmation && self.config.interactive {
+            match select_targets(&projects, self.config)? {
+                Some(selected) => {
+                    apply_selection(&mut projects, &selected);
+                    user_selected = true;
+                    if selected.is_empty() {
+                        info!("No targets selected, cleaning cancelled");
+                        println!("No targets selected, cleaning cancelled");
+                        return Ok(CleanResults::cancelled(projects.len()));
+                    }
+                }
+                None => {
+                    warn!("--interactive requires a terminal, using the default selection");
+                    eprintln!(
+                        "Warning: --interactive requires a terminal, using the default selection"
+                    );
+                }
+            }
+        }
 
         // 如果需要确认且不是强制模式
-        if !self.config.force && !self.config.dry_run && !self.confirm_cleaning()? {
+        if needs_confirmation && !user_selected && !self.confirm_cleaning(&projects)? {
             info!("Cleaning cancelled by user");
             println!("Cleaning cancelled by user");
-            return Ok(Arc::try_unwrap(results).unwrap().into_inner().unwrap());
+            return Ok(CleanResults::cancelled(projects.len()));
+        }
+
+        // 用户查看预览和确认可能花了几分钟，期间文件系统可能已经变化，删除前重新检查。
+        // `strict_missing` 时消失的目标保留在列表中，清理时记为失败
+        let (vanished, appeared) = if needs_confirmation {
+            (
+                vanished_targets(&mut projects, !self.config.strict_missing),
+                self.appeared_targets(&projects, &previewed),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        let appeared_paths: Vec<PathBuf> = appeared
+            .iter()
+            .flat_map(|(_, targets)| targets.iter().map(|target| target.path.clone()))
+            .collect();
+        if (!vanished.is_empty() || !appeared_paths.is_empty())
+            && self.config.format != OutputFormat::Json
+        {
+            write_changes_since_preview(
+                &mut io::stdout(),
+                &vanished,
+                &appeared_paths,
+                self.config.strict_missing,
+            )?;
+        }
+
+        // 新出现的目标没有经过确认：勾选模式下不清理，否则单独再确认一次
+        if !appeared.is_empty() && !user_selected {
+            let new_projects: Vec<Project> = appeared
+                .iter()
+                .map(|(index, targets)| {
+                    let mut project = Project::new(projects[*index].path.clone());
+                    project.detected_targets = targets.clone();
+                    project
+                })
+                .collect();
+            if self.confirm_cleaning(&new_projects)? {
+                for (index, targets) in appeared {
+                    projects[index].detected_targets.extend(targets);
+                }
+            } else {
+                info!("New targets declined, cleaning only the previewed targets");
+            }
         }
 
+        // dry run 不删除任何文件，没有可测量的变化
+        let mut results = if !self.config.measure_free_delta || self.config.dry_run {
+            self.clean_projects(projects, user_selected)
+        } else {
+            let volumes = free_space_paths(&projects, volume_id);
+            let before = total_free_space(&volumes, &SystemFreeSpace);
+            let mut results = self.clean_projects(projects, user_selected);
+            results.free_space_before = before;
+            results.free_space_after = total_free_space(&volumes, &SystemFreeSpace);
+            results
+        };
+        results.vanished_since_preview = vanished;
+        results.appeared_since_preview = appeared_paths;
+        Ok(results)
+    }
+
+    /// 重新分析项目，返回预览中没有的、会被清理的目标及其所属项目的下标
+    fn appeared_targets(
+        &self,
+        projects: &[Project],
+        previewed: &HashSet<PathBuf>,
+    ) -> Vec<(usize, Vec<CleanTarget>)> {
+        let Some(rescan) = self.rescan else {
+            return Vec::new();
+        };
+
+        projects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, project)| {
+                let targets: Vec<CleanTarget> = rescan(&project.path)?
+                    .detected_targets
+                    .into_iter()
+                    .filter(|target| {
+                        !previewed.contains(&target.path)
+                            && self.config.should_clean(&target.target_type)
+                    })
+                    .collect();
+                (!targets.is_empty()).then_some((index, targets))
+            })
+            .collect()
+    }
+
+    /// 并行清理项目；`user_selected` 为 true 时项目中的目标都是用户勾选的，不再按类型过滤
+    fn clean_projects(&self, projects: Vec<Project>, user_selected: bool) -> CleanResults {
+        let results = Arc::new(Mutex::new(CleanResults::new(projects.len())));
+
         // 开始清理
         info!(
             "Starting {} of {} projects{}",
@@ -99,37 +527,107 @@ impl<'a> Cleaner<'a> {
             },
         );
 
-        // 并行处理每个项目
-        let _cleaned_results: Vec<_> = projects
-            .into_par_iter()
-            .map(|project| {
-                let project_result = self.clean_project(&project, &results);
-                progress.inc(1);
-                project_result
-            })
-            .collect();
+        // 并行处理每个项目，配置了删除线程数时在独立的线程池中运行，不受扫描的线程数影响
+        let clean_all = || {
+            projects
+                .into_par_iter()
+                .map(|project| {
+                    let project_result = self.clean_project(&project, &results, user_selected);
+                    progress.inc(1);
+                    project_result
+                })
+                .collect::<Vec<_>>()
+        };
+        let _cleaned_results = match self.delete_pool() {
+            Some(pool) => pool.install(clean_all),
+            None => clean_all(),
+        };
 
         progress.finish_with_message("Cleaning completed");
 
         let final_results = Arc::try_unwrap(results).unwrap().into_inner().unwrap();
 
+        if final_results.not_started_targets > 0 {
+            warn!(
+                "Maximum runtime reached, {} targets were not started",
+                final_results.not_started_targets
+            );
+        }
+
         if self.config.dry_run {
             info!(
-                "Dry run completed. Would have freed {} MB",
-                final_results.total_bytes_removed / (1024 * 1024)
+                "Dry run completed. Would have freed {}",
+                format_size(final_results.total_bytes_removed)
             );
         } else {
             info!(
-                "Cleaning completed. Freed {} MB",
-                final_results.total_bytes_removed / (1024 * 1024)
+                "Cleaning completed. Freed {}",
+                format_size(final_results.total_bytes_removed)
             );
         }
 
-        Ok(final_results)
+        final_results
+    }
+
+    /// 删除使用的线程池：`delete_threads`（未设置时为 `threads`）给定的大小，
+    /// 都未设置或创建失败时使用全局线程池
+    fn delete_pool(&self) -> Option<rayon::ThreadPool> {
+        let threads = self.config.delete_threads.or(self.config.threads)?;
+        match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => Some(pool),
+            Err(e) => {
+                warn!("Failed to create the delete thread pool: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 检查每个目标所在卷的可用空间能否查询，同一卷只查询一次
+    ///
+    /// 无法查询时给出警告；启用 `refuse_unknown_free_space` 且未强制时跳过该目标。
+    fn check_free_space(
+        &self,
+        mut projects: Vec<Project>,
+        probe: &dyn FreeSpaceProbe,
+    ) -> Vec<Project> {
+        let refuse = self.config.refuse_unknown_free_space && !self.config.force;
+        let mut queryable_volumes: HashMap<u64, bool> = HashMap::new();
+
+        for project in &mut projects {
+            project.detected_targets.retain(|target| {
+                let queryable = match volume_id(&target.path) {
+                    Some(id) => *queryable_volumes
+                        .entry(id)
+                        .or_insert_with(|| probe.available_space(&target.path).is_some()),
+                    None => probe.available_space(&target.path).is_some(),
+                };
+                if queryable {
+                    return true;
+                }
+
+                warn!(
+                    "Free space unknown for the volume of {}",
+                    target.path.display()
+                );
+                eprintln!(
+                    "Warning: Cannot determine free space for {}{}",
+                    target.path.display(),
+                    if refuse { ", skipping it" } else { "" }
+                );
+                !refuse
+            });
+        }
+
+        projects
     }
 
     /// 清理单个项目
-    fn clean_project(&self, project: &Project, results: &Arc<Mutex<CleanResults>>) -> Result<()> {
+    fn clean_project(
+        &self,
+        project: &Project,
+        results: &Arc<Mutex<CleanResults>>,
+        user_selected: bool,
+    ) -> Result<()> {
         // 更新统计
         {
             let mut r = results.lock().unwrap();
@@ -138,16 +636,14 @@ impl<'a> Cleaner<'a> {
 
         debug!("Cleaning project: {}", project.path.display());
 
+        self.run_hook(HookType::BeforeCleanProject, || {
+            plugins::project_context(project)
+        });
+
         // 处理项目中的每个目标
         for target in &project.detected_targets {
-            // 检查是否应该清理此目标
-            let should_clean = match target.target_type {
-                TargetType::NodeModules => self.config.clean_node_modules,
-                TargetType::BuildDir => self.config.clean_build_dirs,
-                TargetType::CacheDir => self.config.clean_cache_dirs,
-                TargetType::Coverage => self.config.clean_coverage_dirs,
-                TargetType::Custom(_) => true, // Custom targets are always cleaned
-            };
+            // 检查是否应该清理此目标，用户勾选的目标总是清理
+            let should_clean = user_selected || self.config.should_clean(&target.target_type);
 
             // 如果不应该清理，跳过
             if !should_clean {
@@ -158,6 +654,20 @@ impl<'a> Cleaner<'a> {
                 continue;
             }
 
+            // 超过最长运行时间后不再开始新的目标，已开始的删除不受影响
+            if self.runtime_exceeded() {
+                debug!(
+                    "Not starting {}: maximum runtime exceeded",
+                    target.path.display()
+                );
+                results.lock().unwrap().not_started_targets += 1;
+                continue;
+            }
+
+            self.run_hook(HookType::BeforeCleanTarget, || {
+                plugins::target_context(project, target)
+            });
+
             if let Err(e) = self.clean_target(project, target, results) {
                 error!(
                     "Failed to clean {} in {}: {}",
@@ -167,8 +677,16 @@ impl<'a> Cleaner<'a> {
                 );
                 continue;
             }
+
+            self.run_hook(HookType::AfterCleanTarget, || {
+                plugins::target_context(project, target)
+            });
         }
 
+        self.run_hook(HookType::AfterCleanProject, || {
+            plugins::project_context(project)
+        });
+
         // 更新统计
         {
             let mut r = results.lock().unwrap();
@@ -178,6 +696,28 @@ impl<'a> Cleaner<'a> {
         Ok(())
     }
 
+    /// 从启动开始是否已超过 `max_runtime`
+    fn runtime_exceeded(&self) -> bool {
+        self.config
+            .max_runtime
+            .is_some_and(|max_runtime| self.started.elapsed() >= max_runtime)
+    }
+
+    /// 执行插件钩子，只有存在插件时才构建上下文；钩子失败不会中断清理
+    fn run_hook(&self, hook_type: HookType, build_context: impl FnOnce() -> HookContext) {
+        let Some(registry) = self.plugins else {
+            return;
+        };
+        if registry.get_plugins().is_empty() {
+            return;
+        }
+
+        let context = build_context();
+        if let Err(e) = registry.execute_hook(hook_type, &context) {
+            warn!("Plugin hook {:?} failed: {}", hook_type, e);
+        }
+    }
+
     /// 清理单个目标
     fn clean_target(
         &self,
@@ -187,41 +727,157 @@ impl<'a> Cleaner<'a> {
     ) -> Result<()> {
         let target_path = &target.path;
         let target_type_str = format!("{}", target.target_type);
-        let target_size = target.size.unwrap_or(0) / (1024 * 1024); // MB
+        let target_size = format_size(target.size.unwrap_or(0));
 
         debug!(
-            "Cleaning {} ({} MB) in {}",
+            "Cleaning {} ({}) in {}",
             target_type_str,
             target_size,
             project.path.display()
         );
 
+        // 治理策略：只允许操作允许的根目录之下的目标（已不存在的目标交给下面处理）
+        if fs::symlink_metadata(target_path).is_ok() && !self.config.is_path_allowed(target_path) {
+            let e = anyhow!(
+                "Refusing to clean {}: it is outside the allowed roots",
+                target_path.display()
+            );
+            error!("{}", e);
+            let mut r = results.lock().unwrap();
+            r.record_failure(project, target, &e);
+            return Err(e);
+        }
+
         // 清理目录
         if self.config.dry_run {
             // 模拟清理
-            {
+            let large_size = self.size_above_confirm_threshold(target);
+            let mut r = results.lock().unwrap();
+            if self.config.list_paths {
+                r.would_delete
+                    .push(std::path::absolute(target_path).unwrap_or_else(|_| target_path.clone()));
+            }
+            if let Some(size) = large_size {
+                r.would_confirm.push((target_path.clone(), size));
+            }
+            r.record(
+                project,
+                target,
+                OutcomeStatus::Cleaned,
+                target.size.unwrap_or(0),
+            );
+        } else if fs::symlink_metadata(target_path).is_err() {
+            // 扫描之后目标已被其他进程删除
+            if self.config.strict_missing {
+                let e = anyhow!("Target no longer exists: {}", target_path.display());
+                error!("{}", e);
                 let mut r = results.lock().unwrap();
-                r.cleaned_targets += 1;
-                r.total_bytes_removed += target.size.unwrap_or(0);
+                r.record_failure(project, target, &e);
+                return Err(e);
             }
+
+            debug!(
+                "Target {} no longer exists, nothing to clean",
+                target_path.display()
+            );
+            let mut r = results.lock().unwrap();
+            r.record(project, target, OutcomeStatus::Missing, 0);
         } else {
-            // 实际清理
-            match remove_directory(target_path) {
-                Ok(_) => {
+            // 超过阈值的目标即使在 force 模式下也要单独确认
+            if let Some(size) = self.size_above_confirm_threshold(target) {
+                match self.confirm_large_target(target_path, size) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        info!(
+                            "Skipped {} ({}) at user request",
+                            target_path.display(),
+                            format_size(size)
+                        );
+                        eprintln!("Skipped {}", target_path.display());
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        error!("Failed to confirm {}: {}", target_path.display(), e);
+                        let mut r = results.lock().unwrap();
+                        r.record_failure(project, target, &e);
+                        return Err(e);
+                    }
+                }
+            }
+
+            // 删除前必须先写入日志，否则中断后无法可靠恢复
+            if let Some(journal) = self.journal
+                && let Err(e) = journal.record(JournalEvent::Pending, target)
+            {
+                error!(
+                    "Failed to write journal for {}: {}",
+                    target_path.display(),
+                    e
+                );
+                let mut r = results.lock().unwrap();
+                r.record_failure(project, target, &e);
+                return Err(e);
+            }
+
+            // 实际清理；启用边删边统计时以实际删除的字节数为准，设置了恢复清单时只移到暂存位置
+            let size_unknown = self.manifest.is_none()
+                && !self.config.measure_on_delete
+                && !self.config.recompute_size_on_delete
+                && target.size.is_none();
+            let removal = if let Some(manifest) = self.manifest {
+                manifest.stage(target).map(|_| 0)
+            } else if self.config.measure_on_delete {
+                self.remove_target(target_path, true)
+            } else {
+                let size = if self.config.recompute_size_on_delete {
+                    self.recompute_size(target)
+                } else {
+                    target.size.unwrap_or(0)
+                };
+                self.remove_target(target_path, false).map(|_| size)
+            };
+
+            match removal {
+                Ok(freed) => {
+                    if let Some(journal) = self.journal
+                        && let Err(e) = journal.record(JournalEvent::Deleted, target)
+                    {
+                        warn!(
+                            "Failed to write journal for {}: {}",
+                            target_path.display(),
+                            e
+                        );
+                    }
+
                     let mut r = results.lock().unwrap();
-                    r.cleaned_targets += 1;
-                    r.total_bytes_removed += target.size.unwrap_or(0);
+                    if self.manifest.is_some() {
+                        r.record_staged(project, target);
+                    } else {
+                        r.record(project, target, OutcomeStatus::Cleaned, freed);
+                        if size_unknown {
+                            r.unmeasured_targets += 1;
+                        }
+                    }
 
                     debug!(
-                        "Successfully cleaned {} ({} MB)",
+                        "Successfully cleaned {} ({})",
                         target_path.display(),
                         target_size
                     );
                 }
+                // 检查之后、删除之前目标被其他进程删除，与删除前就不存在的目标同样处理
+                Err(e) if is_not_found(&e) && !self.config.strict_missing => {
+                    debug!(
+                        "Target {} disappeared before removal, nothing to clean",
+                        target_path.display()
+                    );
+                    let mut r = results.lock().unwrap();
+                    r.record(project, target, OutcomeStatus::Missing, 0);
+                }
                 Err(e) => {
                     error!("Failed to clean {}: {}", target_path.display(), e);
                     let mut r = results.lock().unwrap();
-                    r.failed_targets += 1;
+                    r.record_failure(project, target, &e);
                     return Err(e);
                 }
             }
@@ -230,11 +886,94 @@ impl<'a> Cleaner<'a> {
         Ok(())
     }
 
+    /// 目标大小超过 `confirm_above` 时返回其大小；扫描时未统计大小的目标在此计算
+    fn size_above_confirm_threshold(&self, target: &CleanTarget) -> Option<u64> {
+        let threshold = self.config.confirm_above?;
+        let size = match target.size {
+            Some(size) => size,
+            None => calculate_directory_size(&target.path).ok()?,
+        };
+        (size > threshold).then_some(size)
+    }
+
+    /// 逐个询问是否删除超过阈值的目标
+    fn confirm_large_target(&self, path: &Path, size: u64) -> Result<bool> {
+        let _prompt = self.prompt_lock.lock().unwrap();
+        match self.confirmer {
+            Some(confirmer) => confirmer.confirm_large_target(path, size),
+            None => StdinConfirmer::new(self.config).confirm_large_target(path, size),
+        }
+    }
+
+    /// 删除目标目录，`counting` 时返回实际删除的字节数，否则返回 0
+    ///
+    /// 设置了超时时在工作线程中删除，超时后放弃等待并返回错误（工作线程仍在后台继续）。
+    fn remove_target(&self, path: &Path, counting: bool) -> Result<u64> {
+        let Some(timeout) = self.config.timeout else {
+            return self.remover.remove(path, counting);
+        };
+
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let remover = Arc::clone(&self.remover);
+        let worker_path = path.to_path_buf();
+        thread::spawn(move || {
+            let _ = sender.send(remover.remove(&worker_path, counting));
+        });
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(anyhow!(
+                "Timed out after {:?} while deleting {}",
+                timeout,
+                path.display()
+            )),
+            Err(RecvTimeoutError::Disconnected) => Err(anyhow!(
+                "Deletion of {} stopped unexpectedly",
+                path.display()
+            )),
+        }
+    }
+
+    /// 删除前重新计算目标大小，与扫描时的估计相差过大时记录日志
+    fn recompute_size(&self, target: &CleanTarget) -> u64 {
+        let current = match calculate_directory_size(&target.path) {
+            Ok(size) => size,
+            Err(e) => {
+                warn!(
+                    "Failed to recompute size of {}: {}",
+                    target.path.display(),
+                    e
+                );
+                return target.size.unwrap_or(0);
+            }
+        };
+
+        if let Some(scanned) = target.size {
+            let drift = current.abs_diff(scanned) as f64 / scanned.max(1) as f64 * 100.0;
+            if drift > self.config.size_drift_threshold {
+                info!(
+                    "Size of {} changed since scan: {} -> {} bytes ({:.1}% drift)",
+                    target.path.display(),
+                    scanned,
+                    current,
+                    drift
+                );
+            }
+        }
+
+        current
+    }
+
     /// 显示清理预览
     fn display_cleaning_preview(&self, projects: &[Project]) -> Result<()> {
+        if self.config.collapse {
+            self.write_collapsed_preview(&mut io::stdout(), projects)?;
+            return Ok(());
+        }
         println!("\n{}", style("Projects to clean:").bold().underlined());
 
         let mut total_size = 0;
+        let mut unknown_size_targets = 0;
         let mut found_targets = false;
 
         for project in projects {
@@ -245,27 +984,25 @@ impl<'a> Cleaner<'a> {
             found_targets = true;
             println!(
                 "\n• Project: {} [{}]",
-                style(project.path.display().to_string()).green().bold(),
-                style(format!("{:?}", project.project_type)).yellow()
+                style(self.config.display_path(&project.path))
+                    .green()
+                    .bold(),
+                style(project.project_type.to_string()).yellow()
             );
 
             for target in &project.detected_targets {
                 // 检查是否应该清理此目标
-                let should_clean = match target.target_type {
-                    TargetType::NodeModules => self.config.clean_node_modules,
-                    TargetType::BuildDir => self.config.clean_build_dirs,
-                    TargetType::CacheDir => self.config.clean_cache_dirs,
-                    TargetType::Coverage => self.config.clean_coverage_dirs,
-                    TargetType::Custom(_) => true, // Custom targets are always cleaned
-                };
+                let should_clean = self.config.should_clean(&target.target_type);
 
                 let size_str = if let Some(size) = target.size {
-                    let size_mb = size / (1024 * 1024);
                     if should_clean {
                         total_size += size;
                     }
-                    format!(" ({} MB)", size_mb)
+                    format!(" ({})", format_size(size))
                 } else {
+                    if should_clean {
+                        unknown_size_targets += 1;
+                    }
                     " (size unknown)".to_string()
                 };
 
@@ -282,7 +1019,7 @@ impl<'a> Cleaner<'a> {
                 println!(
                     "  - {} {} {}{}",
                     clean_status,
-                    target.path.display(),
+                    self.config.display_path(&target.path),
                     style(format!("[{}]", target.target_type)).yellow(),
                     style(size_str).cyan()
                 );
@@ -293,27 +1030,100 @@ impl<'a> Cleaner<'a> {
             println!("{}", style("No cleanable targets found!").yellow());
         }
 
+        // 部分目标大小未知（如 --no-node-modules-size）时注明总量不含这些目标
+        let unknown_note = if unknown_size_targets > 0 && total_size > 0 {
+            format!(
+                " (excluding {} target{} of unknown size)",
+                unknown_size_targets,
+                if unknown_size_targets == 1 { "" } else { "s" }
+            )
+        } else {
+            String::new()
+        };
         println!(
-            "\nTotal estimated space to free: {} MB\n",
-            style(format!("{}", total_size / (1024 * 1024)))
-                .green()
-                .bold()
+            "\nTotal estimated space to free: {}{}\n",
+            style(format_size(total_size)).green().bold(),
+            unknown_note
         );
 
         Ok(())
     }
 
-    /// 请求用户确认清理
-    fn confirm_cleaning(&self) -> Result<bool> {
-        println!(
-            "{}",
-            style("Do you want to proceed with cleaning? [y/N]:").bold()
-        );
+    /// 按项目类型分组显示清理预览，verbose 时展开每组中的项目
+    fn write_collapsed_preview(
+        &self,
+        out: &mut impl Write,
+        projects: &[Project],
+    ) -> io::Result<()> {
+        // 每组：项目数、目标数、已知大小，以及展开时使用的项目明细
+        let mut groups: BTreeMap<String, (usize, usize, u64, Vec<&Project>)> = BTreeMap::new();
+        let mut total_size = 0;
+
+        for project in projects.iter().filter(|p| !p.detected_targets.is_empty()) {
+            let targets: Vec<_> = project
+                .detected_targets
+                .iter()
+                .filter(|t| self.config.should_clean(&t.target_type))
+                .collect();
+            let size: u64 = targets.iter().filter_map(|t| t.size).sum();
+            total_size += size;
+
+            let group = groups.entry(project.project_type.to_string()).or_default();
+            group.0 += 1;
+            group.1 += targets.len();
+            group.2 += size;
+            group.3.push(project);
+        }
+
+        writeln!(out, "\n{}", style("Projects to clean:").bold().underlined())?;
+        if groups.is_empty() {
+            writeln!(out, "{}", style("No cleanable targets found!").yellow())?;
+        }
+
+        for (project_type, (project_count, target_count, size, members)) in &groups {
+            writeln!(
+                out,
+                "• {}: {} project{}, {} target{}, {}",
+                style(project_type).yellow().bold(),
+                project_count,
+                if *project_count == 1 { "" } else { "s" },
+                target_count,
+                if *target_count == 1 { "" } else { "s" },
+                style(format_size(*size)).cyan()
+            )?;
+
+            if self.config.verbosity >= Verbosity::Verbose {
+                for project in members {
+                    let size: u64 = project
+                        .detected_targets
+                        .iter()
+                        .filter(|t| self.config.should_clean(&t.target_type))
+                        .filter_map(|t| t.size)
+                        .sum();
+                    writeln!(
+                        out,
+                        "    - {} ({})",
+                        self.config.display_path(&project.path),
+                        format_size(size)
+                    )?;
+                }
+            }
+        }
 
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
+        writeln!(
+            out,
+            "\nTotal estimated space to free: {}\n",
+            style(format_size(total_size)).green().bold()
+        )
+    }
 
-        Ok(input.trim().to_lowercase() == "y")
+    /// 请求确认清理
+    fn confirm_cleaning(&self, projects: &[Project]) -> Result<bool> {
+        let summary = CleaningSummary::new(projects, self.config);
+        match self.confirmer {
+            Some(confirmer) => confirmer.confirm(&summary),
+            None => StdinConfirmer::new(self.config).confirm(&summary),
+        }
     }
 
     /// 创建进度条
@@ -331,3 +1141,798 @@ impl<'a> Cleaner<'a> {
         pb
     }
 }
+
+/// 区分卷的键：能查询卷标识时用卷标识，否则退回到路径的根（如 Windows 的盘符）
+#[derive(PartialEq, Eq, Hash)]
+enum VolumeKey {
+    Id(u64),
+    Root(PathBuf),
+}
+
+/// 测量可用空间时查询的路径：每个卷取一个有目标的项目目录（项目目录在清理后仍然存在）
+///
+/// `volume` 查询卷标识，查询不到时按路径的根去重，避免同一个卷的可用空间被重复累加。
+fn free_space_paths(projects: &[Project], volume: impl Fn(&Path) -> Option<u64>) -> Vec<PathBuf> {
+    let mut volumes = HashSet::new();
+    projects
+        .iter()
+        .filter(|project| !project.detected_targets.is_empty())
+        .filter(|project| {
+            let key = match volume(&project.path) {
+                Some(id) => VolumeKey::Id(id),
+                None => {
+                    let path =
+                        std::path::absolute(&project.path).unwrap_or_else(|_| project.path.clone());
+                    VolumeKey::Root(path.ancestors().last().unwrap_or(&path).to_path_buf())
+                }
+            };
+            volumes.insert(key)
+        })
+        .map(|project| project.path.clone())
+        .collect()
+}
+
+/// 各路径所在卷的可用空间合计，任一路径无法查询时返回 None
+fn total_free_space(paths: &[PathBuf], probe: &dyn FreeSpaceProbe) -> Option<u64> {
+    paths.iter().map(|path| probe.available_space(path)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confirm::{AlwaysNo, AlwaysYes};
+    use crate::project::ProjectType;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 模拟无法查询可用空间的卷
+    struct UnknownFreeSpace;
+
+    impl FreeSpaceProbe for UnknownFreeSpace {
+        fn available_space(&self, _path: &Path) -> Option<u64> {
+            None
+        }
+    }
+
+    fn project_with_build_dir(root: &Path) -> Project {
+        let build_dir = root.join("dist");
+        fs::create_dir_all(&build_dir).unwrap();
+        fs::write(build_dir.join("bundle.js"), "console.log(1)").unwrap();
+
+        let mut project = Project::new(root.to_path_buf());
+        project.detected_targets.push(CleanTarget {
+            path: build_dir,
+            target_type: TargetType::BuildDir,
+            size: Some(14),
+        });
+        project
+    }
+
+    #[test]
+    fn test_missing_target_is_not_a_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project_with_build_dir(dir.path());
+        // 模拟扫描后目标被其他进程删除
+        fs::remove_dir_all(dir.path().join("dist")).unwrap();
+
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+
+        assert_eq!(results.failed_targets, 0);
+        assert_eq!(results.cleaned_targets, 1);
+        assert_eq!(results.total_bytes_removed, 0);
+    }
+
+    #[test]
+    fn test_measure_on_delete_counts_freed_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = project_with_build_dir(dir.path());
+        // 扫描时未统计大小
+        project.detected_targets[0].size = None;
+
+        let config = Config {
+            force: true,
+            measure_on_delete: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+
+        assert_eq!(results.cleaned_targets, 1);
+        assert_eq!(results.total_bytes_removed, 14);
+        assert_eq!(results.unmeasured_targets, 0);
+        assert!(!dir.path().join("dist").exists());
+
+        // 不边删边统计时大小未知，不能当作释放了 0 字节
+        let mut project = project_with_build_dir(dir.path());
+        project.detected_targets[0].size = None;
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+        assert_eq!(results.cleaned_targets, 1);
+        assert_eq!(results.unmeasured_targets, 1);
+    }
+
+    #[test]
+    fn test_recompute_size_on_delete_uses_current_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project_with_build_dir(dir.path());
+        // 扫描之后又有构建写入了新文件
+        fs::write(dir.path().join("dist/chunk.js"), vec![0u8; 1000]).unwrap();
+
+        let config = Config {
+            force: true,
+            recompute_size_on_delete: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+
+        assert_eq!(results.total_bytes_removed, 1014);
+    }
+
+    #[test]
+    fn test_missing_target_fails_in_strict_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project_with_build_dir(dir.path());
+        fs::remove_dir_all(dir.path().join("dist")).unwrap();
+
+        let config = Config {
+            force: true,
+            strict_missing: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+
+        assert_eq!(results.failed_targets, 1);
+        assert_eq!(results.cleaned_targets, 0);
+    }
+
+    #[test]
+    fn test_unknown_free_space_refuses_target_unless_forced() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project_with_build_dir(dir.path());
+
+        let config = Config {
+            two_phase_delete: true,
+            refuse_unknown_free_space: true,
+            ..Config::default()
+        };
+        let projects =
+            Cleaner::new(&config).check_free_space(vec![project.clone()], &UnknownFreeSpace);
+        assert!(projects[0].detected_targets.is_empty());
+
+        let config = Config {
+            force: true,
+            ..config
+        };
+        let projects = Cleaner::new(&config).check_free_space(vec![project], &UnknownFreeSpace);
+        assert_eq!(projects[0].detected_targets.len(), 1);
+    }
+
+    #[test]
+    fn test_free_space_paths_dedupe_by_root_without_volume_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let projects: Vec<Project> = ["a", "b", "c"]
+            .iter()
+            .map(|name| project_with_build_dir(&dir.path().join(name)))
+            .collect();
+
+        // 查询不到卷标识时同一个根下的项目只查询一次
+        assert_eq!(
+            free_space_paths(&projects, |_| None),
+            vec![dir.path().join("a")]
+        );
+        assert_eq!(free_space_paths(&projects, |_| Some(1)).len(), 1);
+        let distinct = |path: &Path| Some(u64::from(path.ends_with("b")));
+        assert_eq!(free_space_paths(&projects, distinct).len(), 2);
+    }
+
+    #[test]
+    fn test_two_phase_delete_removes_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project_with_build_dir(dir.path());
+
+        let config = Config {
+            force: true,
+            two_phase_delete: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+
+        assert_eq!(results.cleaned_targets, 1);
+        assert_eq!(results.total_bytes_removed, 14);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_node_modules_target_survives_clean() {
+        let outside = tempfile::tempdir().unwrap();
+        fs::create_dir_all(outside.path().join("shared/pkg")).unwrap();
+        fs::write(outside.path().join("shared/pkg/index.js"), "keep me").unwrap();
+
+        for (measure_on_delete, two_phase_delete) in [(false, false), (true, false), (true, true)] {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join("package.json"), "{}").unwrap();
+            std::os::unix::fs::symlink(
+                outside.path().join("shared"),
+                dir.path().join("node_modules"),
+            )
+            .unwrap();
+
+            let config = Config {
+                force: true,
+                stats: true,
+                measure_on_delete,
+                two_phase_delete,
+                ..Config::default()
+            };
+            let projects = crate::scanner::Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects;
+            // 链接被列为目标，但大小不包含链接指向的内容
+            let target = &projects[0].detected_targets[0];
+            assert!(target.path.ends_with("node_modules"));
+            assert_eq!(target.size, Some(0));
+
+            let results = Cleaner::new(&config).clean(projects).unwrap();
+            assert_eq!(results.cleaned_targets, 1);
+            assert!(fs::symlink_metadata(dir.path().join("node_modules")).is_err());
+            assert_eq!(
+                fs::read_to_string(outside.path().join("shared/pkg/index.js")).unwrap(),
+                "keep me"
+            );
+        }
+    }
+
+    #[test]
+    fn test_collapsed_preview_groups_by_project_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut projects = Vec::new();
+        for (name, project_type) in [
+            ("a", ProjectType::React),
+            ("b", ProjectType::React),
+            ("c", ProjectType::React),
+            ("d", ProjectType::Vue),
+        ] {
+            let root = dir.path().join(name);
+            let mut project = project_with_build_dir(&root);
+            project.project_type = project_type;
+            projects.push(project);
+        }
+
+        let config = Config {
+            collapse: true,
+            ..Config::default()
+        };
+        let mut out = Vec::new();
+        Cleaner::new(&config)
+            .write_collapsed_preview(&mut out, &projects)
+            .unwrap();
+        let output = console::strip_ansi_codes(&String::from_utf8(out).unwrap()).to_string();
+
+        let groups: Vec<_> = output.lines().filter(|l| l.starts_with("• ")).collect();
+        assert_eq!(
+            groups,
+            vec![
+                "• React: 3 projects, 3 targets, 42 B",
+                "• Vue: 1 project, 1 target, 14 B"
+            ]
+        );
+        // 未展开时不列出单个项目
+        assert!(!output.contains(&dir.path().display().to_string()));
+    }
+
+    #[test]
+    fn test_allowed_roots_refuse_targets_outside() {
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let inside_project = project_with_build_dir(allowed.path());
+        let outside_project = project_with_build_dir(outside.path());
+
+        let config = Config {
+            force: true,
+            allowed_roots: vec![allowed.path().to_path_buf()],
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config)
+            .clean(vec![inside_project, outside_project])
+            .unwrap();
+
+        assert_eq!(results.cleaned_targets, 1);
+        assert_eq!(results.failed_targets, 1);
+        assert!(!allowed.path().join("dist").exists());
+        assert!(outside.path().join("dist").exists());
+    }
+
+    #[test]
+    fn test_injected_confirmer_decides_without_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = || {
+            let mut project = Project::new(dir.path().to_path_buf());
+            let path = dir.path().join("dist");
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("app.js"), "x").unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type: TargetType::BuildDir,
+                size: Some(1),
+            });
+            project
+        };
+        // 未设置 force，确认完全由注入的确认器决定
+        let config = Config::default();
+
+        let results = Cleaner::new(&config)
+            .with_confirmer(&AlwaysNo)
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(results.cleaned_targets, 0);
+        assert!(results.cancelled);
+        assert!(dir.path().join("dist").exists());
+
+        let results = Cleaner::new(&config)
+            .with_confirmer(&AlwaysYes)
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(results.cleaned_targets, 1);
+        assert!(!results.cancelled);
+        assert!(!dir.path().join("dist").exists());
+
+        // 回调函数收到的汇总只包含将要清理的目标
+        let seen = std::sync::Mutex::new(None);
+        let callback = |summary: &CleaningSummary| {
+            *seen.lock().unwrap() = Some(summary.clone());
+            false
+        };
+        Cleaner::new(&config)
+            .with_confirmer(&callback)
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(
+            seen.into_inner().unwrap(),
+            Some(CleaningSummary {
+                projects: 1,
+                targets: 1,
+                estimated_bytes: 1,
+            })
+        );
+    }
+
+    /// 记录被询问的大目标，并按预设回答
+    struct RecordingConfirmer {
+        answer: bool,
+        asked: Mutex<Vec<PathBuf>>,
+    }
+
+    impl Confirmer for RecordingConfirmer {
+        fn confirm(&self, _summary: &CleaningSummary) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn confirm_large_target(&self, path: &Path, _size: u64) -> Result<bool> {
+            self.asked.lock().unwrap().push(path.to_path_buf());
+            Ok(self.answer)
+        }
+    }
+
+    #[test]
+    fn test_confirm_above_asks_for_large_targets_even_with_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = || {
+            let mut project = Project::new(dir.path().to_path_buf());
+            for (name, size) in [("dist", 14), ("huge", 4096)] {
+                let path = dir.path().join(name);
+                fs::create_dir_all(&path).unwrap();
+                fs::write(path.join("data"), vec![0u8; size]).unwrap();
+                project.detected_targets.push(CleanTarget {
+                    path,
+                    target_type: TargetType::BuildDir,
+                    size: Some(size as u64),
+                });
+            }
+            project
+        };
+        let config = Config {
+            force: true,
+            confirm_above: Some(1024),
+            ..Config::default()
+        };
+
+        let declining = RecordingConfirmer {
+            answer: false,
+            asked: Mutex::new(Vec::new()),
+        };
+        let results = Cleaner::new(&config)
+            .with_confirmer(&declining)
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(*declining.asked.lock().unwrap(), [dir.path().join("huge")]);
+        assert_eq!(results.cleaned_targets, 1);
+        assert!(dir.path().join("huge").exists());
+        assert!(!dir.path().join("dist").exists());
+
+        // dry run 只记录会触发确认的目标，不询问也不删除
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        let results = Cleaner::new(&dry_run)
+            .with_confirmer(&declining)
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(results.would_confirm, [(dir.path().join("huge"), 4096)]);
+        assert_eq!(declining.asked.lock().unwrap().len(), 1);
+
+        let accepting = RecordingConfirmer {
+            answer: true,
+            asked: Mutex::new(Vec::new()),
+        };
+        let results = Cleaner::new(&config)
+            .with_confirmer(&accepting)
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(results.cleaned_targets, 2);
+        assert!(!dir.path().join("huge").exists());
+    }
+
+    #[test]
+    fn test_targets_vanished_before_confirmation_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = project_with_build_dir(dir.path());
+        let cache = dir.path().join(".cache");
+        fs::create_dir_all(&cache).unwrap();
+        project.detected_targets.push(CleanTarget {
+            path: cache.clone(),
+            target_type: TargetType::CacheDir,
+            size: Some(0),
+        });
+
+        // 用户确认期间另一个进程删除了 .cache
+        let confirm_later = |_summary: &CleaningSummary| {
+            fs::remove_dir_all(&cache).unwrap();
+            true
+        };
+        let config = Config::default();
+        let results = Cleaner::new(&config)
+            .with_confirmer(&confirm_later)
+            .clean(vec![project])
+            .unwrap();
+
+        assert_eq!(results.vanished_since_preview, vec![cache.clone()]);
+        assert_eq!(results.total_targets, 1);
+        assert!(!dir.path().join("dist").exists());
+
+        let mut out = Vec::new();
+        write_changes_since_preview(&mut out, &results.vanished_since_preview, &[], false).unwrap();
+        let note = console::strip_ansi_codes(&String::from_utf8(out).unwrap()).to_string();
+        assert!(note.contains("Changes since preview: 1 targets no longer exist"));
+        assert!(note.contains(&cache.display().to_string()));
+    }
+
+    #[test]
+    fn test_strict_missing_reports_vanished_targets_as_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project_with_build_dir(dir.path());
+        let dist = dir.path().join("dist");
+
+        let confirm_later = |_summary: &CleaningSummary| {
+            fs::remove_dir_all(&dist).unwrap();
+            true
+        };
+        let config = Config {
+            strict_missing: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config)
+            .with_confirmer(&confirm_later)
+            .clean(vec![project])
+            .unwrap();
+
+        assert_eq!(results.vanished_since_preview, vec![dist.clone()]);
+        assert_eq!(results.failed_targets, 1);
+        assert_eq!(results.cleaned_targets, 0);
+    }
+
+    #[test]
+    fn test_targets_appeared_before_confirmation_need_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join(".cache");
+        let rescan = |path: &Path| {
+            let mut project = project_with_build_dir(path);
+            project.detected_targets.push(CleanTarget {
+                path: path.join(".cache"),
+                target_type: TargetType::CacheDir,
+                size: Some(0),
+            });
+            Some(project)
+        };
+
+        for accept_new in [false, true] {
+            let project = project_with_build_dir(dir.path());
+            // 用户确认期间生成了 .cache
+            let prompts = AtomicUsize::new(0);
+            let confirm = |summary: &CleaningSummary| {
+                if prompts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    fs::create_dir_all(&cache).unwrap();
+                    true
+                } else {
+                    assert_eq!(summary.targets, 1);
+                    accept_new
+                }
+            };
+            let config = Config {
+                clean_cache_dirs: true,
+                ..Config::default()
+            };
+            let results = Cleaner::new(&config)
+                .with_confirmer(&confirm)
+                .with_rescan(&rescan)
+                .clean(vec![project])
+                .unwrap();
+
+            assert_eq!(prompts.load(Ordering::SeqCst), 2);
+            assert_eq!(results.appeared_since_preview, vec![cache.clone()]);
+            assert!(!dir.path().join("dist").exists());
+            assert_eq!(cache.exists(), !accept_new);
+        }
+
+        let mut out = Vec::new();
+        write_changes_since_preview(&mut out, &[], std::slice::from_ref(&cache), false).unwrap();
+        let note = console::strip_ansi_codes(&String::from_utf8(out).unwrap()).to_string();
+        assert!(note.contains("1 new targets appeared and need confirmation"));
+    }
+
+    #[test]
+    fn test_interactive_selection_limits_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        for (name, target_type) in [
+            ("dist", TargetType::BuildDir),
+            ("node_modules", TargetType::NodeModules),
+            ("coverage", TargetType::Coverage),
+        ] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type,
+                size: Some(1),
+            });
+        }
+        let mut projects = vec![project];
+
+        // 覆盖率目录默认不清理，但用户勾选后仍会被删除
+        let config = Config {
+            clean_coverage_dirs: false,
+            interactive: true,
+            ..Config::default()
+        };
+        let selected: HashSet<_> = [dir.path().join("dist"), dir.path().join("coverage")]
+            .into_iter()
+            .collect();
+        crate::confirm::apply_selection(&mut projects, &selected);
+        let results = Cleaner::new(&config).clean_projects(projects, true);
+
+        assert_eq!(results.cleaned_targets, 2);
+        assert!(!dir.path().join("dist").exists());
+        assert!(!dir.path().join("coverage").exists());
+        assert!(dir.path().join("node_modules").exists());
+    }
+
+    /// 删除指定名称的目录时卡住，模拟挂起的网络挂载
+    struct HangingRemover {
+        hanging_name: &'static str,
+    }
+
+    impl DirectoryRemover for HangingRemover {
+        fn remove(&self, path: &Path, counting: bool) -> Result<u64> {
+            if path.ends_with(self.hanging_name) {
+                std::thread::sleep(std::time::Duration::from_secs(5));
+            }
+            FsRemover {
+                two_phase: false,
+                trash_suffix: String::new(),
+            }
+            .remove(path, counting)
+        }
+    }
+
+    #[test]
+    fn test_removal_timeout_marks_target_failed_and_continues() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        for name in ["mnt-cache", "dist"] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type: TargetType::BuildDir,
+                size: Some(1),
+            });
+        }
+
+        let config = Config {
+            force: true,
+            timeout: Some(std::time::Duration::from_millis(100)),
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config)
+            .with_remover(Arc::new(HangingRemover {
+                hanging_name: "mnt-cache",
+            }))
+            .clean(vec![project])
+            .unwrap();
+
+        assert_eq!(results.failed_targets, 1);
+        assert_eq!(results.cleaned_targets, 1);
+        let failure = results.failures().next().unwrap();
+        assert!(failure.target_path.ends_with("mnt-cache"));
+        assert!(failure.error.as_deref().unwrap().contains("Timed out"));
+        assert!(!dir.path().join("dist").exists());
+    }
+
+    /// 模拟检查之后被其他进程删除的目标：删除时报告文件不存在
+    struct VanishingRemover;
+
+    impl DirectoryRemover for VanishingRemover {
+        fn remove(&self, path: &Path, _counting: bool) -> Result<u64> {
+            Err(anyhow::Error::new(io::Error::from(io::ErrorKind::NotFound))
+                .context(format!("Failed to remove directory: {}", path.display())))
+        }
+    }
+
+    #[test]
+    fn test_not_found_during_removal_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = || {
+            let mut project = Project::new(dir.path().to_path_buf());
+            let path = dir.path().join("dist");
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type: TargetType::BuildDir,
+                size: Some(1),
+            });
+            project
+        };
+
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config)
+            .with_remover(Arc::new(VanishingRemover))
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(results.failed_targets, 0);
+        assert_eq!(results.outcomes.len(), 1);
+        assert_eq!(results.outcomes[0].status, OutcomeStatus::Missing);
+
+        // `strict_missing` 时仍记为失败
+        let config = Config {
+            force: true,
+            strict_missing: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config)
+            .with_remover(Arc::new(VanishingRemover))
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(results.failed_targets, 1);
+    }
+
+    /// 每次删除都需要一定时间
+    struct SlowRemover;
+
+    impl DirectoryRemover for SlowRemover {
+        fn remove(&self, path: &Path, counting: bool) -> Result<u64> {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            FsRemover {
+                two_phase: false,
+                trash_suffix: String::new(),
+            }
+            .remove(path, counting)
+        }
+    }
+
+    /// 记录删除顺序和同时进行的删除数量的峰值
+    #[derive(Default)]
+    struct InstrumentedRemover {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+        order: Mutex<Vec<PathBuf>>,
+    }
+
+    impl DirectoryRemover for InstrumentedRemover {
+        fn remove(&self, path: &Path, counting: bool) -> Result<u64> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            self.order.lock().unwrap().push(path.to_path_buf());
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            let removed = FsRemover {
+                two_phase: false,
+                trash_suffix: String::new(),
+            }
+            .remove(path, counting);
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            removed
+        }
+    }
+
+    #[test]
+    fn test_single_delete_thread_cleans_serially() {
+        let dir = tempfile::tempdir().unwrap();
+        let projects: Vec<Project> = (0..8)
+            .map(|i| {
+                let path = dir.path().join(format!("app-{}", i));
+                fs::create_dir_all(path.join("dist")).unwrap();
+                let mut project = Project::new(path.clone());
+                project.detected_targets.push(CleanTarget {
+                    path: path.join("dist"),
+                    target_type: TargetType::BuildDir,
+                    size: Some(1),
+                });
+                project
+            })
+            .collect();
+        let expected_order: Vec<PathBuf> = projects
+            .iter()
+            .map(|project| project.detected_targets[0].path.clone())
+            .collect();
+
+        let config = Config {
+            force: true,
+            delete_threads: Some(1),
+            ..Config::default()
+        };
+        let remover = Arc::new(InstrumentedRemover::default());
+        let results = Cleaner::new(&config)
+            .with_remover(remover.clone())
+            .clean(projects)
+            .unwrap();
+
+        assert_eq!(results.cleaned_targets, 8);
+        assert_eq!(remover.max_active.load(Ordering::SeqCst), 1);
+        assert_eq!(*remover.order.lock().unwrap(), expected_order);
+    }
+
+    #[test]
+    fn test_max_runtime_stops_starting_new_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        for i in 0..20 {
+            let path = dir.path().join(format!("out-{}", i));
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type: TargetType::BuildDir,
+                size: Some(1),
+            });
+        }
+
+        let config = Config {
+            force: true,
+            max_runtime: Some(std::time::Duration::from_millis(50)),
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config)
+            .with_remover(Arc::new(SlowRemover))
+            .clean(vec![project])
+            .unwrap();
+
+        assert!(results.cleaned_targets >= 1);
+        assert!(results.cleaned_targets < 20);
+        assert_eq!(results.cleaned_targets + results.not_started_targets, 20);
+        assert_eq!(results.failed_targets, 0);
+
+        let remaining = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(remaining, results.not_started_targets);
+    }
+}
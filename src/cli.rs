@@ -1,9 +1,22 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{ArgAction, Parser, Subcommand};
+use console::style;
+use serde::Serialize;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use walkdir::WalkDir;
 
-use crate::cleaner::CleanResults;
-use crate::config::Config;
-use crate::project::Project;
+use crate::cleaner::{CleanResults, OutcomeStatus};
+use crate::config::{ColorMode, Config, ConfirmDefault, OutputFormat, Verbosity};
+use crate::lifetime::LifetimeRun;
+use crate::manifest::{PurgeSummary, RestoreSummary};
+use crate::preflight::{CheckStatus, PreflightReport};
+use crate::project::{CleanTarget, Project, ProjectType, TargetType};
+use crate::report::MergedReport;
+use crate::scanner::ScanStats;
+use crate::update::UpdateStatus;
+use crate::utils::fs_utils::format_size;
+use crate::utils::parse::{parse_duration, parse_size};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -12,128 +25,1752 @@ use crate::project::Project;
     version
 )]
 pub struct CliArgs {
-    /// Path to project or directory, defaults to current directory
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Paths to projects or directories, defaults to current directory
     #[arg(default_value = ".")]
-    pub path: PathBuf,
+    pub paths: Vec<PathBuf>,
 
     /// Recursively find and clean projects in subdirectories
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub recursive: bool,
 
     /// Skip confirmation prompts
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub force: bool,
 
+    /// Choose which targets to delete from a checklist before cleaning
+    #[arg(short, long, global = true)]
+    pub interactive: bool,
+
     /// Show what would be deleted without deleting
-    #[arg(short = 'd', long = "dry-run")]
+    #[arg(short = 'd', long = "dry-run", global = true)]
     pub dry_run: bool,
 
+    /// With --dry-run, print the absolute paths that would be deleted, one per line
+    #[arg(
+        long,
+        visible_alias = "print-paths",
+        requires = "dry_run",
+        global = true
+    )]
+    pub list: bool,
+
+    /// Only scan and report projects with their sizes in the chosen --format, never clean
+    #[arg(long = "scan-only", global = true)]
+    pub scan_only: bool,
+
+    /// List the largest packages directly inside PROJECT's node_modules, never clean
+    #[arg(long, value_name = "PROJECT", global = true)]
+    pub drill: Option<PathBuf>,
+
+    /// Number of packages listed by --drill
+    #[arg(
+        long = "drill-top",
+        value_name = "N",
+        default_value_t = 10,
+        requires = "drill",
+        global = true
+    )]
+    pub drill_top: usize,
+
     /// Use specific config file
-    #[arg(short, long, value_name = "FILE")]
+    #[arg(short, long, value_name = "FILE", global = true)]
     pub config: Option<PathBuf>,
 
+    /// Match --include directories case-insensitively (--exclude always is)
+    #[arg(long = "ignore-case", global = true)]
+    pub ignore_case: bool,
+
+    /// Also scan directories excluded by .gitignore, .npmignore and global git excludes
+    #[arg(long = "no-gitignore", global = true)]
+    pub no_gitignore: bool,
+
+    /// Only process projects of these types (e.g. next,react), comma separated or repeated
+    #[arg(
+        long = "type",
+        visible_alias = "only-type",
+        value_name = "TYPE",
+        value_delimiter = ',',
+        global = true
+    )]
+    pub project_types: Vec<ProjectType>,
+
+    /// Skip projects of these types (e.g. angular,nextjs), comma separated or repeated
+    #[arg(
+        long = "exclude-type",
+        value_name = "TYPE",
+        value_delimiter = ',',
+        global = true
+    )]
+    pub exclude_types: Vec<ProjectType>,
+
+    /// Only process projects whose path matches this regular expression
+    #[arg(long = "match-regex", value_name = "PATTERN", global = true)]
+    pub match_regex: Option<String>,
+
+    /// Only clean projects whose source files have not changed for this long (e.g. 30d)
+    #[arg(long = "older-than", value_name = "DURATION", value_parser = parse_duration, global = true)]
+    pub older_than: Option<Duration>,
+
+    /// Only clean build/cache directories created within this window (e.g. 1h, 2d)
+    #[arg(long = "created-within", value_name = "DURATION", value_parser = parse_duration, global = true)]
+    pub created_within: Option<Duration>,
+
+    /// Read package locations from a `pnpm list --json` or `lerna ls --json` file instead of scanning
+    #[arg(long = "packages-manifest", value_name = "FILE", global = true)]
+    pub packages_manifest: Option<PathBuf>,
+
     /// Clean only node_modules directories
-    #[arg(short = 'n', long = "node-modules")]
+    #[arg(short = 'n', long = "node-modules", global = true)]
     pub node_modules_only: bool,
 
     /// Clean only build directories
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub build: bool,
 
-    /// Additional directories to clean (comma-separated)
-    #[arg(long, value_name = "DIRS")]
+    /// Treat cache and coverage directories as build output, so --build also covers them
+    #[arg(long = "unified-targets", global = true)]
+    pub unified_targets: bool,
+
+    /// Keep the scan root project's own node_modules, still cleaning nested projects
+    #[arg(long = "keep-root-node-modules", global = true)]
+    pub keep_root_node_modules: bool,
+
+    /// Clean caches inside node_modules instead of removing node_modules itself
+    #[arg(long = "prune-node-modules-caches", global = true)]
+    pub prune_node_modules_caches: bool,
+
+    /// Also clean pnpm's virtual store (node_modules/.pnpm) as its own target when node_modules is kept
+    #[arg(long = "pnpm-store", global = true)]
+    pub clean_pnpm_store: bool,
+
+    /// Also clean `.cache` directories nested anywhere in the source tree (outside node_modules)
+    #[arg(long = "deep-cache", global = true)]
+    pub deep_cache: bool,
+
+    /// Report projects whose node_modules contains dangling symlinks (a corrupted install)
+    #[arg(long = "check-broken-links", global = true)]
+    pub check_broken_links: bool,
+
+    /// Estimate how much projects with identical lockfiles would save with a shared store (needs --stats)
+    #[arg(long = "dedupe-by-lockfile", global = true)]
+    pub dedupe_by_lockfile: bool,
+
+    /// Only clean the single largest target of each project (sizes are always computed)
+    #[arg(long = "largest-only", global = true)]
+    pub largest_only: bool,
+
+    /// Skip targets smaller than this size (e.g. 100MB, 1.5GiB)
+    #[arg(long = "min-size", value_name = "SIZE", value_parser = parse_size, global = true)]
+    pub min_size: Option<u64>,
+
+    /// Skip node_modules smaller than this size (e.g. 100MB)
+    #[arg(long = "min-size-node-modules", value_name = "SIZE", value_parser = parse_size, global = true)]
+    pub min_size_node_modules: Option<u64>,
+
+    /// Maximum directory depth to search for projects (0 checks only the given path)
+    #[arg(long = "max-depth", value_name = "N", global = true)]
+    pub max_depth: Option<usize>,
+
+    /// Skip node_modules with fewer top-level entries than this
+    #[arg(long = "node-modules-min-entries", value_name = "COUNT", global = true)]
+    pub node_modules_min_entries: Option<usize>,
+
+    /// Skip build directories smaller than this size
+    #[arg(long = "min-size-build", value_name = "SIZE", value_parser = parse_size, global = true)]
+    pub min_size_build: Option<u64>,
+
+    /// Skip cache directories smaller than this size
+    #[arg(long = "min-size-cache", value_name = "SIZE", value_parser = parse_size, global = true)]
+    pub min_size_cache: Option<u64>,
+
+    /// Skip coverage directories smaller than this size
+    #[arg(long = "min-size-coverage", value_name = "SIZE", value_parser = parse_size, global = true)]
+    pub min_size_coverage: Option<u64>,
+
+    /// Ask before deleting any single target larger than this size, even with --force
+    #[arg(long = "confirm-above", value_name = "SIZE", value_parser = parse_size, global = true)]
+    pub confirm_above: Option<u64>,
+
+    /// Additional directories to clean (comma-separated, globs like packages/*/dist allowed)
+    #[arg(long, value_name = "DIRS", global = true)]
     pub include: Option<String>,
 
-    /// Directories to exclude (comma-separated)
-    #[arg(long, value_name = "DIRS")]
+    /// Directories to exclude (comma-separated names, globs, or paths relative to the scan root)
+    #[arg(long, value_name = "DIRS", global = true)]
     pub exclude: Option<String>,
 
+    /// Count freed bytes while deleting instead of computing sizes up front
+    #[arg(long = "measure-on-delete", global = true)]
+    pub measure_on_delete: bool,
+
+    /// Recompute each target's size right before deleting it
+    #[arg(long = "force-size-recompute-on-delete", global = true)]
+    pub recompute_size_on_delete: bool,
+
+    /// Log when a recomputed size differs from the scan by more than this percentage
+    #[arg(long = "size-drift-threshold", value_name = "PERCENT", global = true)]
+    pub size_drift_threshold: Option<f64>,
+
+    /// Treat targets that disappeared before deletion as failures
+    #[arg(long = "strict-missing", global = true)]
+    pub strict_missing: bool,
+
+    /// Read directories to clean from stdin, one per line, skipping detection
+    #[arg(long, global = true)]
+    pub stdin: bool,
+
+    /// Append a record to this journal before and after every deletion
+    #[arg(long = "journal", value_name = "FILE", global = true)]
+    pub journal: Option<PathBuf>,
+
+    /// Move targets aside instead of deleting them and record them in this manifest,
+    /// so `npmclean restore --manifest FILE` can put them back
+    #[arg(long = "manifest", value_name = "FILE")]
+    pub manifest: Option<PathBuf>,
+
+    /// After cleaning, write one NDJSON line per cleaned target to this file
+    #[arg(long = "report", value_name = "FILE", global = true)]
+    pub report: Option<PathBuf>,
+
+    /// Run this command after a successful clean (NPMCLEAN_FREED_BYTES, NPMCLEAN_PROJECT_COUNT
+    /// and NPMCLEAN_TARGET_COUNT are set); with --dry-run it is only printed
+    #[arg(long = "post-clean", value_name = "COMMAND", global = true)]
+    pub post_clean_command: Option<String>,
+
+    /// Resume an interrupted clean, skipping targets this journal marks as deleted
+    #[arg(long = "resume", value_name = "JOURNAL", global = true)]
+    pub resume: Option<PathBuf>,
+
+    /// Wait when another npmclean process is cleaning an overlapping directory
+    #[arg(long, overrides_with = "no_wait", global = true)]
+    pub wait: bool,
+
+    /// Abort when another npmclean process is cleaning an overlapping directory (default)
+    #[arg(long = "no-wait", overrides_with = "wait", global = true)]
+    pub no_wait: bool,
+
+    /// Give up on a target whose deletion takes longer than this (e.g. 30s, 5m)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, global = true)]
+    pub timeout: Option<Duration>,
+
+    /// Stop starting new targets once this much time has passed since startup (e.g. 5m)
+    #[arg(long = "max-runtime", value_name = "DURATION", value_parser = parse_duration, global = true)]
+    pub max_runtime: Option<Duration>,
+
+    /// Number of worker threads used for scanning and cleaning
+    #[arg(long, value_name = "COUNT", global = true)]
+    pub threads: Option<usize>,
+
+    /// Number of projects deleted in parallel, independent of scanning (defaults to --threads)
+    #[arg(long = "delete-threads", value_name = "COUNT", global = true)]
+    pub delete_threads: Option<usize>,
+
+    /// Lower CPU and IO priority (nice/ionice on Linux, background mode on Windows)
+    #[arg(long = "low-priority", global = true)]
+    pub low_priority: bool,
+
+    /// Rename targets to a temporary name before deleting them
+    #[arg(long = "two-phase-delete", global = true)]
+    pub two_phase_delete: bool,
+
+    /// Skip targets on volumes whose free space cannot be queried (unless --force)
+    #[arg(long = "refuse-unknown-free-space", global = true)]
+    pub refuse_unknown_free_space: bool,
+
+    /// Measure free disk space before and after cleaning and report the real change
+    #[arg(long = "measure-free-delta", global = true)]
+    pub measure_free_delta: bool,
+
+    /// Check whether a newer npmclean release is available (queries the network, never updates)
+    #[arg(long = "check-update", global = true)]
+    pub check_update: bool,
+
     /// Show space-saving statistics
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub stats: bool,
 
-    /// Display detailed output
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// With --stats, skip the slow node_modules size computation (shown as unknown)
+    #[arg(long = "no-node-modules-size", global = true)]
+    pub no_node_modules_size: bool,
+
+    /// Group the cleaning preview by project type (-v lists the projects in each group)
+    #[arg(long, global = true)]
+    pub collapse: bool,
+
+    /// Show paths relative to the scan root (paths outside it stay absolute)
+    #[arg(long, global = true)]
+    pub relative: bool,
+
+    /// Output format
+    #[arg(long, value_enum, value_name = "FORMAT", global = true)]
+    pub format: Option<OutputFormat>,
+
+    /// When to use colors; auto disables them when stdout is not a terminal or NO_COLOR is set
+    #[arg(long, value_enum, value_name = "WHEN", global = true)]
+    pub color: Option<ColorMode>,
+
+    /// Write --format json output on a single line (default when stdout is not a terminal)
+    #[arg(long = "json-compact", overrides_with = "json_pretty", global = true)]
+    pub json_compact: bool,
+
+    /// Pretty-print --format json output (default when stdout is a terminal)
+    #[arg(long = "json-pretty", overrides_with = "json_compact", global = true)]
+    pub json_pretty: bool,
+
+    /// Answer used when pressing Enter at the confirmation prompt
+    #[arg(
+        long = "confirm-default",
+        value_enum,
+        value_name = "ANSWER",
+        global = true
+    )]
+    pub confirm_default: Option<ConfirmDefault>,
+
+    /// Display detailed output (-v per target, -vv per file)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Only print a one-line summary of the freed space (confirmation is still required)
+    #[arg(short, long, conflicts_with = "verbose", global = true)]
+    pub quiet: bool,
+}
+
+/// 子命令，未指定时执行默认的清理流程
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Only scan and report reclaimable space, never delete anything
+    Scan {
+        /// Paths to projects or directories, defaults to the main PATH arguments
+        paths: Vec<PathBuf>,
+    },
+
+    /// Scan and clean projects (the default when no subcommand is given)
+    Clean {
+        /// Paths to projects or directories, defaults to the main PATH arguments
+        paths: Vec<PathBuf>,
+
+        /// Same as the main --manifest option, accepted after the subcommand too
+        #[arg(long = "manifest", value_name = "FILE")]
+        manifest: Option<PathBuf>,
+    },
+
+    /// Merge several run reports (JSON, NDJSON or CSV) into one summary of freed space; dry-run reports are skipped
+    MergeReport {
+        /// File to write the aggregated JSON summary to
+        output: PathBuf,
+
+        /// Report files to merge
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+    },
+
+    /// Validate configuration, scan path and tools without scanning or cleaning
+    Preflight {
+        /// Path to check, defaults to the main PATH argument
+        path: Option<PathBuf>,
+    },
+
+    /// Move targets staged by a clean with --manifest back to their original paths
+    Restore {
+        /// Manifest written by the clean
+        #[arg(long = "manifest", value_name = "FILE")]
+        manifest: PathBuf,
+    },
+
+    /// Permanently delete targets staged by a clean with --manifest (they can no longer be restored)
+    Purge {
+        /// Manifest written by the clean
+        #[arg(long = "manifest", value_name = "FILE")]
+        manifest: PathBuf,
+    },
+
+    /// Show statistics recorded by previous runs
+    Stats {
+        /// Show the total space freed by all runs and the per-run history
+        #[arg(long, required = true)]
+        lifetime: bool,
+    },
+}
+
+impl CliArgs {
+    /// 要扫描的路径：`scan`/`clean` 子命令给出的路径优先于主参数中的路径
+    pub fn scan_paths(&self) -> &[PathBuf] {
+        match &self.command {
+            Some(Command::Scan { paths } | Command::Clean { paths, .. }) if !paths.is_empty() => {
+                paths
+            }
+            _ => &self.paths,
+        }
+    }
+
+    /// 是否为 `scan` 子命令或 `--scan-only`：只扫描和报告，不进入清理流程
+    pub fn is_scan_only(&self) -> bool {
+        self.scan_only || matches!(self.command, Some(Command::Scan { .. }))
+    }
+
+    /// 暂存清单路径：`clean --manifest` 优先于主参数中的 `--manifest`
+    pub fn manifest_path(&self) -> Option<&Path> {
+        match &self.command {
+            Some(Command::Clean {
+                manifest: Some(manifest),
+                ..
+            }) => Some(manifest),
+            _ => self.manifest.as_deref(),
+        }
+    }
 }
 
 pub fn parse_args() -> CliArgs {
     CliArgs::parse()
 }
 
+/// 按颜色模式全局设置 `console` 的颜色输出（标准输出和标准错误），
+/// `no_color` 表示设置了非空的 `NO_COLOR` 环境变量
+pub fn configure_colors(mode: ColorMode, no_color: bool) {
+    console::set_colors_enabled(mode.enabled(no_color, io::stdout().is_terminal()));
+    console::set_colors_enabled_stderr(mode.enabled(no_color, io::stderr().is_terminal()));
+}
+
 pub fn display_scan_results(projects: &[Project], config: &Config) {
+    let _ = write_scan_results(&mut io::stdout().lock(), projects, config);
+}
+
+/// `--scan-only` 的输出：按输出格式报告扫描到的项目和大小
+pub fn write_scan_report(
+    out: &mut impl Write,
+    projects: &[Project],
+    config: &Config,
+) -> io::Result<()> {
+    match config.format {
+        OutputFormat::Json => write_scan_json(out, projects, config.json_compact()),
+        OutputFormat::Csv => write_scan_csv(out, projects),
+        OutputFormat::Table => write_scan_table(out, projects, config),
+        _ => write_scan_results(out, projects, config),
+    }
+}
+
+/// `--scan-only --format json` 输出的 JSON 文档
+#[derive(Serialize)]
+struct ScanJsonReport<'a> {
+    version: u32,
+    projects: &'a [Project],
+}
+
+/// 以 JSON 写出扫描到的项目，包括目标和大小统计
+fn write_scan_json(out: &mut impl Write, projects: &[Project], compact: bool) -> io::Result<()> {
+    let report = ScanJsonReport {
+        version: 1,
+        projects,
+    };
+    if compact {
+        serde_json::to_writer(&mut *out, &report)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, &report)?;
+    }
+    writeln!(out)
+}
+
+/// 扫描报告的每行大小统计：总计、node_modules、构建、缓存和覆盖率目录（未统计时为 None）
+fn size_columns(project: &Project) -> [Option<u64>; 5] {
+    match &project.size_info {
+        Some(info) => [
+            Some(info.total_size),
+            Some(info.node_modules_size),
+            Some(info.build_dirs_size),
+            Some(info.cache_dirs_size),
+            Some(info.coverage_dirs_size),
+        ],
+        None => [None; 5],
+    }
+}
+
+/// 按 RFC 4180 转义 CSV 字段
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 以 CSV 写出扫描到的项目，大小为字节数，未统计时留空
+fn write_scan_csv(out: &mut impl Write, projects: &[Project]) -> io::Result<()> {
+    writeln!(
+        out,
+        "path,type,total_bytes,node_modules_bytes,build_bytes,cache_bytes,coverage_bytes,targets"
+    )?;
+    for project in projects {
+        let sizes: Vec<String> = size_columns(project)
+            .iter()
+            .map(|size| size.map(|size| size.to_string()).unwrap_or_default())
+            .collect();
+        writeln!(
+            out,
+            "{},{},{},{}",
+            csv_field(&project.path.display().to_string()),
+            csv_field(&project.project_type.to_string()),
+            sizes.join(","),
+            project.detected_targets.len()
+        )?;
+    }
+    Ok(())
+}
+
+/// 以对齐的表格写出扫描到的项目
+fn write_scan_table(out: &mut impl Write, projects: &[Project], config: &Config) -> io::Result<()> {
+    let header = [
+        "PATH",
+        "TYPE",
+        "TOTAL",
+        "NODE_MODULES",
+        "BUILD",
+        "CACHE",
+        "COVERAGE",
+    ];
+    let rows: Vec<Vec<String>> = projects
+        .iter()
+        .map(|project| {
+            let mut row = vec![
+                config.display_path(&project.path),
+                project.project_type.to_string(),
+            ];
+            row.extend(
+                size_columns(project)
+                    .iter()
+                    .map(|size| size.map(format_size).unwrap_or_else(|| "-".to_string())),
+            );
+            row
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        writeln!(out, "{}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+pub fn display_scan_stats(stats: &ScanStats) {
+    let _ = write_scan_stats(&mut io::stdout().lock(), stats);
+}
+
+/// 输出扫描统计，用于排查找到的项目比预期少的原因
+fn write_scan_stats(out: &mut impl Write, stats: &ScanStats) -> io::Result<()> {
+    writeln!(out, "\nScan statistics:")?;
+    writeln!(
+        out,
+        "  Directories visited:   {}",
+        stats.directories_visited
+    )?;
+    writeln!(
+        out,
+        "  Directories skipped:   {} node_modules, {} excluded",
+        stats.skipped_node_modules, stats.skipped_excluded
+    )?;
+    writeln!(out, "  Projects found:        {}", stats.projects_found)?;
+    writeln!(out, "  Projects filtered out: {}", stats.projects_filtered)?;
+    writeln!(out, "  Analysis failures:     {}", stats.analysis_failures)
+}
+
+/// debug 级别下每个目标最多列出的文件数
+const MAX_FILES_PER_TARGET: usize = 20;
+
+/// 按详细程度输出扫描结果：normal 显示项目，verbose 显示目标，debug 显示文件
+pub fn write_scan_results(
+    out: &mut impl Write,
+    projects: &[Project],
+    config: &Config,
+) -> io::Result<()> {
     if projects.is_empty() {
-        println!("No projects found.");
-        return;
+        writeln!(out, "No projects found.")?;
+        return Ok(());
     }
 
-    println!("Found {} projects:", projects.len());
+    writeln!(out, "Found {} projects:", projects.len())?;
+
+    if config.verbosity == Verbosity::Quiet {
+        return Ok(());
+    }
 
     for (i, project) in projects.iter().enumerate() {
-        println!("{}. {}", i + 1, project.path.display());
-        println!("   Type: {:?}", project.project_type);
+        writeln!(out, "{}. {}", i + 1, config.display_path(&project.path))?;
+        writeln!(out, "   Type: {}", project.project_type)?;
 
         if let Some(size_info) = &project.size_info {
-            let total_mb = size_info.total_size / (1024 * 1024);
-            println!("   Total Size: {} MB", total_mb);
+            writeln!(
+                out,
+                "   Total Size: {}{}",
+                format_size(size_info.total_size),
+                if config.skip_node_modules_size {
+                    " (excluding node_modules)"
+                } else {
+                    ""
+                }
+            )?;
         }
 
-        if config.verbose {
-            println!("   Targets to clean:");
+        if config.verbosity >= Verbosity::Verbose {
+            writeln!(out, "   Targets to clean:")?;
             for target in &project.detected_targets {
                 let size_str = if let Some(size) = target.size {
-                    format!(" ({} MB)", size / (1024 * 1024))
+                    format!(" ({})", format_size(size))
                 } else {
                     String::new()
                 };
 
-                println!(
+                writeln!(
+                    out,
                     "     - {} [{}]{}",
-                    target.path.display(),
+                    config.display_path(&target.path),
                     target.target_type,
                     size_str
-                );
+                )?;
+
+                if config.verbosity >= Verbosity::Debug {
+                    write_target_files(out, &target.path)?;
+                }
+            }
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+/// 列出目标中的文件，数量过多时截断
+fn write_target_files(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    let mut files = WalkDir::new(path)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file());
+
+    for entry in files.by_ref().take(MAX_FILES_PER_TARGET) {
+        writeln!(out, "         {}", entry.path().display())?;
+    }
+
+    let remaining = files.count();
+    if remaining > 0 {
+        writeln!(out, "         ... and {} more files", remaining)?;
+    }
+
+    Ok(())
+}
+
+/// 生成单行摘要，例如 `42 projects, 18.3 GB reclaimable (node_modules 15.1 GB)`
+pub fn format_oneline(projects: &[Project], config: &Config) -> String {
+    let mut reclaimable = 0;
+    let mut node_modules = 0;
+
+    for target in projects.iter().flat_map(|p| &p.detected_targets) {
+        if !config.should_clean(&target.target_type) {
+            continue;
+        }
+        let size = target.size.unwrap_or(0);
+        reclaimable += size;
+        if target.target_type == TargetType::NodeModules {
+            node_modules += size;
+        }
+    }
+
+    format!(
+        "{} {}, {} reclaimable (node_modules {})",
+        projects.len(),
+        if projects.len() == 1 {
+            "project"
+        } else {
+            "projects"
+        },
+        format_size(reclaimable),
+        format_size(node_modules)
+    )
+}
+
+/// JSON 报告中的单个目标：路径、类型、大小，以及是否被选中及原因
+#[derive(Serialize)]
+struct PlannedTarget<'a> {
+    #[serde(flatten)]
+    target: &'a CleanTarget,
+    selected: bool,
+    reason: String,
+}
+
+/// JSON 报告中的单个项目
+#[derive(Serialize)]
+struct PlannedProject<'a> {
+    path: &'a Path,
+    project_type: String,
+    targets: Vec<PlannedTarget<'a>>,
+}
+
+/// `--format json` 输出的 JSON 文档
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    version: u32,
+    dry_run: bool,
+    projects: Vec<PlannedProject<'a>>,
+    /// 清理结果，未执行清理时为 null
+    results: Option<&'a CleanResults>,
+}
+
+/// 写出 JSON 报告：扫描到的项目（包含被跳过的目标及跳过原因）和清理结果
+///
+/// `compact` 为 true 时整个文档输出为一行，便于写入日志。
+pub fn write_json(
+    out: &mut impl Write,
+    projects: &[Project],
+    results: Option<&CleanResults>,
+    config: &Config,
+    compact: bool,
+) -> serde_json::Result<()> {
+    let projects = projects
+        .iter()
+        .map(|project| {
+            let detected = project.detected_targets.iter().map(|target| {
+                let selected = config.should_clean(&target.target_type);
+                PlannedTarget {
+                    target,
+                    selected,
+                    reason: if selected {
+                        "would be cleaned".to_string()
+                    } else {
+                        format!("{} targets are not enabled", target.target_type)
+                    },
+                }
+            });
+            let skipped = project.skipped_targets.iter().map(|skipped| PlannedTarget {
+                target: &skipped.target,
+                selected: false,
+                reason: skipped.reason.clone(),
+            });
+
+            PlannedProject {
+                path: &project.path,
+                project_type: project.project_type.to_string(),
+                targets: detected.chain(skipped).collect(),
             }
+        })
+        .collect();
+
+    let report = JsonReport {
+        version: 1,
+        dry_run: config.dry_run,
+        projects,
+        results,
+    };
+    if compact {
+        serde_json::to_writer(&mut *out, &report)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, &report)?;
+    }
+    writeln!(out).map_err(serde_json::Error::io)
+}
+
+/// 生成 Markdown 格式的清理结果：每个目标一行，最后是汇总
+pub fn format_markdown(results: &CleanResults, config: &Config) -> String {
+    let mut markdown = String::from("| Project | Target | Type | Size | Status |\n");
+    markdown.push_str("| --- | --- | --- | --- | --- |\n");
+
+    for outcome in &results.outcomes {
+        let status = match (outcome.status, config.dry_run) {
+            (OutcomeStatus::Cleaned, true) => "would clean",
+            (OutcomeStatus::Cleaned, false) => "cleaned",
+            (OutcomeStatus::Missing, _) => "already gone",
+            (OutcomeStatus::Failed, _) => "failed",
+            (OutcomeStatus::Staged, _) => "staged",
+        };
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            escape_markdown(&outcome.project_path.display().to_string()),
+            escape_markdown(&outcome.target_path.display().to_string()),
+            escape_markdown(&outcome.target_type.to_string()),
+            format_size(outcome.bytes_removed),
+            status
+        ));
+    }
+
+    markdown.push_str(&format!(
+        "\n**{}** {} {} across {} {}: {} {}.\n",
+        if config.dry_run {
+            "Dry run:"
+        } else {
+            "Summary:"
+        },
+        results.cleaned_targets,
+        if results.cleaned_targets == 1 {
+            "target"
+        } else {
+            "targets"
+        },
+        results.total_projects,
+        if results.total_projects == 1 {
+            "project"
+        } else {
+            "projects"
+        },
+        format_size(results.total_bytes_removed),
+        if config.dry_run {
+            "would be freed"
+        } else {
+            "freed"
+        }
+    ));
+    if results.failed_targets > 0 {
+        markdown.push_str(&format!(
+            "{} targets failed to clean.\n",
+            results.failed_targets
+        ));
+    }
+
+    markdown
+}
+
+/// 转义 Markdown 表格单元格中的特殊字符
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#'
+        ) {
+            escaped.push('\\');
         }
-        println!();
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 显示改用共享依赖存储可节省空间的建议
+pub fn display_shared_store_savings(savings: u64) {
+    println!(
+        "\nTip: Some projects have identical lockfiles. If you adopted pnpm, you'd save ~{}",
+        format_size(savings)
+    );
+}
+
+/// 最多列出的安装已损坏的项目数
+const MAX_BROKEN_INSTALLS: usize = 10;
+
+/// 安装已损坏（node_modules 中有悬空符号链接）的项目提示，没有时返回 None
+pub fn format_broken_installs(projects: &[Project]) -> Option<String> {
+    let broken: Vec<&Project> = projects
+        .iter()
+        .filter(|project| !project.broken_links.is_empty())
+        .collect();
+    if broken.is_empty() {
+        return None;
+    }
+
+    let mut message = format!(
+        "Warning: {} projects have dangling symlinks in node_modules, their installs look corrupted \
+         and are worth cleaning:",
+        broken.len()
+    );
+    for project in broken.iter().take(MAX_BROKEN_INSTALLS) {
+        message.push_str(&format!(
+            "\n  - {} ({} broken links, e.g. {})",
+            project.path.display(),
+            project.broken_links.len(),
+            project.broken_links[0].display()
+        ));
+    }
+    if broken.len() > MAX_BROKEN_INSTALLS {
+        message.push_str(&format!(
+            "\n  ... and {} more",
+            broken.len() - MAX_BROKEN_INSTALLS
+        ));
+    }
+    Some(message)
+}
+
+/// 提示暂存的目标可以恢复
+pub fn display_restore_hint(manifest: &Path) {
+    println!(
+        "\nTargets were moved aside, not deleted. Restore them with: npmclean restore --manifest {}",
+        manifest.display()
+    );
+}
+
+/// `--drill` 的输出：node_modules 中最大的直接子目录
+pub fn write_drill_report(
+    out: &mut impl Write,
+    node_modules: &Path,
+    packages: &[(String, u64)],
+) -> io::Result<()> {
+    writeln!(out, "Largest packages in {}:", node_modules.display())?;
+    for (name, size) in packages {
+        writeln!(out, "  {:>10}  {}", format_size(*size), name)?;
+    }
+    Ok(())
+}
+
+/// `--check-update` 的提示
+pub fn format_update_status(status: &UpdateStatus, current: &str) -> String {
+    match status {
+        UpdateStatus::UpToDate => format!("npmclean {} is up to date", current),
+        UpdateStatus::NewerAvailable(latest) => format!(
+            "A newer version of npmclean is available: {} (current {}). Run `cargo install npmclean` to update.",
+            latest, current
+        ),
+    }
+}
+
+/// 显示按清单永久删除暂存目录的结果
+pub fn display_purge_summary(summary: &PurgeSummary) {
+    println!(
+        "Purged {} staged targets, freed {}",
+        summary.purged.len(),
+        format_size(summary.bytes_freed)
+    );
+    for path in &summary.purged {
+        println!("  {}", path.display());
+    }
+    if summary.already_gone > 0 {
+        println!(
+            "{} entries were skipped because their staged copy no longer exists",
+            summary.already_gone
+        );
+    }
+    for (path, error) in &summary.failed {
+        eprintln!(
+            "{} {}: {}",
+            style("Failed to purge").red(),
+            path.display(),
+            error
+        );
+    }
+}
+
+/// 显示按清单恢复的结果
+pub fn display_restore_summary(summary: &RestoreSummary) {
+    println!("Restored {} targets", summary.restored.len());
+    for path in &summary.restored {
+        println!("  {}", path.display());
+    }
+    if summary.already_gone > 0 {
+        println!(
+            "{} entries were skipped because their staged copy no longer exists",
+            summary.already_gone
+        );
+    }
+    for (path, error) in &summary.failed {
+        eprintln!(
+            "{} {}: {}",
+            style("Failed to restore").red(),
+            path.display(),
+            error
+        );
     }
 }
 
 pub fn display_clean_results(results: &CleanResults, config: &Config) {
+    let mut out = io::stdout();
+    let written = write_clean_results(&mut out, results, config)
+        .and_then(|_| write_would_delete(&mut out, results))
+        .and_then(|_| write_would_confirm(&mut out, results));
+    if let Err(e) = written {
+        log::error!("Failed to write clean results: {}", e);
+    }
+}
+
+/// 在结果之后逐行列出 dry run 将会删除的路径（按路径排序），便于复制
+fn write_would_delete(out: &mut impl Write, results: &CleanResults) -> io::Result<()> {
+    if results.would_delete.is_empty() {
+        return Ok(());
+    }
+
+    let mut paths: Vec<&PathBuf> = results.would_delete.iter().collect();
+    paths.sort();
+    writeln!(out)?;
+    for path in paths {
+        writeln!(out, "{}", path.display())?;
+    }
+    Ok(())
+}
+
+/// dry run 时列出实际清理会要求单独确认的大目标
+fn write_would_confirm(out: &mut impl Write, results: &CleanResults) -> io::Result<()> {
+    if results.would_confirm.is_empty() {
+        return Ok(());
+    }
+
+    let mut targets: Vec<&(PathBuf, u64)> = results.would_confirm.iter().collect();
+    targets.sort();
+    writeln!(
+        out,
+        "\nThese targets would require confirmation (--confirm-above):"
+    )?;
+    for (path, size) in targets {
+        writeln!(out, "  {} ({})", path.display(), format_size(*size))?;
+    }
+    Ok(())
+}
+
+/// 写出清理结果；删除失败的目标总会逐个列出，便于手动处理
+fn write_clean_results(
+    out: &mut impl Write,
+    results: &CleanResults,
+    config: &Config,
+) -> io::Result<()> {
+    if config.verbosity == Verbosity::Quiet {
+        return write_quiet_summary(out, results, config);
+    }
+
     if config.dry_run {
-        println!("\n[DRY RUN] - No files were actually deleted");
+        writeln!(out, "\n[DRY RUN] - No files were actually deleted")?;
     } else {
-        println!("\n[CLEANING COMPLETED]");
+        writeln!(out, "\n[CLEANING COMPLETED]")?;
     }
 
     // 显示统计数据
-    let freed_mb = results.total_bytes_removed / (1024 * 1024);
+    let freed = format_size(results.total_bytes_removed);
 
     if config.dry_run {
-        println!("Space that would be freed: {} MB", freed_mb);
+        writeln!(out, "Space that would be freed: {}", freed)?;
     } else {
-        println!("Space freed: {} MB", freed_mb);
+        writeln!(out, "Space freed: {}", freed)?;
+    }
+    if results.staged_bytes > 0 {
+        writeln!(
+            out,
+            "Staged for restore (not freed yet): {}",
+            format_size(results.staged_bytes)
+        )?;
+    }
+
+    // 实测值可能因硬链接、并发写入或压缩与计算值不同
+    if let Some(delta) = results.free_space_delta() {
+        let measured = format_size(delta.unsigned_abs().try_into().unwrap_or(u64::MAX));
+        writeln!(
+            out,
+            "Free space change (measured): {}{} (computed: {})",
+            if delta < 0 { "-" } else { "+" },
+            measured,
+            freed
+        )?;
+    } else if config.measure_free_delta && !config.dry_run {
+        writeln!(out, "Free space change (measured): unavailable")?;
     }
 
     // 仅在详细模式下显示更多统计信息
     if config.stats {
-        println!(
+        writeln!(
+            out,
             "Projects processed: {}/{}",
             results.cleaned_projects, results.total_projects
-        );
-        println!(
+        )?;
+        writeln!(
+            out,
             "Targets cleaned: {}/{}",
             results.cleaned_targets, results.total_targets
+        )?;
+
+        writeln!(out, "\nSpace by target type:")?;
+        // 未计算 node_modules 大小时其释放量未知，不显示为 0
+        let node_modules_unknown = config.skip_node_modules_size && !config.measure_on_delete;
+        for (label, bytes) in [
+            ("node_modules", results.node_modules_bytes),
+            ("build", results.build_bytes),
+            ("cache", results.cache_bytes),
+            ("coverage", results.coverage_bytes),
+            ("custom", results.custom_bytes),
+        ] {
+            let size = if label == "node_modules" && node_modules_unknown {
+                "unknown".to_string()
+            } else {
+                format_size(bytes)
+            };
+            writeln!(out, "  {:<14}{:>10}", label, size)?;
+        }
+
+        if results.failed_projects > 0 {
+            writeln!(
+                out,
+                "\n[WARNING] Failed projects: {}",
+                results.failed_projects
+            )?;
+        }
+    }
+
+    if results.not_started_targets > 0 {
+        writeln!(
+            out,
+            "\n[STOPPED] Maximum runtime reached: {} of {} targets were not started",
+            results.not_started_targets, results.total_targets
+        )?;
+    }
+
+    if results.failed_targets > 0 {
+        writeln!(
+            out,
+            "\n[WARNING] {} targets could not be cleaned:",
+            results.failed_targets
+        )?;
+
+        let mut stuck = 0;
+        for failure in results.failures() {
+            stuck += failure.size.unwrap_or(0);
+            writeln!(
+                out,
+                "  - {} [{}] ({}): {}",
+                config.display_path(&failure.target_path),
+                failure.target_type,
+                failure
+                    .size
+                    .map(format_size)
+                    .unwrap_or_else(|| "size unknown".to_string()),
+                failure.error.as_deref().unwrap_or("unknown error")
+            )?;
+        }
+        writeln!(out, "Space still occupied: {}", format_size(stuck))?;
+    }
+
+    Ok(())
+}
+
+/// quiet 模式下的单行汇总，有失败的目标时附带失败数量
+fn write_quiet_summary(
+    out: &mut impl Write,
+    results: &CleanResults,
+    config: &Config,
+) -> io::Result<()> {
+    write!(
+        out,
+        "{} {} across {} project{}",
+        if config.dry_run {
+            "Would free"
+        } else {
+            "Freed"
+        },
+        format_size(results.total_bytes_removed),
+        results.cleaned_projects,
+        if results.cleaned_projects == 1 {
+            ""
+        } else {
+            "s"
+        }
+    )?;
+    if results.failed_targets > 0 {
+        write!(
+            out,
+            " ({} targets could not be cleaned)",
+            results.failed_targets
+        )?;
+    }
+    writeln!(out)
+}
+
+pub fn display_merged_report(report: &MergedReport, output: &Path) {
+    println!(
+        "Merged {} reports: {} projects, {} total",
+        report.reports,
+        report.total_projects,
+        format_size(report.total_bytes)
+    );
+
+    for (project_type, bytes) in &report.by_project_type {
+        println!("  - {}: {}", project_type, format_size(*bytes));
+    }
+
+    for skipped in &report.skipped {
+        println!(
+            "[WARNING] Skipped {}: {}",
+            skipped.path.display(),
+            skipped.error
         );
+    }
 
-        if results.failed_projects > 0 || results.failed_targets > 0 {
-            println!("\n[WARNING] Issues encountered during cleaning:");
-            if results.failed_projects > 0 {
-                println!("  - Failed projects: {}", results.failed_projects);
-            }
-            if results.failed_targets > 0 {
-                println!("  - Failed targets: {}", results.failed_targets);
-            }
+    println!("Summary written to {}", output.display());
+}
+
+/// `stats --lifetime` 的输出：累计释放的空间和每次清理的记录
+pub fn write_lifetime_stats(out: &mut impl Write, runs: &[LifetimeRun]) -> io::Result<()> {
+    if runs.is_empty() {
+        return writeln!(out, "No cleaning runs recorded yet");
+    }
+
+    writeln!(
+        out,
+        "Lifetime: {} freed over {} runs",
+        format_size(crate::lifetime::total_bytes_freed(runs)),
+        runs.len()
+    )?;
+    for run in runs {
+        writeln!(
+            out,
+            "  {}  {:>10}  ({} targets)",
+            run.timestamp,
+            run.bytes_freed.map_or("unknown".to_string(), format_size),
+            run.targets
+        )?;
+    }
+    let unknown = runs.iter().filter(|run| run.bytes_freed.is_none()).count();
+    if unknown > 0 {
+        writeln!(
+            out,
+            "{} runs freed an unknown amount (sizes were not measured) and are not included in the total",
+            unknown
+        )?;
+    }
+    Ok(())
+}
+
+pub fn display_preflight_report(report: &PreflightReport) {
+    println!("{}", style("Preflight checks:").bold().underlined());
+
+    for check in &report.checks {
+        let status = match check.status {
+            CheckStatus::Pass => style("[PASS]").green(),
+            CheckStatus::Warn => style("[WARN]").yellow(),
+            CheckStatus::Fail => style("[FAIL]").red(),
+        };
+        println!("  {} {}: {}", status, check.name, check.detail);
+    }
+
+    if let Some(config) = &report.effective_config {
+        println!(
+            "\n{}",
+            style("Effective configuration:").bold().underlined()
+        );
+        match serde_yaml::to_string(config) {
+            Ok(yaml) => println!("{}", yaml.trim_end()),
+            Err(e) => println!("  (failed to render configuration: {})", e),
+        }
+    }
+
+    if report.passed() {
+        println!("\n{}", style("Preflight passed").green().bold());
+    } else {
+        println!("\n{}", style("Preflight failed").red().bold());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::CleanTarget;
+    use std::fs;
+
+    #[test]
+    fn test_manifest_is_accepted_after_clean_subcommand() {
+        let args = CliArgs::try_parse_from([
+            "npmclean",
+            "clean",
+            "--manifest",
+            "staged.json",
+            "-f",
+            "/tmp/project",
+        ])
+        .unwrap();
+        assert_eq!(args.manifest_path(), Some(Path::new("staged.json")));
+        assert_eq!(args.scan_paths(), [PathBuf::from("/tmp/project")]);
+
+        let args =
+            CliArgs::try_parse_from(["npmclean", "--manifest", "staged.json", "clean"]).unwrap();
+        assert_eq!(args.manifest_path(), Some(Path::new("staged.json")));
+
+        let args =
+            CliArgs::try_parse_from(["npmclean", "restore", "--manifest", "staged.json"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Restore { .. })));
+    }
+
+    #[test]
+    fn test_color_mode_respects_no_color_and_terminal() {
+        assert!(ColorMode::Always.enabled(true, false));
+        assert!(!ColorMode::Never.enabled(false, true));
+        assert!(ColorMode::Auto.enabled(false, true));
+        assert!(!ColorMode::Auto.enabled(true, true));
+        assert!(!ColorMode::Auto.enabled(false, false));
+    }
+
+    fn project_with_targets(path: &str, targets: &[(TargetType, u64)]) -> Project {
+        let mut project = Project::new(PathBuf::from(path));
+        for (target_type, size) in targets {
+            project.detected_targets.push(CleanTarget {
+                path: project.path.join(target_type.to_string()),
+                target_type: target_type.clone(),
+                size: Some(*size),
+            });
+        }
+        project
+    }
+
+    #[test]
+    fn test_scan_only_json_contains_size_breakdown() {
+        let mut project = project_with_targets(
+            "/work/web, app",
+            &[(TargetType::NodeModules, 1000), (TargetType::BuildDir, 200)],
+        );
+        project.project_type = ProjectType::NextJs;
+        project.size_info = Some(crate::project::SizeInfo {
+            total_size: 1200,
+            node_modules_size: 1000,
+            build_dirs_size: 200,
+            cache_dirs_size: 0,
+            coverage_dirs_size: 0,
+        });
+        let projects = vec![project, Project::new(PathBuf::from("/work/api"))];
+        let config = Config {
+            scan_only: true,
+            format: OutputFormat::Json,
+            json_compact: Some(true),
+            ..Config::default()
+        };
+
+        let mut out = Vec::new();
+        write_scan_report(&mut out, &projects, &config).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let web = &json["projects"][0];
+        assert_eq!(web["path"], "/work/web, app");
+        assert_eq!(web["project_type"], "Next.js");
+        assert_eq!(web["size_info"]["total_size"], 1200);
+        assert_eq!(web["size_info"]["node_modules_size"], 1000);
+        assert_eq!(web["size_info"]["build_dirs_size"], 200);
+        assert_eq!(json["projects"][1]["path"], "/work/api");
+
+        let config = Config {
+            format: OutputFormat::Csv,
+            ..config
+        };
+        let mut out = Vec::new();
+        write_scan_report(&mut out, &projects, &config).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "\"/work/web, app\",Next.js,1200,1000,200,0,0,2");
+        assert_eq!(lines[2], "/work/api,Unknown,,,,,,0");
+    }
+
+    #[test]
+    fn test_verbosity_levels_control_scan_output() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::write(dir.path().join("dist/main.js"), "").unwrap();
+        fs::write(dir.path().join("dist/vendor.js"), "").unwrap();
+
+        let mut project = Project::new(dir.path().to_path_buf());
+        project.detected_targets.push(CleanTarget {
+            path: dir.path().join("dist"),
+            target_type: TargetType::BuildDir,
+            size: None,
+        });
+        let projects = vec![project];
+
+        let render = |verbosity| {
+            let config = Config {
+                verbosity,
+                ..Config::default()
+            };
+            let mut out = Vec::new();
+            write_scan_results(&mut out, &projects, &config).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let quiet = render(Verbosity::Quiet);
+        let normal = render(Verbosity::Normal);
+        let verbose = render(Verbosity::Verbose);
+        let debug = render(Verbosity::Debug);
+
+        assert_eq!(quiet.lines().count(), 1);
+        assert!(normal.lines().count() > quiet.lines().count());
+        assert!(!normal.contains("Targets to clean"));
+        assert!(verbose.contains("Targets to clean"));
+        assert!(!verbose.contains("main.js"));
+        assert!(debug.contains("main.js") && debug.contains("vendor.js"));
+    }
+
+    #[test]
+    fn test_relative_paths_shown_for_targets_under_root() {
+        let mut project = project_with_targets("/work/repo/app", &[(TargetType::BuildDir, 1)]);
+        project.detected_targets.push(CleanTarget {
+            path: PathBuf::from("/elsewhere/node_modules"),
+            target_type: TargetType::NodeModules,
+            size: None,
+        });
+        let config = Config {
+            verbosity: Verbosity::Verbose,
+            relative_paths: true,
+            scan_roots: vec![PathBuf::from("/work/repo")],
+            ..Config::default()
+        };
+
+        let mut out = Vec::new();
+        write_scan_results(&mut out, &[project], &config).unwrap();
+        let output = String::from_utf8(out).unwrap();
+
+        assert!(output.contains("1. app\n"));
+        assert!(output.contains(&format!(
+            "- {} [build]",
+            Path::new("app").join("build").display()
+        )));
+        assert!(!output.contains("/work/repo"));
+        // 根目录之外的目标仍显示绝对路径
+        assert!(output.contains("- /elsewhere/node_modules [node_modules]"));
+        assert_eq!(config.display_path(Path::new("/work/repo")), ".");
+    }
+
+    #[test]
+    fn test_format_oneline() {
+        let gib = 1024 * 1024 * 1024;
+        let projects = vec![
+            project_with_targets(
+                "/work/a",
+                &[
+                    (TargetType::NodeModules, 2 * gib),
+                    (TargetType::BuildDir, gib / 2),
+                ],
+            ),
+            project_with_targets("/work/b", &[(TargetType::NodeModules, gib)]),
+        ];
+
+        assert_eq!(
+            format_oneline(&projects, &Config::default()),
+            "2 projects, 3.5 GB reclaimable (node_modules 3.0 GB)"
+        );
+
+        // 不清理的类型不计入可回收空间
+        let config = Config {
+            clean_build_dirs: false,
+            ..Config::default()
+        };
+        assert_eq!(
+            format_oneline(&projects[..1], &config),
+            "1 project, 2.0 GB reclaimable (node_modules 2.0 GB)"
+        );
+    }
+
+    #[test]
+    fn test_dry_run_json_records_excluded_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::create_dir_all(dir.path().join("build")).unwrap();
+
+        let config = Config {
+            dry_run: true,
+            exclude: vec!["**/build".to_string()],
+            ..Config::default()
+        };
+        let projects = crate::scanner::Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let mut out = Vec::new();
+        write_json(&mut out, &projects, None, &config, false).unwrap();
+        let plan: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(plan["dry_run"], true);
+        let targets = plan["projects"][0]["targets"].as_array().unwrap();
+        let find = |name: &str| {
+            targets
+                .iter()
+                .find(|t| t["path"].as_str().unwrap().ends_with(name))
+                .unwrap()
+        };
+
+        let build = find("build");
+        assert_eq!(build["selected"], false);
+        assert_eq!(build["type"], "build");
+        assert_eq!(build["reason"], "matches an exclude pattern");
+
+        let dist = find("dist");
+        assert_eq!(dist["selected"], true);
+        assert!(!dist["reason"].as_str().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_json_report_includes_clean_results() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::write(dir.path().join("dist/app.js"), "x").unwrap();
+
+        let config = Config {
+            force: true,
+            stats: true,
+            format: OutputFormat::Json,
+            ..Config::default()
+        };
+        let projects = crate::scanner::Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(projects.clone())
+            .unwrap();
+        let mut out = Vec::new();
+        write_json(&mut out, &projects, Some(&results), &config, false).unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(report["dry_run"], false);
+        assert_eq!(report["projects"][0]["targets"][0]["type"], "build");
+        assert_eq!(report["projects"][0]["targets"][0]["size"], 1);
+        assert_eq!(report["results"]["cleaned_targets"], 1);
+        assert_eq!(report["results"]["total_bytes_removed"], 1);
+        assert_eq!(report["results"]["outcomes"][0]["status"], "cleaned");
+        assert!(!dir.path().join("dist").exists());
+
+        // 紧凑格式：整个文档只占一行，内容与美化格式相同
+        let mut compact = Vec::new();
+        write_json(&mut compact, &projects, Some(&results), &config, true).unwrap();
+        let compact = String::from_utf8(compact).unwrap();
+        assert_eq!(compact.trim_end().lines().count(), 1);
+        assert!(!compact.trim_end().contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&compact).unwrap(),
+            report
+        );
+    }
+
+    #[test]
+    fn test_format_markdown_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("my_app|v2");
+        let mut project = Project::new(root.clone());
+        for name in ["dist", "node_modules"] {
+            let path = root.join(name);
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                target_type: if name == "dist" {
+                    TargetType::BuildDir
+                } else {
+                    TargetType::NodeModules
+                },
+                path,
+                size: Some(2048),
+            });
         }
+
+        let config = Config {
+            dry_run: true,
+            force: true,
+            ..Config::default()
+        };
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(vec![project])
+            .unwrap();
+        let markdown = format_markdown(&results, &config);
+        let lines: Vec<_> = markdown.lines().collect();
+
+        assert_eq!(lines[0], "| Project | Target | Type | Size | Status |");
+        assert_eq!(lines[1], "| --- | --- | --- | --- | --- |");
+        let rows: Vec<_> = lines[2..]
+            .iter()
+            .take_while(|line| line.starts_with('|'))
+            .collect();
+        assert_eq!(rows.len(), 2);
+        // 路径中的 `_` 和 `|` 被转义，不会破坏表格
+        assert!(rows.iter().all(|row| row.contains(r"my\_app\|v2")));
+        assert!(
+            markdown.contains("**Dry run:** 2 targets across 1 project: 4.0 KB would be freed.")
+        );
+    }
+
+    #[test]
+    fn test_clean_results_list_failed_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        // 目标在清理前已不存在，严格模式下必然失败
+        project.detected_targets.push(CleanTarget {
+            path: dir.path().join("dist"),
+            target_type: TargetType::BuildDir,
+            size: Some(3 * 1024),
+        });
+
+        let config = Config {
+            force: true,
+            strict_missing: true,
+            ..Config::default()
+        };
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(vec![project])
+            .unwrap();
+
+        let mut out = Vec::new();
+        write_clean_results(&mut out, &results, &config).unwrap();
+        let output = String::from_utf8(out).unwrap();
+
+        let failed_path = dir.path().join("dist").display().to_string();
+        assert!(output.contains("1 targets could not be cleaned"));
+        assert!(output.contains(&format!(
+            "{} [build] (3.0 KB): Target no longer exists: {}",
+            failed_path, failed_path
+        )));
+        assert!(output.contains("Space still occupied: 3.0 KB"));
+    }
+
+    #[test]
+    fn test_measured_free_delta_reported_with_computed_sum() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        let path = dir.path().join("dist");
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("bundle.js"), vec![0u8; 64 * 1024]).unwrap();
+        project.detected_targets.push(CleanTarget {
+            path,
+            target_type: TargetType::BuildDir,
+            size: Some(64 * 1024),
+        });
+
+        let config = Config {
+            force: true,
+            measure_on_delete: true,
+            measure_free_delta: true,
+            ..Config::default()
+        };
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(vec![project])
+            .unwrap();
+        // 实际变化量取决于文件系统，这里只确认两个数字都被报告
+        assert!(results.free_space_before.is_some());
+        assert!(results.free_space_after.is_some());
+
+        let mut out = Vec::new();
+        write_clean_results(&mut out, &results, &config).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Space freed: 64.0 KB"));
+        let line = output
+            .lines()
+            .find(|line| line.starts_with("Free space change (measured): "))
+            .unwrap();
+        assert!(line.ends_with("(computed: 64.0 KB)"));
+    }
+
+    #[test]
+    fn test_quiet_mode_prints_only_summary_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let projects: Vec<Project> = ["web", "api"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name).join("dist");
+                fs::create_dir_all(&path).unwrap();
+                fs::write(path.join("bundle.js"), vec![0u8; 1536]).unwrap();
+                let mut project = Project::new(dir.path().join(name));
+                project.detected_targets.push(CleanTarget {
+                    path,
+                    target_type: TargetType::BuildDir,
+                    size: Some(1536),
+                });
+                project
+            })
+            .collect();
+
+        let config = Config {
+            force: true,
+            stats: true,
+            quiet: true,
+            verbosity: Verbosity::Quiet,
+            ..Config::default()
+        };
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(projects)
+            .unwrap();
+
+        let mut out = Vec::new();
+        write_clean_results(&mut out, &results, &config).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert_eq!(output, "Freed 3.0 KB across 2 projects\n");
+    }
+
+    #[test]
+    fn test_dry_run_list_prints_paths_that_would_be_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        for (name, target_type) in [
+            ("node_modules", TargetType::NodeModules),
+            ("dist", TargetType::BuildDir),
+            ("coverage", TargetType::Coverage),
+        ] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type,
+                size: Some(1),
+            });
+        }
+
+        let config = Config {
+            dry_run: true,
+            list_paths: true,
+            clean_coverage_dirs: false,
+            ..Config::default()
+        };
+        let mut expected: Vec<String> = project
+            .detected_targets
+            .iter()
+            .filter(|t| config.should_clean(&t.target_type))
+            .map(|t| t.path.display().to_string())
+            .collect();
+        expected.sort();
+
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(vec![project])
+            .unwrap();
+        let mut out = Vec::new();
+        write_would_delete(&mut out, &results).unwrap();
+        let output = String::from_utf8(out).unwrap();
+
+        let listed: Vec<&str> = output.lines().skip(1).collect();
+        assert_eq!(listed, expected);
+        assert!(dir.path().join("dist").exists());
+    }
+
+    #[test]
+    fn test_stats_show_space_by_target_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        for (name, target_type, size) in [
+            ("node_modules", TargetType::NodeModules, 4096),
+            ("dist", TargetType::BuildDir, 1024),
+            (".cache", TargetType::CacheDir, 512),
+            (".turbo", TargetType::Custom(".turbo".to_string()), 256),
+        ] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type,
+                size: Some(size),
+            });
+        }
+
+        // dry run 与实际清理使用相同的计数器
+        let config = Config {
+            dry_run: true,
+            stats: true,
+            ..Config::default()
+        };
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(vec![project])
+            .unwrap();
+        assert_eq!(results.node_modules_bytes, 4096);
+        assert_eq!(results.build_bytes, 1024);
+        assert_eq!(results.cache_bytes, 512);
+        assert_eq!(results.coverage_bytes, 0);
+        assert_eq!(results.custom_bytes, 256);
+
+        let mut out = Vec::new();
+        write_clean_results(&mut out, &results, &config).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Space by target type:"));
+        assert!(output.contains(&format!("  {:<14}{:>10}", "node_modules", "4.0 KB")));
+        assert!(output.contains(&format!("  {:<14}{:>10}", "coverage", "0 B")));
     }
 }
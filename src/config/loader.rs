@@ -1,27 +1,112 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 
 use super::schema::Config;
 
+/// 从配置文件加载的配置，以及文件中实际出现的顶层键
+///
+/// 合并时只有出现过的键才会覆盖基础配置，这样显式写出的 `false` 也能生效。
+pub struct ConfigFile {
+    pub config: Config,
+    pub present_keys: HashSet<String>,
+}
+
+impl ConfigFile {
+    /// 从 YAML 内容解析配置
+    pub fn parse(content: &str) -> serde_yaml::Result<Self> {
+        let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+        let present_keys = match &value {
+            serde_yaml::Value::Mapping(mapping) => mapping
+                .keys()
+                .filter_map(|key| key.as_str().map(str::to_string))
+                .collect(),
+            _ => HashSet::new(),
+        };
+        let config = if value.is_null() {
+            // 空文件等价于没有设置任何键
+            serde_yaml::from_str("{}")?
+        } else {
+            serde_yaml::from_value(value)?
+        };
+
+        Ok(Self {
+            config,
+            present_keys,
+        })
+    }
+
+    /// 从 JSON 内容解析配置
+    pub fn parse_json(content: &str) -> serde_json::Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        let present_keys = match &value {
+            serde_json::Value::Object(object) => object.keys().cloned().collect(),
+            _ => HashSet::new(),
+        };
+
+        Ok(Self {
+            config: serde_json::from_value(value)?,
+            present_keys,
+        })
+    }
+
+    /// 从 TOML 内容解析配置
+    pub fn parse_toml(content: &str) -> Result<Self, toml::de::Error> {
+        let table: toml::Table = toml::from_str(content)?;
+        let present_keys = table.keys().cloned().collect();
+
+        Ok(Self {
+            config: toml::Value::Table(table).try_into()?,
+            present_keys,
+        })
+    }
+
+    /// 文件中是否设置了该键
+    pub fn has(&self, key: &str) -> bool {
+        self.present_keys.contains(key)
+    }
+}
+
 /// 加载指定路径的配置文件
-pub fn load_config_file(path: &Path) -> Result<Config> {
+pub fn load_config_file(path: &Path) -> Result<ConfigFile> {
     let content = fs::read_to_string(path)?;
 
     // 基于文件扩展名选择解析器
     match path.extension().and_then(|e| e.to_str()) {
-        Some("yml") | Some("yaml") => serde_yaml::from_str(&content)
+        Some("yml") | Some("yaml") => ConfigFile::parse(&content)
             .context(format!("Failed to parse YAML file: {}", path.display())),
+        Some("json") => ConfigFile::parse_json(&content)
+            .context(format!("Failed to parse JSON file: {}", path.display())),
+        Some("toml") => ConfigFile::parse_toml(&content)
+            .context(format!("Failed to parse TOML file: {}", path.display())),
         _ => {
-            // 默认尝试作为 YAML 解析
-            serde_yaml::from_str(&content)
-                .context(format!("Failed to parse config file: {}", path.display()))
+            // 未知扩展名：依次尝试每种格式，全部失败时报告每种格式的错误
+            let yaml_error = match ConfigFile::parse(&content) {
+                Ok(config) => return Ok(config),
+                Err(e) => e,
+            };
+            let json_error = match ConfigFile::parse_json(&content) {
+                Ok(config) => return Ok(config),
+                Err(e) => e,
+            };
+            let toml_error = match ConfigFile::parse_toml(&content) {
+                Ok(config) => return Ok(config),
+                Err(e) => e,
+            };
+            bail!(
+                "Failed to parse config file {} as YAML ({}), JSON ({}) or TOML ({})",
+                path.display(),
+                yaml_error,
+                json_error,
+                toml_error.message()
+            )
         }
     }
 }
 
 /// 加载用户主目录中的配置文件（如果存在）
-pub fn load_user_config() -> Result<Option<Config>> {
+pub fn load_user_config() -> Result<Option<ConfigFile>> {
     if let Some(home_dir) = dirs::home_dir() {
         let user_config_path = home_dir.join(".npmcleanrc.yml");
         if user_config_path.exists() {
@@ -32,35 +117,110 @@ pub fn load_user_config() -> Result<Option<Config>> {
     Ok(None)
 }
 
+/// 对文件中出现过的每个字段，用文件中的值覆盖结果
+macro_rules! override_present {
+    ($result:ident, $file:ident, $($field:ident),+ $(,)?) => {
+        $(
+            if $file.has(stringify!($field)) {
+                $result.$field = $file.config.$field.clone();
+            }
+        )+
+    };
+}
+
 /// 合并两个配置，以第二个配置为优先
-pub fn merge_configs(base: Config, override_config: Config) -> Config {
+///
+/// 标量和可选字段只要在覆盖配置文件中出现就使用其值（包括 `false` 和 `null`），
+/// 列表字段则追加到基础配置中。
+pub fn merge_configs(base: Config, override_file: ConfigFile) -> Config {
     // 创建一个新配置，从基础配置开始
     let mut result = base;
 
-    // 合并简单字段（优先使用覆盖配置的值）
-    result.recursive = override_config.recursive || result.recursive;
-    result.force = override_config.force || result.force;
-    result.dry_run = override_config.dry_run || result.dry_run;
-    result.stats = override_config.stats || result.stats;
-    result.verbose = override_config.verbose || result.verbose;
-    result.clean_node_modules = override_config.clean_node_modules || result.clean_node_modules;
-    result.clean_build_dirs = override_config.clean_build_dirs || result.clean_build_dirs;
+    // 合并简单字段（文件中出现的键优先）
+    override_present!(
+        result,
+        override_file,
+        recursive,
+        force,
+        dry_run,
+        stats,
+        collapse,
+        relative_paths,
+        verbose,
+        quiet,
+        verbosity,
+        format,
+        color,
+        json_compact,
+        interactive,
+        confirm_default,
+        clean_node_modules,
+        clean_build_dirs,
+        clean_cache_dirs,
+        clean_coverage_dirs,
+        clean_root_node_modules,
+        unified_targets,
+        prune_node_modules_caches,
+        clean_pnpm_store,
+        deep_cache,
+        check_broken_links,
+        dedupe_by_lockfile,
+        largest_only,
+        skip_node_modules_size,
+        measure_on_delete,
+        recompute_size_on_delete,
+        size_drift_threshold,
+        strict_missing,
+        journal,
+        manifest,
+        wait_for_lock,
+        low_priority,
+        two_phase_delete,
+        trash_suffix,
+        refuse_unknown_free_space,
+        measure_free_delta,
+        check_update,
+        ignore_case,
+        respect_gitignore,
+    );
 
-    // 合并可选字段（如果覆盖配置中有值，则使用该值）
-    if override_config.max_depth.is_some() {
-        result.max_depth = override_config.max_depth;
-    }
+    // 合并可选字段（文件中出现时使用该值，显式的 null 会清除基础配置中的值）
+    override_present!(
+        result,
+        override_file,
+        max_depth,
+        node_modules_min_entries,
+        min_size,
+        confirm_above,
+        threads,
+        delete_threads,
+        timeout,
+        max_runtime,
+        match_regex,
+        created_within,
+        older_than,
+        packages_manifest,
+        report,
+        post_clean_command,
+        update_url,
+    );
 
-    if override_config.min_size.is_some() {
-        result.min_size = override_config.min_size;
-    }
+    let override_config = override_file.config;
 
-    if override_config.threads.is_some() {
-        result.threads = override_config.threads;
+    result
+        .min_size_by_type
+        .extend(override_config.min_size_by_type);
+
+    for project_type in override_config.project_types {
+        if !result.project_types.contains(&project_type) {
+            result.project_types.push(project_type);
+        }
     }
 
-    if override_config.timeout.is_some() {
-        result.timeout = override_config.timeout;
+    for project_type in override_config.exclude_types {
+        if !result.exclude_types.contains(&project_type) {
+            result.exclude_types.push(project_type);
+        }
     }
 
     // 合并列表（添加不重复的项）
@@ -77,8 +237,118 @@ pub fn merge_configs(base: Config, override_config: Config) -> Config {
         }
     }
 
+    for root in override_config.allowed_roots {
+        if !result.allowed_roots.contains(&root) {
+            result.allowed_roots.push(root);
+        }
+    }
+
+    for protected in override_config.protected_paths {
+        if !result.protected_paths.contains(&protected) {
+            result.protected_paths.push(protected);
+        }
+    }
+
     // 对于 exclude，直接添加所有项（允许重复，简化处理）
     result.exclude.extend(override_config.exclude);
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_config_in_yaml_json_and_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "npmclean.yml",
+                "recursive: true\nclean_cache_dirs: false\nexclude:\n  - vendor\nmax_depth: 3\n\
+                 timeout: 30s\nformat: json\nmin_size_by_type:\n  node_modules: 1024\n",
+            ),
+            (
+                "npmclean.json",
+                r#"{"recursive": true, "clean_cache_dirs": false, "exclude": ["vendor"],
+                    "max_depth": 3, "timeout": "30s", "format": "json",
+                    "min_size_by_type": {"node_modules": 1024}}"#,
+            ),
+            (
+                "npmclean.toml",
+                "recursive = true\nclean_cache_dirs = false\nexclude = [\"vendor\"]\nmax_depth = 3\n\
+                 timeout = \"30s\"\nformat = \"json\"\n\n[min_size_by_type]\nnode_modules = 1024\n",
+            ),
+        ];
+
+        let loaded: Vec<ConfigFile> = files
+            .iter()
+            .map(|(name, content)| {
+                let path = dir.path().join(name);
+                fs::write(&path, content).unwrap();
+                load_config_file(&path).unwrap()
+            })
+            .collect();
+
+        let yaml = serde_json::to_value(&loaded[0].config).unwrap();
+        assert!(loaded[0].config.recursive);
+        assert_eq!(loaded[0].config.max_depth, Some(3));
+        for file in &loaded[1..] {
+            assert_eq!(serde_json::to_value(&file.config).unwrap(), yaml);
+            assert_eq!(file.present_keys, loaded[0].present_keys);
+        }
+    }
+
+    #[test]
+    fn test_unknown_extension_tries_each_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".npmcleanrc");
+
+        fs::write(&path, "stats = true\n").unwrap();
+        assert!(load_config_file(&path).unwrap().config.stats);
+
+        fs::write(&path, "stats = [not valid").unwrap();
+        let error = load_config_file(&path).err().unwrap().to_string();
+        assert!(error.contains("YAML"));
+        assert!(error.contains("JSON"));
+        assert!(error.contains("TOML"));
+    }
+
+    #[test]
+    fn test_explicit_false_overrides_base() {
+        let base = merge_configs(
+            Config::default(),
+            ConfigFile::parse("clean_cache_dirs: true\nclean_coverage_dirs: true\n").unwrap(),
+        );
+        assert!(base.clean_cache_dirs);
+
+        let merged = merge_configs(
+            base,
+            ConfigFile::parse("clean_cache_dirs: false\nclean_coverage_dirs: false\n").unwrap(),
+        );
+        assert!(!merged.clean_cache_dirs);
+        assert!(!merged.clean_coverage_dirs);
+        // 未出现的键保持基础配置的值
+        assert!(merged.clean_node_modules);
+        assert!(merged.clean_build_dirs);
+    }
+
+    #[test]
+    fn test_absent_keys_keep_base_values() {
+        let base = Config {
+            force: true,
+            max_depth: Some(3),
+            ..Config::default()
+        };
+
+        let merged = merge_configs(base, ConfigFile::parse("stats: true\n").unwrap());
+        assert!(merged.force);
+        assert!(merged.stats);
+        assert_eq!(merged.max_depth, Some(3));
+
+        // 空文件不改变任何值
+        let merged = merge_configs(merged, ConfigFile::parse("").unwrap());
+        assert!(merged.force);
+        assert_eq!(merged.max_depth, Some(3));
+    }
+}
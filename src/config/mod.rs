@@ -2,9 +2,10 @@ mod loader;
 mod schema;
 
 use crate::cli::CliArgs;
+use crate::project::TargetType;
 use anyhow::{Context, Result};
 
-pub use schema::Config;
+pub use schema::{ColorMode, Config, ConfirmDefault, OutputFormat, Verbosity};
 
 /// 加载配置，按优先级从高到低：命令行参数 > 项目配置 > 用户配置 > 默认配置
 pub fn load_config(args: &CliArgs) -> Result<Config> {
@@ -35,10 +36,23 @@ pub fn load_config(args: &CliArgs) -> Result<Config> {
 
     // 应用命令行参数覆盖配置
     config = apply_cli_args(config, args);
+    validate_trash_suffix(&config.trash_suffix)?;
 
     Ok(config)
 }
 
+/// 两阶段删除的后缀必须是 npmclean 专用的：带此后缀的残留目录会被当作清理目标，
+/// `.old`、`.bak` 之类的通用后缀会把用户自己的目录当成残留
+fn validate_trash_suffix(suffix: &str) -> Result<()> {
+    if !suffix.contains("npmclean") {
+        anyhow::bail!(
+            "Invalid trash_suffix '{}': it must contain 'npmclean', e.g. '.npmclean-trash'",
+            suffix
+        );
+    }
+    Ok(())
+}
+
 /// 将命令行参数应用到配置中
 fn apply_cli_args(mut config: Config, args: &CliArgs) -> Config {
     // 基本选项
@@ -46,10 +60,93 @@ fn apply_cli_args(mut config: Config, args: &CliArgs) -> Config {
     config.force = args.force || config.force;
     config.dry_run = args.dry_run;
     config.stats = args.stats || config.stats;
-    config.verbose = args.verbose || config.verbose;
+    config.collapse = args.collapse || config.collapse;
+    config.relative_paths = args.relative || config.relative_paths;
+    config.scan_roots = args.scan_paths().to_vec();
+    config.interactive = args.interactive || config.interactive;
+    if let Some(verbosity) = Verbosity::from_occurrences(args.verbose) {
+        config.verbosity = config.verbosity.max(verbosity);
+    }
+    if config.verbose {
+        config.verbosity = config.verbosity.max(Verbosity::Verbose);
+    }
+    // 命令行的 -v 覆盖配置文件中的 quiet
+    config.quiet = (args.quiet || config.quiet) && args.verbose == 0;
+    if config.quiet {
+        config.verbosity = Verbosity::Quiet;
+    }
+    config.verbose = config.verbosity >= Verbosity::Verbose;
+    config.strict_missing = args.strict_missing || config.strict_missing;
+    if let Some(journal) = &args.journal {
+        config.journal = Some(journal.clone());
+    }
+    if let Some(manifest) = args.manifest_path() {
+        config.manifest = Some(manifest.to_path_buf());
+    }
+    if let Some(report) = &args.report {
+        config.report = Some(report.clone());
+    }
+    if let Some(command) = &args.post_clean_command {
+        config.post_clean_command = Some(command.clone());
+    }
+    config.stdin = args.stdin;
+    config.list_paths = args.list;
+    config.scan_only = args.is_scan_only();
+    if let Some(resume) = &args.resume {
+        config.resume = Some(resume.clone());
+    }
+    if args.wait {
+        config.wait_for_lock = true;
+    } else if args.no_wait {
+        config.wait_for_lock = false;
+    }
+    if args.threads.is_some() {
+        config.threads = args.threads;
+    }
+    if args.delete_threads.is_some() {
+        config.delete_threads = args.delete_threads;
+    }
+    if args.timeout.is_some() {
+        config.timeout = args.timeout;
+    }
+    if args.max_runtime.is_some() {
+        config.max_runtime = args.max_runtime;
+    }
+    config.low_priority = args.low_priority || config.low_priority;
+    config.two_phase_delete = args.two_phase_delete || config.two_phase_delete;
+    config.refuse_unknown_free_space =
+        args.refuse_unknown_free_space || config.refuse_unknown_free_space;
+    config.measure_free_delta = args.measure_free_delta || config.measure_free_delta;
+    config.check_update = args.check_update || config.check_update;
+    config.measure_on_delete = args.measure_on_delete || config.measure_on_delete;
+    config.recompute_size_on_delete =
+        args.recompute_size_on_delete || config.recompute_size_on_delete;
+    if let Some(threshold) = args.size_drift_threshold {
+        config.size_drift_threshold = threshold;
+    }
 
-    // 清理模式 - 修改逻辑，使默认清理所有目标类型
-    // 只有当用户明确指定了某一类型时，才限制为仅清理该类型
+    if let Some(format) = args.format {
+        config.format = format;
+    }
+    if let Some(color) = args.color {
+        config.color = color;
+    }
+    if args.json_compact {
+        config.json_compact = Some(true);
+    } else if args.json_pretty {
+        config.json_compact = Some(false);
+    }
+
+    if let Some(confirm_default) = args.confirm_default {
+        config.confirm_default = confirm_default;
+    }
+    // 单行摘要和只扫描的报告需要大小信息
+    if config.format == OutputFormat::Oneline || config.scan_only {
+        config.stats = true;
+    }
+
+    // 清理模式：只有明确指定了某一类型时才限制为仅清理该类型，
+    // 否则保留配置文件中的设置（默认清理所有类型）
     if args.node_modules_only {
         // 只清理 node_modules
         config.clean_node_modules = true;
@@ -62,14 +159,82 @@ fn apply_cli_args(mut config: Config, args: &CliArgs) -> Config {
         config.clean_build_dirs = true;
         config.clean_cache_dirs = false;
         config.clean_coverage_dirs = false;
-    } else {
-        // 默认情况：清理所有类型的目标
-        config.clean_node_modules = true;
-        config.clean_build_dirs = true;
-        config.clean_cache_dirs = true;
-        config.clean_coverage_dirs = true;
     }
 
+    config.unified_targets = args.unified_targets || config.unified_targets;
+    if args.keep_root_node_modules {
+        config.clean_root_node_modules = false;
+    }
+    config.ignore_case = args.ignore_case || config.ignore_case;
+    if args.no_gitignore {
+        config.respect_gitignore = false;
+    }
+
+    for project_type in &args.project_types {
+        if !config.project_types.contains(project_type) {
+            config.project_types.push(project_type.clone());
+        }
+    }
+
+    for project_type in &args.exclude_types {
+        if !config.exclude_types.contains(project_type) {
+            config.exclude_types.push(project_type.clone());
+        }
+    }
+
+    if let Some(pattern) = &args.match_regex {
+        config.match_regex = Some(pattern.clone());
+    }
+
+    if args.older_than.is_some() {
+        config.older_than = args.older_than;
+    }
+
+    if args.created_within.is_some() {
+        config.created_within = args.created_within;
+    }
+
+    if let Some(manifest) = &args.packages_manifest {
+        config.packages_manifest = Some(manifest.clone());
+    }
+
+    if args.max_depth.is_some() {
+        config.max_depth = args.max_depth;
+    }
+
+    if args.node_modules_min_entries.is_some() {
+        config.node_modules_min_entries = args.node_modules_min_entries;
+    }
+
+    if args.confirm_above.is_some() {
+        config.confirm_above = args.confirm_above;
+    }
+
+    if args.min_size.is_some() {
+        config.min_size = args.min_size;
+    }
+
+    // 按类型的最小清理大小
+    for (target_type, min_size) in [
+        (TargetType::NodeModules, args.min_size_node_modules),
+        (TargetType::BuildDir, args.min_size_build),
+        (TargetType::CacheDir, args.min_size_cache),
+        (TargetType::Coverage, args.min_size_coverage),
+    ] {
+        if let Some(min_size) = min_size {
+            config.min_size_by_type.insert(target_type, min_size);
+        }
+    }
+
+    config.prune_node_modules_caches =
+        args.prune_node_modules_caches || config.prune_node_modules_caches;
+    config.largest_only = args.largest_only || config.largest_only;
+    config.skip_node_modules_size = args.no_node_modules_size || config.skip_node_modules_size;
+    config.clean_pnpm_store = args.clean_pnpm_store || config.clean_pnpm_store;
+    config.deep_cache = args.deep_cache || config.deep_cache;
+    config.check_broken_links = args.check_broken_links || config.check_broken_links;
+    config.dedupe_by_lockfile = args.dedupe_by_lockfile || config.dedupe_by_lockfile;
+
     // 自定义包含/排除目录
     if let Some(include_str) = &args.include {
         let includes: Vec<String> = include_str
@@ -97,6 +262,10 @@ fn apply_cli_args(mut config: Config, args: &CliArgs) -> Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::project::ProjectType;
+    use clap::Parser;
+    use std::fs;
+    use std::path::PathBuf;
 
     #[test]
     fn test_default_config() {
@@ -123,20 +292,8 @@ mod tests {
         assert!(config.clean_node_modules);
         assert!(config.clean_build_dirs);
 
-        // 创建一个模拟的 CLI 参数
-        let args = CliArgs {
-            path: std::path::PathBuf::from("."),
-            recursive: false,
-            force: false,
-            dry_run: false,
-            config: None,
-            node_modules_only: true, // 只清理 node_modules
-            build: false,
-            include: None,
-            exclude: None,
-            stats: false,
-            verbose: false,
-        };
+        // 创建一个模拟的 CLI 参数，只清理 node_modules
+        let args = CliArgs::parse_from(["npmclean", "--node-modules"]);
 
         // 应用 CLI 参数
         config = apply_cli_args(config, &args);
@@ -159,4 +316,128 @@ mod tests {
             "clean_coverage_dirs should be false with node_modules_only"
         );
     }
+
+    #[test]
+    fn test_multiple_paths_become_scan_roots() {
+        let args = CliArgs::parse_from(["npmclean", "~/work", "~/personal"]);
+        let config = apply_cli_args(Config::default(), &args);
+        assert_eq!(
+            config.scan_roots,
+            vec![PathBuf::from("~/work"), PathBuf::from("~/personal")]
+        );
+
+        let args = CliArgs::parse_from(["npmclean"]);
+        assert_eq!(args.paths, vec![PathBuf::from(".")]);
+    }
+
+    #[test]
+    fn test_scan_subcommand_maps_to_scan_only() {
+        let args = CliArgs::parse_from(["npmclean", "scan", "--format", "json", "~/work"]);
+        let config = apply_cli_args(Config::default(), &args);
+        assert!(config.scan_only);
+        assert_eq!(config.format, OutputFormat::Json);
+        assert_eq!(config.scan_roots, vec![PathBuf::from("~/work")]);
+
+        // 没有子命令或使用 clean 子命令时进入清理流程
+        for args in [
+            CliArgs::parse_from(["npmclean", "-r"]),
+            CliArgs::parse_from(["npmclean", "clean", "-r"]),
+        ] {
+            let config = apply_cli_args(Config::default(), &args);
+            assert!(!config.scan_only);
+            assert!(config.recursive);
+            assert_eq!(config.scan_roots, vec![PathBuf::from(".")]);
+        }
+    }
+
+    #[test]
+    fn test_quiet_flag_does_not_imply_force() {
+        let args = CliArgs::parse_from(["npmclean", "-q"]);
+        let config = apply_cli_args(Config::default(), &args);
+        assert!(config.quiet);
+        assert_eq!(config.verbosity, Verbosity::Quiet);
+        assert!(!config.force);
+
+        // 命令行的 -v 覆盖配置文件中的 quiet
+        let file_config = Config {
+            quiet: true,
+            ..Config::default()
+        };
+        let config = apply_cli_args(file_config, &CliArgs::parse_from(["npmclean", "-v"]));
+        assert!(!config.quiet);
+        assert_eq!(config.verbosity, Verbosity::Verbose);
+
+        assert!(CliArgs::try_parse_from(["npmclean", "-q", "-v"]).is_err());
+    }
+
+    #[test]
+    fn test_config_file_target_types_survive_cli_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join(".npmcleanrc.yml");
+        fs::write(
+            &config_path,
+            "clean_cache_dirs: false\nclean_coverage_dirs: false\n",
+        )
+        .unwrap();
+        let config_arg = config_path.to_str().unwrap();
+
+        let config =
+            load_config(&CliArgs::parse_from(["npmclean", "--config", config_arg])).unwrap();
+        assert!(config.clean_node_modules);
+        assert!(config.clean_build_dirs);
+        assert!(!config.clean_cache_dirs);
+        assert!(!config.clean_coverage_dirs);
+
+        // 明确指定类型时才覆盖配置文件
+        let config = load_config(&CliArgs::parse_from([
+            "npmclean", "--config", config_arg, "--build",
+        ]))
+        .unwrap();
+        assert!(!config.clean_node_modules);
+        assert!(config.clean_build_dirs);
+    }
+
+    #[test]
+    fn test_trash_suffix_must_be_npmclean_specific() {
+        assert!(validate_trash_suffix(".npmclean-trash").is_ok());
+        assert!(validate_trash_suffix("~npmclean").is_ok());
+        for suffix in [".old", ".bak", ""] {
+            assert!(validate_trash_suffix(suffix).is_err());
+        }
+    }
+
+    #[test]
+    fn test_max_depth_flag_overrides_config() {
+        let file_config = Config {
+            max_depth: Some(5),
+            ..Config::default()
+        };
+        let args = CliArgs::parse_from(["npmclean", "--max-depth", "0"]);
+        assert_eq!(
+            apply_cli_args(file_config.clone(), &args).max_depth,
+            Some(0)
+        );
+
+        let args = CliArgs::parse_from(["npmclean"]);
+        assert_eq!(apply_cli_args(file_config, &args).max_depth, Some(5));
+    }
+
+    #[test]
+    fn test_type_filters_accept_comma_separated_lists() {
+        let args = CliArgs::parse_from(["npmclean", "--exclude-type", "Angular,nextjs"]);
+        let config = apply_cli_args(Config::default(), &args);
+
+        assert_eq!(
+            config.exclude_types,
+            vec![ProjectType::Angular, ProjectType::NextJs]
+        );
+        assert!(CliArgs::try_parse_from(["npmclean", "--exclude-type", "cobol"]).is_err());
+
+        let args = CliArgs::parse_from(["npmclean", "--only-type", "nextjs,react"]);
+        let config = apply_cli_args(Config::default(), &args);
+        assert_eq!(
+            config.project_types,
+            vec![ProjectType::NextJs, ProjectType::React]
+        );
+    }
 }
@@ -1,7 +1,13 @@
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::project::{ProjectType, TargetType};
+
 /// 应用程序的主要配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -24,9 +30,49 @@ pub struct Config {
     #[serde(default)]
     pub stats: bool,
 
+    /// 清理预览按项目类型分组汇总
+    #[serde(default)]
+    pub collapse: bool,
+
+    /// 输出中显示相对于扫描根目录的路径
+    #[serde(default)]
+    pub relative_paths: bool,
+
+    /// 扫描根目录，用于显示相对路径（仅命令行）
+    #[serde(skip)]
+    pub scan_roots: Vec<PathBuf>,
+
     #[serde(default)]
     pub verbose: bool,
 
+    /// 只输出一行释放空间的汇总，等价于 `quiet` 级别；确认提示仍会显示
+    #[serde(default)]
+    pub quiet: bool,
+
+    /// 输出详细程度，`verbose: true` 等价于 `verbose` 级别
+    #[serde(default)]
+    pub verbosity: Verbosity,
+
+    /// 输出格式
+    #[serde(default)]
+    pub format: OutputFormat,
+
+    /// 彩色输出模式
+    #[serde(default)]
+    pub color: ColorMode,
+
+    /// JSON 输出是否为单行紧凑格式，未设置时根据标准输出是否为终端决定
+    #[serde(default)]
+    pub json_compact: Option<bool>,
+
+    /// 确认前逐个勾选要删除的目标（需要终端）
+    #[serde(default)]
+    pub interactive: bool,
+
+    /// 确认提示中直接回车时的默认回答
+    #[serde(default)]
+    pub confirm_default: ConfirmDefault,
+
     // 清理选项
     #[serde(default = "default_true")]
     pub clean_node_modules: bool,
@@ -40,28 +86,419 @@ pub struct Config {
     #[serde(default = "default_true")]
     pub clean_coverage_dirs: bool,
 
+    /// 是否清理扫描根目录项目自身的 node_modules（例如递归清理单仓时根目录共享的依赖），
+    /// 为 false 时只跳过它，子项目的目标照常清理
+    #[serde(default = "default_true")]
+    pub clean_root_node_modules: bool,
+
+    /// 简化模式：缓存和覆盖率目录归入构建目录，由 `clean_build_dirs` 统一控制
+    #[serde(default)]
+    pub unified_targets: bool,
+
+    /// 额外清理的目录（相对于项目根目录），包含 glob 元字符时每个匹配的路径都是一个目标
     #[serde(default)]
     pub custom_targets: Vec<String>,
 
+    /// 只清理 node_modules 内部的缓存目录，而不删除 node_modules 本身
+    #[serde(default)]
+    pub prune_node_modules_caches: bool,
+
+    /// 保留 node_modules 时，把 pnpm 虚拟存储 `node_modules/.pnpm` 作为独立目标清理
+    #[serde(default)]
+    pub clean_pnpm_store: bool,
+
+    /// 在项目源码树中递归查找 `.cache` 目录（不进入 node_modules），作为缓存目标清理
+    #[serde(default)]
+    pub deep_cache: bool,
+
+    /// 扫描时浅层检查 node_modules 中的悬空符号链接，报告安装已损坏的项目
+    #[serde(default)]
+    pub check_broken_links: bool,
+
+    /// 比较各项目的 lockfile，估算相同依赖树改用共享存储可节省的空间（需要开启统计）
+    #[serde(default)]
+    pub dedupe_by_lockfile: bool,
+
+    /// 每个项目只清理最大的一个目标，其余目标跳过
+    #[serde(default)]
+    pub largest_only: bool,
+
+    /// 统计时不计算 node_modules 的大小（显示为未知），只统计构建和缓存等目标
+    #[serde(default)]
+    pub skip_node_modules_size: bool,
+
+    /// 局部清理模式下永远不会被删除的路径（相对于项目根目录）
+    #[serde(default = "default_protected_paths")]
+    pub protected_paths: Vec<String>,
+
+    /// 允许操作的根目录；非空时，扫描路径和清理目标都必须位于其中之一
+    #[serde(default)]
+    pub allowed_roots: Vec<PathBuf>,
+
     // 高级选项
+    /// 查找项目时的最大目录深度：0 只检查扫描根目录本身，1 再检查其直接子目录
     #[serde(default)]
     pub max_depth: Option<usize>,
 
-    #[serde(default)]
+    /// 小于该大小的目标不清理，可以写成 `100MB` 这样的字符串或字节数
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::parse::deserialize_optional_size"
+    )]
     pub min_size: Option<u64>,
 
+    /// 顶层条目少于该数量的 node_modules 不作为清理目标
+    #[serde(default)]
+    pub node_modules_min_entries: Option<usize>,
+
+    /// 按目标类型设置的最小清理大小，未设置的类型使用 `min_size`
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::parse::deserialize_size_map"
+    )]
+    pub min_size_by_type: HashMap<TargetType, u64>,
+
+    /// 大于该大小的单个目标删除前必须单独确认，即使使用了 `force`
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::parse::deserialize_optional_size"
+    )]
+    pub confirm_above: Option<u64>,
+
     #[serde(default)]
     pub threads: Option<usize>,
 
+    /// 并行删除的线程数，与扫描的线程数相互独立；未设置时使用 `threads`
     #[serde(default)]
+    pub delete_threads: Option<usize>,
+
+    /// 删除单个目标的超时时间，超时的目标记为失败并继续处理其余目标
+    #[serde(
+        default,
+        serialize_with = "crate::utils::parse::serialize_optional_duration",
+        deserialize_with = "crate::utils::parse::deserialize_optional_duration"
+    )]
     pub timeout: Option<Duration>,
 
+    /// 从启动开始计算的最长运行时间，超过后不再开始清理新的目标
+    #[serde(
+        default,
+        serialize_with = "crate::utils::parse::serialize_optional_duration",
+        deserialize_with = "crate::utils::parse::deserialize_optional_duration"
+    )]
+    pub max_runtime: Option<Duration>,
+
+    /// 自定义目标按名称匹配时不区分大小写（排除模式始终不区分大小写）
+    #[serde(default)]
+    pub ignore_case: bool,
+
+    /// 查找项目时跳过被 `.gitignore`、`.npmignore` 和全局 git 排除规则忽略的目录
+    #[serde(default = "default_true")]
+    pub respect_gitignore: bool,
+
+    /// 只处理这些类型的项目，为空时不限制；与 `exclude_types` 冲突时以排除为准
+    #[serde(default, alias = "only_types")]
+    pub project_types: Vec<ProjectType>,
+
+    /// 跳过这些类型的项目
+    #[serde(default)]
+    pub exclude_types: Vec<ProjectType>,
+
+    /// 只处理路径匹配该正则表达式的项目
+    #[serde(default)]
+    pub match_regex: Option<String>,
+
+    /// 只清理在此时间窗口内创建的构建/缓存目录
+    #[serde(
+        default,
+        serialize_with = "crate::utils::parse::serialize_optional_duration",
+        deserialize_with = "crate::utils::parse::deserialize_optional_duration"
+    )]
+    pub created_within: Option<Duration>,
+
+    /// 只处理在此时长内没有修改过源文件（不含 node_modules）的项目
+    #[serde(
+        default,
+        serialize_with = "crate::utils::parse::serialize_optional_duration",
+        deserialize_with = "crate::utils::parse::deserialize_optional_duration"
+    )]
+    pub older_than: Option<Duration>,
+
+    /// 包目录清单文件（`pnpm list --json` 或 `lerna ls --json` 的输出），设置后不再扫描文件系统
+    #[serde(default)]
+    pub packages_manifest: Option<PathBuf>,
+
+    /// 在删除时统计释放的字节数，无需事先计算大小
+    #[serde(default)]
+    pub measure_on_delete: bool,
+
+    /// 删除前重新计算目标大小，并以此计入释放空间
+    #[serde(default)]
+    pub recompute_size_on_delete: bool,
+
+    /// 重新计算的大小与扫描时相差超过该百分比时记录日志
+    #[serde(default = "default_size_drift_threshold")]
+    pub size_drift_threshold: f64,
+
+    /// 删除时目标已不存在是否视为失败（用于发现并发修改）
+    #[serde(default)]
+    pub strict_missing: bool,
+
+    /// 删除日志文件，每次删除前后追加记录
+    #[serde(default)]
+    pub journal: Option<PathBuf>,
+
+    /// 恢复清单文件；设置后目标移到暂存位置而不是删除，之后可用 `restore` 子命令恢复
+    #[serde(default)]
+    pub manifest: Option<PathBuf>,
+
+    /// 清理完成后写入的 NDJSON 报告文件，每个已清理目标一行
+    #[serde(default)]
+    pub report: Option<PathBuf>,
+
+    /// 清理成功后运行的命令，通过环境变量获得释放的字节数和项目数
+    #[serde(default)]
+    pub post_clean_command: Option<String>,
+
+    /// 从标准输入读取要清理的目录，跳过扫描和项目检测（仅命令行）
+    #[serde(skip)]
+    pub stdin: bool,
+
+    /// dry run 结束时逐行列出将会删除的目标的绝对路径（仅命令行）
+    #[serde(skip)]
+    pub list_paths: bool,
+
+    /// 只扫描并按输出格式报告项目和大小，不清理（仅命令行）
+    #[serde(skip)]
+    pub scan_only: bool,
+
+    /// 从该删除日志恢复，跳过其中已删除的目标（仅命令行）
+    #[serde(skip)]
+    pub resume: Option<PathBuf>,
+
+    /// 其他进程正在清理重叠的目录树时等待，而不是直接退出
+    #[serde(default)]
+    pub wait_for_lock: bool,
+
+    /// 降低进程的 CPU/IO 优先级，避免影响其他任务
+    #[serde(default)]
+    pub low_priority: bool,
+
+    /// 两阶段删除：先重命名为临时名称再删除
+    #[serde(default)]
+    pub two_phase_delete: bool,
+
+    /// 两阶段删除时附加到目标名称后的后缀，必须包含 `npmclean`；
+    /// 扫描路径下带此后缀的残留目录会作为清理目标一起预览和确认
+    #[serde(default = "default_trash_suffix")]
+    pub trash_suffix: String,
+
+    /// 风险模式下，无法查询可用空间的目标是否拒绝清理（`force` 时仍然清理）
+    #[serde(default)]
+    pub refuse_unknown_free_space: bool,
+
+    /// 清理前后查询可用磁盘空间，报告实际变化量
+    #[serde(default)]
+    pub measure_free_delta: bool,
+
+    /// 启动时查询是否有更新的版本（默认关闭，只有启用时才访问网络）
+    #[serde(default)]
+    pub check_update: bool,
+
+    /// 版本查询使用的发布接口，未设置时查询 crates.io
+    #[serde(default)]
+    pub update_url: Option<String>,
+
     // 内部使用，不从配置文件加载
     #[serde(skip)]
     #[allow(dead_code)]
     pub project_path: Option<PathBuf>,
 }
 
+/// 输出详细程度
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// 只显示汇总
+    Quiet,
+    /// 显示每个项目
+    #[default]
+    Normal,
+    /// 显示每个清理目标
+    Verbose,
+    /// 显示目标中的文件
+    Debug,
+}
+
+impl Verbosity {
+    /// 将 `-v` 的出现次数映射为详细程度，`-v`/`--verbose` 为 verbose，`-vv` 及以上为 debug
+    pub fn from_occurrences(count: u8) -> Option<Self> {
+        match count {
+            0 => None,
+            1 => Some(Verbosity::Verbose),
+            _ => Some(Verbosity::Debug),
+        }
+    }
+}
+
+/// 确认提示的默认回答
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmDefault {
+    /// 直接回车表示继续
+    Yes,
+    /// 直接回车表示取消
+    #[default]
+    No,
+}
+
+impl ConfirmDefault {
+    /// 提示中显示的选项，默认选项大写
+    pub fn prompt_hint(self) -> &'static str {
+        match self {
+            ConfirmDefault::Yes => "[Y/n]",
+            ConfirmDefault::No => "[y/N]",
+        }
+    }
+
+    /// 解析用户输入的一行回答；空行使用默认值，无法识别的回答视为拒绝
+    ///
+    /// 输入结束（EOF）或读取失败时不应调用此方法，调用方直接视为拒绝。
+    pub fn accepts(self, answer: &str) -> bool {
+        match answer.trim().to_lowercase().as_str() {
+            "" => self == ConfirmDefault::Yes,
+            "y" | "yes" => true,
+            _ => false,
+        }
+    }
+}
+
+/// 彩色输出模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// 标准输出是终端且未设置 `NO_COLOR` 时使用颜色
+    #[default]
+    Auto,
+    /// 总是使用颜色
+    Always,
+    /// 从不使用颜色
+    Never,
+}
+
+impl ColorMode {
+    /// 是否启用颜色；显式的 `always`/`never` 优先于 `NO_COLOR` 环境变量
+    pub fn enabled(self, no_color: bool, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => !no_color && is_terminal,
+        }
+    }
+}
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// 人类可读的彩色输出
+    #[default]
+    Text,
+    /// 单行摘要，适合状态栏；只扫描不清理
+    Oneline,
+    /// JSON 文档，列出每个目标是否被选中及原因，以及清理结果（需要 dry run 或 force）
+    Json,
+    /// Markdown 表格形式的清理结果，便于粘贴到 PR 描述中
+    Markdown,
+    /// CSV，每个项目一行大小统计（仅用于 `--scan-only`）
+    Csv,
+    /// 对齐的纯文本表格，每个项目一行大小统计（仅用于 `--scan-only`）
+    Table,
+}
+
+impl Config {
+    /// JSON 输出是否使用紧凑格式：显式设置优先，否则标准输出不是终端时使用紧凑格式
+    pub fn json_compact(&self) -> bool {
+        self.json_compact
+            .unwrap_or_else(|| !std::io::stdout().is_terminal())
+    }
+
+    /// 根据配置判断某类目标是否应该被清理
+    pub fn should_clean(&self, target_type: &TargetType) -> bool {
+        match target_type {
+            TargetType::NodeModules => self.clean_node_modules,
+            TargetType::BuildDir => self.clean_build_dirs,
+            TargetType::CacheDir | TargetType::Coverage if self.unified_targets => {
+                self.clean_build_dirs
+            }
+            TargetType::CacheDir => self.clean_cache_dirs,
+            TargetType::Coverage => self.clean_coverage_dirs,
+            TargetType::Custom(_) => true, // Custom targets are always cleaned
+        }
+    }
+
+    /// 用于显示的路径：开启相对路径时显示相对于（最近的）扫描根目录的路径，不在根目录下时显示原路径
+    pub fn display_path(&self, path: &Path) -> String {
+        if self.relative_paths
+            && let Some(relative) = self
+                .scan_roots
+                .iter()
+                .filter_map(|root| path.strip_prefix(root).ok())
+                .min_by_key(|relative| relative.components().count())
+        {
+            if relative.as_os_str().is_empty() {
+                return ".".to_string();
+            }
+            return relative.display().to_string();
+        }
+
+        path.display().to_string()
+    }
+
+    /// 获取目标类型对应的最小清理大小
+    pub fn min_size_for(&self, target_type: &TargetType) -> Option<u64> {
+        self.min_size_by_type
+            .get(target_type)
+            .copied()
+            .or(self.min_size)
+    }
+
+    /// 判断路径是否位于允许的根目录之下，比较前会规范化路径
+    ///
+    /// 只规范化父目录再拼上文件名：路径本身是符号链接时按链接所在位置判断，
+    /// 而不是链接指向的位置（删除的是链接本身）。
+    /// 未配置允许的根目录时总是允许；路径不存在或无法规范化时拒绝。
+    pub fn is_path_allowed(&self, path: &Path) -> bool {
+        if self.allowed_roots.is_empty() {
+            return true;
+        }
+
+        if fs::symlink_metadata(path).is_err() {
+            return false;
+        }
+        let canonical = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => {
+                let parent = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+                parent.canonicalize().map(|parent| parent.join(name))
+            }
+            _ => path.canonicalize(),
+        };
+        let Ok(path) = canonical else {
+            return false;
+        };
+        self.allowed_roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .any(|root| path.starts_with(root))
+    }
+}
+
 // 实现默认值
 impl Default for Config {
     fn default() -> Self {
@@ -72,16 +509,70 @@ impl Default for Config {
             force: false,
             dry_run: false,
             stats: false,
+            collapse: false,
+            relative_paths: false,
+            scan_roots: Vec::new(),
             verbose: false,
+            quiet: false,
+            verbosity: Verbosity::Normal,
+            format: OutputFormat::Text,
+            color: ColorMode::Auto,
+            json_compact: None,
+            interactive: false,
+            confirm_default: ConfirmDefault::No,
             clean_node_modules: true,
             clean_build_dirs: true,
             clean_cache_dirs: true,
             clean_coverage_dirs: true,
+            clean_root_node_modules: true,
+            unified_targets: false,
             custom_targets: Vec::new(),
+            prune_node_modules_caches: false,
+            clean_pnpm_store: false,
+            deep_cache: false,
+            check_broken_links: false,
+            dedupe_by_lockfile: false,
+            largest_only: false,
+            skip_node_modules_size: false,
+            protected_paths: default_protected_paths(),
+            allowed_roots: Vec::new(),
             max_depth: None,
+            node_modules_min_entries: None,
             min_size: None,
+            min_size_by_type: HashMap::new(),
+            confirm_above: None,
             threads: None,
+            delete_threads: None,
             timeout: None,
+            max_runtime: None,
+            ignore_case: false,
+            respect_gitignore: true,
+            project_types: Vec::new(),
+            exclude_types: Vec::new(),
+            match_regex: None,
+            created_within: None,
+            older_than: None,
+            packages_manifest: None,
+            measure_on_delete: false,
+            recompute_size_on_delete: false,
+            size_drift_threshold: default_size_drift_threshold(),
+            strict_missing: false,
+            journal: None,
+            manifest: None,
+            report: None,
+            post_clean_command: None,
+            stdin: false,
+            list_paths: false,
+            scan_only: false,
+            resume: None,
+            wait_for_lock: false,
+            low_priority: false,
+            two_phase_delete: false,
+            trash_suffix: default_trash_suffix(),
+            refuse_unknown_free_space: false,
+            measure_free_delta: false,
+            check_update: false,
+            update_url: None,
             project_path: None,
         }
     }
@@ -91,6 +582,19 @@ fn default_true() -> bool {
     true
 }
 
+fn default_trash_suffix() -> String {
+    crate::utils::fs_utils::DEFAULT_TRASH_SUFFIX.to_string()
+}
+
+fn default_size_drift_threshold() -> f64 {
+    10.0
+}
+
+/// 局部清理模式下默认受保护的路径，`.bin` 中的符号链接被删除后工具将无法运行
+pub fn default_protected_paths() -> Vec<String> {
+    vec!["node_modules/.bin".to_string()]
+}
+
 /// 默认构建目录列表
 #[allow(dead_code)]
 pub fn default_build_dirs() -> Vec<&'static str> {
@@ -115,6 +619,39 @@ pub fn default_coverage_dirs() -> Vec<&'static str> {
 mod tests {
     use super::*;
 
+    #[cfg(unix)]
+    #[test]
+    fn test_path_allowed_checks_symlink_location_not_target() {
+        use std::os::unix::fs::symlink;
+
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::create_dir_all(allowed.path().join("app/dist")).unwrap();
+        fs::create_dir_all(outside.path().join("shared/dist")).unwrap();
+        let config = Config {
+            allowed_roots: vec![allowed.path().to_path_buf()],
+            ..Config::default()
+        };
+
+        assert!(config.is_path_allowed(&allowed.path().join("app/dist")));
+        assert!(!config.is_path_allowed(&outside.path().join("shared/dist")));
+        assert!(!config.is_path_allowed(&allowed.path().join("app/missing")));
+
+        // 允许目录中指向外部的链接：删除的是链接本身，允许
+        let inner_link = allowed.path().join("app/node_modules");
+        symlink(outside.path().join("shared"), &inner_link).unwrap();
+        assert!(config.is_path_allowed(&inner_link));
+
+        // 外部指向允许目录的链接：链接位于允许目录之外，拒绝
+        let outer_link = outside.path().join("node_modules");
+        symlink(allowed.path().join("app"), &outer_link).unwrap();
+        assert!(!config.is_path_allowed(&outer_link));
+
+        // 经过符号链接的父目录时按真实位置判断
+        assert!(!config.is_path_allowed(&inner_link.join("dist")));
+        assert!(config.is_path_allowed(&outer_link.join("dist")));
+    }
+
     #[test]
     fn test_config_default() {
         let config = Config::default();
@@ -152,4 +689,32 @@ mod tests {
             "custom_targets should default to empty"
         );
     }
+
+    #[test]
+    fn test_unified_targets_follow_build_toggle() {
+        let config = Config {
+            clean_build_dirs: false,
+            unified_targets: true,
+            ..Config::default()
+        };
+        assert!(!config.should_clean(&TargetType::Coverage));
+        assert!(!config.should_clean(&TargetType::CacheDir));
+        assert!(config.should_clean(&TargetType::NodeModules));
+
+        let config = Config {
+            clean_cache_dirs: false,
+            clean_coverage_dirs: false,
+            unified_targets: true,
+            ..Config::default()
+        };
+        assert!(config.should_clean(&TargetType::Coverage));
+        assert!(config.should_clean(&TargetType::CacheDir));
+
+        // 默认的细分模式下各开关互不影响
+        let config = Config {
+            clean_build_dirs: false,
+            ..Config::default()
+        };
+        assert!(config.should_clean(&TargetType::Coverage));
+    }
 }
@@ -0,0 +1,290 @@
+use anyhow::{Result, bail};
+use console::style;
+use dialoguer::MultiSelect;
+use log::warn;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, ConfirmDefault};
+use crate::project::{CleanTarget, Project};
+use crate::utils::fs_utils::format_size;
+
+/// 请求确认时提供给确认器的清理汇总
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleaningSummary {
+    /// 有清理目标的项目数
+    pub projects: usize,
+    /// 将要清理的目标数
+    pub targets: usize,
+    /// 已知大小的目标合计字节数
+    pub estimated_bytes: u64,
+}
+
+impl CleaningSummary {
+    /// 根据配置统计将要清理的项目和目标
+    pub fn new(projects: &[Project], config: &Config) -> Self {
+        let mut summary = Self {
+            projects: 0,
+            targets: 0,
+            estimated_bytes: 0,
+        };
+
+        for project in projects {
+            let targets: Vec<_> = project
+                .detected_targets
+                .iter()
+                .filter(|t| config.should_clean(&t.target_type))
+                .collect();
+            if targets.is_empty() {
+                continue;
+            }
+
+            summary.projects += 1;
+            summary.targets += targets.len();
+            summary.estimated_bytes += targets.iter().filter_map(|t| t.size).sum::<u64>();
+        }
+
+        summary
+    }
+}
+
+/// 清理前的确认方式，命令行默认从标准输入读取，图形界面等调用方可以提供自己的实现
+pub trait Confirmer: Sync {
+    /// 返回是否继续清理
+    fn confirm(&self, summary: &CleaningSummary) -> Result<bool>;
+
+    /// 删除超过 `confirm_above` 的单个目标前调用，返回是否删除该目标
+    ///
+    /// 默认拒绝，没有实现逐个确认的确认器不会删除这些目标。
+    fn confirm_large_target(&self, _path: &Path, _size: u64) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// 回调函数也可以作为确认器，便于图形界面弹出对话框
+impl<F> Confirmer for F
+where
+    F: Fn(&CleaningSummary) -> bool + Sync,
+{
+    fn confirm(&self, summary: &CleaningSummary) -> Result<bool> {
+        Ok(self(summary))
+    }
+}
+
+/// 总是继续清理
+#[allow(dead_code)]
+pub struct AlwaysYes;
+
+impl Confirmer for AlwaysYes {
+    fn confirm(&self, _summary: &CleaningSummary) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn confirm_large_target(&self, _path: &Path, _size: u64) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+/// 总是取消清理
+#[allow(dead_code)]
+pub struct AlwaysNo;
+
+impl Confirmer for AlwaysNo {
+    fn confirm(&self, _summary: &CleaningSummary) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// 命令行确认：显示提示并从标准输入读取回答
+pub struct StdinConfirmer {
+    confirm_default: ConfirmDefault,
+    /// 目标来自标准输入时，改为从终端读取确认
+    from_terminal: bool,
+}
+
+impl StdinConfirmer {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            confirm_default: config.confirm_default,
+            from_terminal: config.stdin,
+        }
+    }
+
+    /// 显示确认提示并从输入中读取回答
+    fn read_answer(&self, input: &mut dyn BufRead) -> Result<bool> {
+        println!(
+            "{}",
+            style(format!(
+                "Do you want to proceed with cleaning? {}:",
+                self.confirm_default.prompt_hint()
+            ))
+            .bold()
+        );
+
+        Ok(read_line(input).is_some_and(|answer| self.confirm_default.accepts(&answer)))
+    }
+
+    /// 询问是否删除超过阈值的单个目标，直接回车视为拒绝
+    ///
+    /// 提示写到标准错误，避免混入 `--format json` 的输出。
+    fn read_large_target_answer(
+        &self,
+        path: &Path,
+        size: u64,
+        input: &mut dyn BufRead,
+    ) -> Result<bool> {
+        eprintln!(
+            "{}",
+            style(format!(
+                "{} is {}, above the --confirm-above threshold. Delete it? {}:",
+                path.display(),
+                format_size(size),
+                ConfirmDefault::No.prompt_hint()
+            ))
+            .bold()
+        );
+
+        Ok(read_line(input).is_some_and(|answer| ConfirmDefault::No.accepts(&answer)))
+    }
+
+    /// 用确认输入源执行 `read`：目标来自标准输入时读取终端，否则读取标准输入
+    fn with_input<T>(&self, read: impl FnOnce(&mut dyn BufRead) -> Result<T>) -> Result<T> {
+        if self.from_terminal {
+            let terminal = if cfg!(windows) { "CONIN$" } else { "/dev/tty" };
+            let Ok(terminal) = fs::File::open(terminal) else {
+                bail!("Cannot ask for confirmation while reading targets from stdin, use --force");
+            };
+            return read(&mut io::BufReader::new(terminal));
+        }
+
+        read(&mut io::stdin().lock())
+    }
+}
+
+impl Confirmer for StdinConfirmer {
+    fn confirm(&self, _summary: &CleaningSummary) -> Result<bool> {
+        self.with_input(|input| self.read_answer(input))
+    }
+
+    fn confirm_large_target(&self, path: &Path, size: u64) -> Result<bool> {
+        self.with_input(|input| self.read_large_target_answer(path, size, input))
+    }
+}
+
+/// 读取一行回答；输入已结束（EOF）或读取失败时返回 None，调用方视为拒绝，不使用默认值
+fn read_line(input: &mut dyn BufRead) -> Option<String> {
+    let mut answer = String::new();
+    match input.read_line(&mut answer) {
+        Ok(0) => {
+            warn!("No answer on input (EOF), treating it as no");
+            None
+        }
+        Ok(_) => Some(answer),
+        Err(e) => {
+            warn!("Failed to read answer: {}, treating it as no", e);
+            None
+        }
+    }
+}
+
+/// 交互式勾选要删除的目标，默认勾选按配置会清理的目标，返回勾选的目标路径
+///
+/// 标准输入或标准错误不是终端时无法交互，返回 `None`。
+pub fn select_targets(projects: &[Project], config: &Config) -> Result<Option<HashSet<PathBuf>>> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        return Ok(None);
+    }
+
+    let targets: Vec<&CleanTarget> = projects
+        .iter()
+        .flat_map(|project| &project.detected_targets)
+        .collect();
+    let items: Vec<String> = targets
+        .iter()
+        .map(|target| {
+            format!(
+                "{} [{}] ({})",
+                target.path.display(),
+                target.target_type,
+                target
+                    .size
+                    .map(format_size)
+                    .unwrap_or_else(|| "size unknown".to_string())
+            )
+        })
+        .collect();
+    let defaults: Vec<bool> = targets
+        .iter()
+        .map(|target| config.should_clean(&target.target_type))
+        .collect();
+
+    let chosen = MultiSelect::new()
+        .with_prompt("Select targets to delete (space to toggle, enter to confirm)")
+        .items(&items)
+        .defaults(&defaults)
+        .interact()?;
+
+    Ok(Some(
+        chosen
+            .into_iter()
+            .map(|index| targets[index].path.clone())
+            .collect(),
+    ))
+}
+
+/// 只保留用户勾选的目标
+pub fn apply_selection(projects: &mut [Project], selected: &HashSet<PathBuf>) {
+    for project in projects {
+        project
+            .detected_targets
+            .retain(|target| selected.contains(&target.path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_confirmation_uses_default() {
+        let confirm = |confirm_default, input: &str| {
+            let config = Config {
+                confirm_default,
+                ..Config::default()
+            };
+            StdinConfirmer::new(&config)
+                .read_answer(&mut io::Cursor::new(input))
+                .unwrap()
+        };
+
+        assert!(!confirm(ConfirmDefault::No, "\n"));
+        assert!(confirm(ConfirmDefault::Yes, "\n"));
+        assert!(confirm(ConfirmDefault::No, "y\n"));
+        assert!(!confirm(ConfirmDefault::Yes, "n\n"));
+        // 输入已结束时即使默认是 yes 也拒绝
+        assert!(!confirm(ConfirmDefault::Yes, ""));
+        assert_eq!(ConfirmDefault::Yes.prompt_hint(), "[Y/n]");
+        assert_eq!(ConfirmDefault::No.prompt_hint(), "[y/N]");
+    }
+
+    #[test]
+    fn test_large_target_confirmation_defaults_to_no() {
+        // 即使整体确认默认是 yes，大目标也需要明确回答 y
+        let config = Config {
+            confirm_default: ConfirmDefault::Yes,
+            ..Config::default()
+        };
+        let confirmer = StdinConfirmer::new(&config);
+        let confirm = |input: &str| {
+            confirmer
+                .read_large_target_answer(Path::new("huge"), 40 << 30, &mut io::Cursor::new(input))
+                .unwrap()
+        };
+
+        assert!(!confirm("\n"));
+        assert!(!confirm("n\n"));
+        assert!(confirm("y\n"));
+    }
+}
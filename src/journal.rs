@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::project::{CleanTarget, Project, TargetType};
+
+/// 日志中的事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalEvent {
+    /// 即将删除，在删除开始前写入
+    Pending,
+    /// 删除已完成
+    Deleted,
+}
+
+/// 删除日志中的一行（NDJSON）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub event: JournalEvent,
+    pub path: PathBuf,
+    pub target_type: TargetType,
+    pub timestamp: String,
+}
+
+/// 删除日志，每次删除前后各追加一行，用于中断后恢复
+pub struct Journal {
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /// 以追加模式打开日志文件，不存在时创建
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("Failed to open journal {}", path.display()))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// 追加一条记录并立即落盘
+    pub fn record(&self, event: JournalEvent, target: &CleanTarget) -> Result<()> {
+        let entry = JournalEntry {
+            event,
+            path: journal_path(&target.path),
+            target_type: target.target_type.clone(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+        };
+        let line = serde_json::to_string(&entry)?;
+
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line)?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// 读取日志中已经删除完成的目标路径
+///
+/// 中断时最后一行可能只写了一半，无法解析的行会被忽略。
+pub fn read_deleted(path: &Path) -> Result<HashSet<PathBuf>> {
+    let file = File::open(path).context(format!("Failed to open journal {}", path.display()))?;
+
+    let mut deleted = HashSet::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if let Ok(entry) = serde_json::from_str::<JournalEntry>(&line)
+            && entry.event == JournalEvent::Deleted
+        {
+            deleted.insert(entry.path);
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// 从计划清理的目标中去掉日志里已删除的目标，返回去掉的数量
+pub fn skip_completed(projects: &mut [Project], deleted: &HashSet<PathBuf>) -> usize {
+    let mut skipped = 0;
+    for project in projects {
+        project.detected_targets.retain(|target| {
+            let done = deleted.contains(&journal_path(&target.path));
+            if done {
+                skipped += 1;
+            }
+            !done
+        });
+    }
+    skipped
+}
+
+/// 日志中使用规范化后的路径，使不同工作目录下的运行能够对上
+///
+/// 目标删除后无法直接规范化，此时规范化父目录再拼接名称。
+fn journal_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = fs::canonicalize(path) {
+        return canonical;
+    }
+
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .map(|parent| parent.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaner::Cleaner;
+    use crate::config::Config;
+
+    fn project_with_targets(root: &Path, names: &[&str]) -> Project {
+        let mut project = Project::new(root.to_path_buf());
+        for name in names {
+            let path = root.join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("file.js"), "x").unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type: TargetType::BuildDir,
+                size: Some(1),
+            });
+        }
+        project
+    }
+
+    #[test]
+    fn test_resume_skips_targets_deleted_before_interruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_file = dir.path().join("journal.ndjson");
+        let project = project_with_targets(dir.path(), &["dist", "build", "out"]);
+
+        // 模拟中断：dist 已删除完成，build 只写了 pending，out 还没开始
+        {
+            let journal = Journal::open(&journal_file).unwrap();
+            let dist = &project.detected_targets[0];
+            journal.record(JournalEvent::Pending, dist).unwrap();
+            journal.record(JournalEvent::Deleted, dist).unwrap();
+            journal
+                .record(JournalEvent::Pending, &project.detected_targets[1])
+                .unwrap();
+        }
+        // 留下 dist 以验证恢复时不会再次处理它
+        let mut projects = vec![project];
+
+        let deleted = read_deleted(&journal_file).unwrap();
+        assert_eq!(skip_completed(&mut projects, &deleted), 1);
+
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let journal = Journal::open(&journal_file).unwrap();
+        let results = Cleaner::new(&config)
+            .with_journal(&journal)
+            .clean(projects)
+            .unwrap();
+
+        assert_eq!(results.cleaned_targets, 2);
+        assert!(dir.path().join("dist").exists());
+        assert!(!dir.path().join("build").exists());
+        assert!(!dir.path().join("out").exists());
+
+        // 恢复后的运行同样写入了日志
+        assert_eq!(read_deleted(&journal_file).unwrap().len(), 3);
+    }
+}
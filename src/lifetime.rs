@@ -0,0 +1,98 @@
+//! 累计释放空间的统计：每次实际清理后追加一行记录，`npmclean stats --lifetime` 读取并汇总
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 状态文件中的一行（NDJSON）：一次清理的时间和释放的空间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifetimeRun {
+    pub timestamp: String,
+    /// 释放的字节数，有目标的大小未统计时为 None（未知）
+    pub bytes_freed: Option<u64>,
+    pub targets: usize,
+}
+
+/// 状态文件的位置，与日志目录同在本地数据目录下的 `npmclean` 目录中
+pub fn state_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("npmclean")
+        .join("lifetime.ndjson")
+}
+
+/// 追加一次清理的记录
+pub fn record_run(path: &Path, bytes_freed: Option<u64>, targets: usize) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let run = LifetimeRun {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        bytes_freed,
+        targets,
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(&run)?)?;
+    Ok(())
+}
+
+/// 读取所有记录，按写入顺序排列
+///
+/// 文件不存在或无法读取时视为空，无法解析的行被忽略。
+pub fn read_runs(path: &Path) -> Vec<LifetimeRun> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// 所有记录释放的空间合计，释放空间未知的记录不计入
+pub fn total_bytes_freed(runs: &[LifetimeRun]) -> u64 {
+    runs.iter().filter_map(|run| run.bytes_freed).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_accumulate_into_lifetime_total() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("lifetime.ndjson");
+        assert!(read_runs(&path).is_empty());
+
+        record_run(&path, Some(1_000), 2).unwrap();
+        record_run(&path, Some(250), 1).unwrap();
+        record_run(&path, Some(4_000_000), 7).unwrap();
+        record_run(&path, None, 3).unwrap();
+
+        let runs = read_runs(&path);
+        assert_eq!(runs.len(), 4);
+        assert_eq!(runs[1].bytes_freed, Some(250));
+        assert_eq!(runs[2].targets, 7);
+        assert_eq!(runs[3].bytes_freed, None);
+        assert_eq!(total_bytes_freed(&runs), 4_001_250);
+    }
+
+    #[test]
+    fn test_corrupt_state_file_is_treated_as_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lifetime.ndjson");
+        fs::write(&path, "not json\n{\"bytes_freed\": \"oops\"}\n").unwrap();
+        assert!(read_runs(&path).is_empty());
+
+        // 损坏的内容不影响之后追加的记录
+        record_run(&path, Some(42), 1).unwrap();
+        assert_eq!(total_bytes_freed(&read_runs(&path)), 42);
+    }
+}
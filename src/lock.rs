@@ -0,0 +1,287 @@
+use anyhow::{Context, Result, bail};
+use fs2::FileExt;
+use log::{debug, info};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::project::Project;
+
+/// 等待其他进程释放锁时的轮询间隔
+const WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 清理锁：防止多个进程同时清理重叠的目录树
+///
+/// 每个进程在锁目录中持有 `<hash>.lock` 的排他锁，并在 `<hash>.path` 中记录扫描根目录，
+/// 其他进程据此判断根目录是否重叠。锁在 drop 时释放。
+pub struct CleanLock {
+    file: File,
+    lock_path: PathBuf,
+    root_path_file: PathBuf,
+}
+
+/// 一次加锁尝试的结果
+enum LockAttempt {
+    Acquired(CleanLock),
+    /// 与另一个进程正在清理的根目录重叠
+    Conflict(PathBuf),
+}
+
+impl CleanLock {
+    /// 获取扫描根目录的清理锁；`wait` 为 false 时遇到重叠的清理直接返回错误
+    pub fn acquire(lock_dir: &Path, root: &Path, wait: bool) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .context(format!("Failed to resolve {}", root.display()))?;
+        fs::create_dir_all(lock_dir).context(format!(
+            "Failed to create lock directory {}",
+            lock_dir.display()
+        ))?;
+
+        let mut announced = false;
+        loop {
+            match Self::try_acquire(lock_dir, &root)? {
+                LockAttempt::Acquired(lock) => {
+                    info!("Acquired clean lock for {}", root.display());
+                    return Ok(lock);
+                }
+                LockAttempt::Conflict(other) if wait => {
+                    if !announced {
+                        eprintln!(
+                            "Another npmclean process is cleaning {}, waiting for it to finish...",
+                            other.display()
+                        );
+                        announced = true;
+                    }
+                    thread::sleep(WAIT_INTERVAL);
+                }
+                LockAttempt::Conflict(other) => bail!(
+                    "Another npmclean process is cleaning {} which overlaps {} (use --wait to wait for it)",
+                    other.display(),
+                    root.display()
+                ),
+            }
+        }
+    }
+
+    /// 获取多个扫描根目录的清理锁；位于其他根目录之内的根目录由外层的锁覆盖，
+    /// 不再单独加锁（否则会与本进程自己的锁冲突）
+    pub fn acquire_all(lock_dir: &Path, roots: &[PathBuf], wait: bool) -> Result<Vec<Self>> {
+        let mut canonical = Vec::new();
+        for root in roots {
+            let root = root
+                .canonicalize()
+                .context(format!("Failed to resolve {}", root.display()))?;
+            if !canonical.contains(&root) {
+                canonical.push(root);
+            }
+        }
+
+        canonical
+            .iter()
+            .filter(|root| {
+                !canonical
+                    .iter()
+                    .any(|other| other != *root && root.starts_with(other))
+            })
+            .map(|root| Self::acquire(lock_dir, root, wait))
+            .collect()
+    }
+
+    fn try_acquire(lock_dir: &Path, root: &Path) -> Result<LockAttempt> {
+        // 全局守护锁保证“检查重叠”和“创建锁文件”是原子的
+        let guard = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_dir.join(".guard"))?;
+        guard.lock_exclusive()?;
+
+        let result = Self::try_acquire_locked(lock_dir, root);
+
+        let _ = FileExt::unlock(&guard);
+        result
+    }
+
+    fn try_acquire_locked(lock_dir: &Path, root: &Path) -> Result<LockAttempt> {
+        for entry in fs::read_dir(lock_dir)?.filter_map(|e| e.ok()) {
+            let lock_path = entry.path();
+            if lock_path.extension().is_none_or(|ext| ext != "lock") {
+                continue;
+            }
+
+            let Ok(file) = OpenOptions::new().write(true).open(&lock_path) else {
+                continue;
+            };
+            let root_path_file = lock_path.with_extension("path");
+
+            if file.try_lock_exclusive().is_ok() {
+                // 持有者已经退出，清理残留的锁文件
+                debug!("Removing stale lock {}", lock_path.display());
+                let _ = FileExt::unlock(&file);
+                let _ = fs::remove_file(&lock_path);
+                let _ = fs::remove_file(&root_path_file);
+                continue;
+            }
+
+            let other = fs::read_to_string(&root_path_file)
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            if other.as_os_str().is_empty() || other.starts_with(root) || root.starts_with(&other) {
+                return Ok(LockAttempt::Conflict(other));
+            }
+        }
+
+        let name = lock_name(root);
+        let lock_path = lock_dir.join(format!("{}.lock", name));
+        let root_path_file = lock_dir.join(format!("{}.path", name));
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+        if file.try_lock_exclusive().is_err() {
+            return Ok(LockAttempt::Conflict(root.to_path_buf()));
+        }
+        fs::write(&root_path_file, root.to_string_lossy().as_bytes())?;
+
+        Ok(LockAttempt::Acquired(CleanLock {
+            file,
+            lock_path,
+            root_path_file,
+        }))
+    }
+}
+
+impl Drop for CleanLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.root_path_file);
+        let _ = fs::remove_file(&self.lock_path);
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// 需要加锁的根目录：通常是扫描根目录；目标来自标准输入或包清单时扫描根目录与目标无关，
+/// 改为锁定实际选中目标所在的项目目录
+pub fn lock_roots(config: &Config, projects: &[Project]) -> Vec<PathBuf> {
+    if !config.stdin && config.packages_manifest.is_none() {
+        return config.scan_roots.clone();
+    }
+
+    projects
+        .iter()
+        .filter(|project| !project.detected_targets.is_empty())
+        .map(|project| {
+            if project.path.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                project.path.clone()
+            }
+        })
+        .collect()
+}
+
+/// 锁目录：优先使用本地数据目录，其次使用临时目录
+pub fn lock_directory() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("npmclean")
+        .join("locks")
+}
+
+/// 根据规范化的根目录生成锁文件名
+fn lock_name(root: &Path) -> String {
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire_all_skips_nested_roots() {
+        let locks = tempfile::tempdir().unwrap();
+        let tree = tempfile::tempdir().unwrap();
+        let nested = tree.path().join("packages/app");
+        fs::create_dir_all(&nested).unwrap();
+        let unrelated = tempfile::tempdir().unwrap();
+
+        let acquired = CleanLock::acquire_all(
+            locks.path(),
+            &[
+                nested.clone(),
+                tree.path().to_path_buf(),
+                unrelated.path().to_path_buf(),
+            ],
+            false,
+        )
+        .unwrap();
+        assert_eq!(acquired.len(), 2);
+        assert!(CleanLock::acquire(locks.path(), &nested, false).is_err());
+    }
+
+    #[test]
+    fn test_stdin_targets_lock_their_projects_not_scan_roots() {
+        use crate::project::{CleanTarget, TargetType};
+
+        let dir = tempfile::tempdir().unwrap();
+        let project = |name: &str, with_target: bool| {
+            let mut project = Project::new(dir.path().join(name));
+            if with_target {
+                project.detected_targets.push(CleanTarget {
+                    path: project.path.join("node_modules"),
+                    target_type: TargetType::NodeModules,
+                    size: None,
+                });
+            }
+            project
+        };
+        let projects = [project("web", true), project("api", false)];
+
+        let config = Config {
+            scan_roots: vec![PathBuf::from(".")],
+            ..Config::default()
+        };
+        assert_eq!(lock_roots(&config, &projects), [PathBuf::from(".")]);
+
+        let config = Config {
+            stdin: true,
+            ..config
+        };
+        assert_eq!(lock_roots(&config, &projects), [dir.path().join("web")]);
+
+        let config = Config {
+            stdin: false,
+            packages_manifest: Some(dir.path().join("packages.txt")),
+            ..config
+        };
+        assert_eq!(lock_roots(&config, &projects), [dir.path().join("web")]);
+    }
+
+    #[test]
+    fn test_overlapping_clean_is_aborted() {
+        let locks = tempfile::tempdir().unwrap();
+        let tree = tempfile::tempdir().unwrap();
+        let nested = tree.path().join("packages/app");
+        fs::create_dir_all(&nested).unwrap();
+        let unrelated = tempfile::tempdir().unwrap();
+
+        let first = CleanLock::acquire(locks.path(), tree.path(), false).unwrap();
+
+        // 相同或嵌套的根目录都会被拒绝，不相关的目录不受影响
+        assert!(CleanLock::acquire(locks.path(), tree.path(), false).is_err());
+        assert!(CleanLock::acquire(locks.path(), &nested, false).is_err());
+        let other = CleanLock::acquire(locks.path(), unrelated.path(), false).unwrap();
+
+        drop(first);
+        drop(other);
+        assert!(CleanLock::acquire(locks.path(), &nested, false).is_ok());
+    }
+}
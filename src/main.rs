@@ -1,23 +1,34 @@
 mod cleaner;
 mod cli;
 mod config;
+mod confirm;
+mod journal;
+mod lifetime;
+mod lock;
+mod manifest;
 mod plugins;
+mod post_clean;
+mod preflight;
 mod project;
+mod report;
 mod scanner;
+mod update;
 mod utils;
 
-use anyhow::Result;
-use log::{LevelFilter, info};
-use std::any::Any;
-use std::collections::HashMap;
+use anyhow::{Context, Result};
+use log::{LevelFilter, info, warn};
 use std::fs;
 use std::path::PathBuf;
+use std::time::Instant;
 
 use crate::plugins::{ExamplePlugin, HookType, PluginRegistry};
 
 fn main() -> Result<()> {
+    // 最长运行时间从启动开始计算
+    let started = Instant::now();
+
     // 初始化日志系统 - 日志输出到文件
-    let log_dir = setup_logging()?;
+    let log_dir = setup_logging();
     info!("Starting npmclean");
 
     // 初始化插件系统
@@ -26,65 +37,297 @@ fn main() -> Result<()> {
     // 解析命令行参数
     let args = cli::parse_args();
 
+    // 处理子命令，`scan` 和 `clean` 继续执行下面的扫描流程
+    if let Some(command) = &args.command
+        && !matches!(
+            command,
+            cli::Command::Scan { .. } | cli::Command::Clean { .. }
+        )
+    {
+        return run_command(command, &args);
+    }
+
     // 加载配置
     let config = config::load_config(&args)?;
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    cli::configure_colors(config.color, no_color);
 
-    // 创建上下文
-    let mut context: HashMap<String, Box<dyn Any>> = HashMap::new();
-    context.insert("config".to_string(), Box::new(config.clone()));
+    // 在创建任何工作线程之前降低优先级，使线程池继承该设置
+    if config.low_priority
+        && let Err(e) = utils::priority::lower_process_priority()
+    {
+        warn!("Failed to lower process priority: {}", e);
+        eprintln!("Warning: Failed to lower process priority: {}", e);
+    }
 
-    // 执行清理前钩子
-    if let Err(e) = plugin_registry.execute_hook(HookType::BeforeCleaning, &context) {
-        eprintln!(
-            "Warning: Plugin execution failed: {}. See log file for details.",
-            e
-        );
+    // 限制扫描和清理的并行度，未配置时使用 rayon 的默认线程数
+    if let Some(threads) = config.threads {
+        thread_pool_builder(Some(threads))
+            .build_global()
+            .context("Failed to configure the thread pool")?;
+        info!("Using {} worker threads", rayon::current_num_threads());
+    }
+
+    // 只有显式启用时才查询新版本，失败时只给出警告
+    if config.check_update {
+        let url = config
+            .update_url
+            .as_deref()
+            .unwrap_or(update::DEFAULT_UPDATE_URL);
+        let current = env!("CARGO_PKG_VERSION");
+        match update::check_for_update(url, current, update::cache_path().as_deref()) {
+            Ok(status) => eprintln!("{}", cli::format_update_status(&status, current)),
+            Err(e) => {
+                warn!("Update check failed: {:#}", e);
+                eprintln!("Warning: Update check failed: {:#}", e);
+            }
+        }
+    }
+
+    // 只读地列出单个项目 node_modules 中最大的包，不扫描也不清理
+    if let Some(project) = &args.drill {
+        let packages = scanner::largest_packages(project, args.drill_top)?;
+        cli::write_drill_report(
+            &mut std::io::stdout().lock(),
+            &project.join("node_modules"),
+            &packages,
+        )?;
+        return Ok(());
     }
 
-    // 创建扫描器并扫描项目
-    let scanner = scanner::Scanner::new(&config);
-    let projects = match scanner.scan(&args.path) {
-        Ok(projects) => projects,
+    // 创建扫描器并扫描项目，插件提供的检测器参与项目识别
+    let plugin_detectors = plugin_registry.get_project_detectors();
+    if !plugin_detectors.is_empty() {
+        info!(
+            "Loaded {} project detectors from plugins",
+            plugin_detectors.len()
+        );
+    }
+    let scanner = scanner::Scanner::new(&config)?.with_detectors(plugin_detectors);
+    let scanned = if config.stdin {
+        scanner.targets_from_reader(std::io::stdin().lock())
+    } else {
+        scanner.scan(&config.scan_roots)
+    };
+    let scanner::ScanReport {
+        mut projects,
+        stats: scan_stats,
+        hint: scan_hint,
+    } = match scanned {
+        Ok(report) => report,
         Err(e) => {
             eprintln!("Error: Failed to scan projects: {}", e);
-            eprintln!("Detailed logs can be found at {}", log_dir.display());
+            report_log_location(log_dir.as_ref());
             return Err(e);
         }
     };
+    if let Some(hint) = &scan_hint {
+        eprintln!("{}", hint);
+    }
+    if let Some(warning) = cli::format_broken_installs(&projects) {
+        eprintln!("{}", warning);
+    }
+
+    // 从中断的删除日志恢复时，跳过已删除的目标
+    if let Some(resume_path) = &config.resume {
+        let deleted = journal::read_deleted(resume_path)?;
+        let skipped = journal::skip_completed(&mut projects, &deleted);
+        info!(
+            "Resuming from {}: skipping {} already deleted targets",
+            resume_path.display(),
+            skipped
+        );
+        if config.format == config::OutputFormat::Text {
+            println!(
+                "Resuming from {}: skipping {} already deleted targets",
+                resume_path.display(),
+                skipped
+            );
+        }
+    }
+
+    // 设置了恢复清单时目标移到暂存位置而不是删除
+    let manifest = match &config.manifest {
+        Some(path) if !config.dry_run => Some(manifest::Manifest::open(path)?),
+        _ => None,
+    };
+
+    // 恢复时继续写入同一个日志
+    let journal = match config.journal.as_ref().or(config.resume.as_ref()) {
+        Some(path) if !config.dry_run => Some(journal::Journal::open(path)?),
+        _ => None,
+    };
+
+    // 单行摘要只报告可回收空间，不执行清理
+    if config.format == config::OutputFormat::Oneline {
+        println!("{}", cli::format_oneline(&projects, &config));
+        return Ok(());
+    }
+
+    // 只扫描时按输出格式报告项目，用于只读审计
+    if config.scan_only {
+        if config.format == config::OutputFormat::Markdown {
+            anyhow::bail!("--scan-only does not support --format markdown");
+        }
+        cli::write_scan_report(&mut std::io::stdout().lock(), &projects, &config)?;
+        if config.dedupe_by_lockfile
+            && config.format == config::OutputFormat::Text
+            && config.verbosity > config::Verbosity::Quiet
+        {
+            let savings = scanner::shared_store_savings(&projects);
+            if savings > 0 {
+                cli::display_shared_store_savings(savings);
+            }
+        }
+        return Ok(());
+    }
+    if matches!(
+        config.format,
+        config::OutputFormat::Csv | config::OutputFormat::Table
+    ) {
+        anyhow::bail!("--format csv and --format table require --scan-only");
+    }
+
+    // JSON 输出时标准输出只能包含 JSON 文档，无法显示确认提示
+    let json_output = config.format == config::OutputFormat::Json;
+    if json_output && !config.dry_run && !config.force {
+        anyhow::bail!("--format json requires --force or --dry-run");
+    }
+    let scanned_projects = json_output.then(|| projects.clone());
+    // 共享存储的估算只是建议，只在开启时计算，并且只在 dry run 中显示
+    let shared_store_savings = if config.dedupe_by_lockfile && config.dry_run {
+        scanner::shared_store_savings(&projects)
+    } else {
+        0
+    };
 
     // 显示扫描结果
-    if config.verbose {
+    if config.verbosity >= config::Verbosity::Verbose && !json_output {
         cli::display_scan_results(&projects, &config);
+        cli::display_scan_stats(&scan_stats);
     }
 
-    // 创建清理器并执行清理
-    let mut cleaner = cleaner::Cleaner::new(&config);
-
-    // 将插件检测器添加到清理器
-    let plugin_detectors = plugin_registry.get_project_detectors();
-    if !plugin_detectors.is_empty() {
-        info!(
-            "Loaded {} project detectors from plugins",
-            plugin_detectors.len()
+    // 执行清理前钩子
+    let context = plugins::before_cleaning_context(&config, &projects);
+    if let Err(e) = plugin_registry.execute_hook(HookType::BeforeCleaning, &context) {
+        eprintln!(
+            "Warning: Plugin execution failed: {}. See log file for details.",
+            e
         );
-        // 这里需要更新cleaner，将插件检测器集成到清理器中
-        cleaner.add_detectors(plugin_detectors);
+    }
+
+    // 实际清理前加锁，防止多个进程同时清理重叠的目录树
+    let _clean_locks = if config.dry_run {
+        Vec::new()
+    } else {
+        lock::CleanLock::acquire_all(
+            &lock::lock_directory(),
+            &lock::lock_roots(&config, &projects),
+            config.wait_for_lock,
+        )?
+    };
+
+    // 创建清理器并执行清理
+    let confirmer = confirm::StdinConfirmer::new(&config);
+    let mut cleaner = cleaner::Cleaner::new(&config)
+        .with_plugins(&plugin_registry)
+        .with_confirmer(&confirmer)
+        .with_start_time(started);
+    if let Some(journal) = &journal {
+        cleaner = cleaner.with_journal(journal);
+    }
+    if let Some(manifest) = &manifest {
+        cleaner = cleaner.with_manifest(manifest);
+    }
+    // 目标来自标准输入或清单时只处理列出的目标，不查找新出现的目标
+    let rescan = |path: &std::path::Path| scanner.rescan_project(path);
+    if !config.stdin && config.packages_manifest.is_none() {
+        cleaner = cleaner.with_rescan(&rescan);
     }
 
     let results = match cleaner.clean(projects) {
         Ok(results) => results,
         Err(e) => {
             eprintln!("Error: Failed during cleaning process: {}", e);
-            eprintln!("Detailed logs can be found at {}", log_dir.display());
+            report_log_location(log_dir.as_ref());
             return Err(e);
         }
     };
 
     // 显示清理结果
-    cli::display_clean_results(&results, &config);
+    match config.format {
+        config::OutputFormat::Markdown => print!("{}", cli::format_markdown(&results, &config)),
+        config::OutputFormat::Json => cli::write_json(
+            &mut std::io::stdout().lock(),
+            scanned_projects.as_deref().unwrap_or_default(),
+            Some(&results),
+            &config,
+            config.json_compact(),
+        )?,
+        _ => {
+            cli::display_clean_results(&results, &config);
+            if shared_store_savings > 0 && config.verbosity > config::Verbosity::Quiet {
+                cli::display_shared_store_savings(shared_store_savings);
+            }
+            if let Some(path) = &config.manifest
+                && manifest.is_some()
+                && results.cleaned_targets > 0
+            {
+                cli::display_restore_hint(path);
+            }
+        }
+    }
+
+    // 记录实际删除释放的空间，供 `stats --lifetime` 汇总；暂存到恢复清单的目标尚未释放空间，
+    // 有目标的大小未知时记录为未知而不是 0
+    let deleted_targets = results
+        .outcomes
+        .iter()
+        .filter(|outcome| outcome.status == cleaner::OutcomeStatus::Cleaned)
+        .count();
+    if !config.dry_run
+        && deleted_targets > 0
+        && let Err(e) = lifetime::record_run(
+            &lifetime::state_path(),
+            (results.unmeasured_targets == 0).then_some(results.total_bytes_removed),
+            deleted_targets,
+        )
+    {
+        warn!("Failed to record lifetime statistics: {:#}", e);
+    }
+
+    // GitHub Actions 中自动把 Markdown 摘要写入工作流摘要页面，用户取消时不写入
+    let step_summary = std::env::var_os(report::GITHUB_STEP_SUMMARY)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
+    if let Some(markdown) = report::github_step_summary(&results, &config)
+        && let Err(e) = report::append_github_step_summary(step_summary.as_deref(), &markdown)
+    {
+        eprintln!("Warning: Failed to write the GitHub step summary: {:#}", e);
+    }
+
+    if let Some(report_path) = &config.report {
+        report::write_ndjson_report(&results, config.dry_run, report_path)?;
+    }
+
+    if let Some(command) = &config.post_clean_command {
+        if results.failed_targets > 0 {
+            eprintln!(
+                "Warning: Skipping post-clean command because {} targets could not be cleaned",
+                results.failed_targets
+            );
+        } else if let Err(e) = post_clean::run_post_clean_command(
+            command,
+            &results,
+            config.dry_run,
+            &utils::command::SystemRunner,
+        ) {
+            eprintln!("Warning: Post-clean command failed: {:#}", e);
+        }
+    }
 
     // 执行清理后钩子
-    context.insert("results".to_string(), Box::new(results));
+    let context = plugins::after_cleaning_context(&config, &results);
     if let Err(e) = plugin_registry.execute_hook(HookType::AfterCleaning, &context) {
         eprintln!(
             "Warning: Plugin execution failed: {}. See log file for details.",
@@ -96,6 +339,63 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// 执行子命令
+fn run_command(command: &cli::Command, args: &cli::CliArgs) -> Result<()> {
+    match command {
+        cli::Command::MergeReport { output, reports } => {
+            let merged = report::merge_report_files(reports)?;
+            report::write_merged_report(&merged, output)?;
+            cli::display_merged_report(&merged, output);
+            Ok(())
+        }
+        cli::Command::Preflight { path } => {
+            let path = path.as_ref().unwrap_or(&args.paths[0]);
+            let report = preflight::run_preflight(config::load_config(args), path);
+            cli::display_preflight_report(&report);
+            if !report.passed() {
+                anyhow::bail!("Preflight checks failed");
+            }
+            Ok(())
+        }
+        cli::Command::Restore { manifest } => {
+            let summary = manifest::restore(manifest)?;
+            cli::display_restore_summary(&summary);
+            if !summary.failed.is_empty() {
+                anyhow::bail!("{} targets could not be restored", summary.failed.len());
+            }
+            Ok(())
+        }
+        cli::Command::Purge { manifest } => {
+            let summary = manifest::purge(manifest)?;
+            cli::display_purge_summary(&summary);
+            if !summary.failed.is_empty() {
+                anyhow::bail!(
+                    "{} staged targets could not be purged",
+                    summary.failed.len()
+                );
+            }
+            Ok(())
+        }
+        cli::Command::Stats { .. } => {
+            let runs = lifetime::read_runs(&lifetime::state_path());
+            cli::write_lifetime_stats(&mut std::io::stdout().lock(), &runs)?;
+            Ok(())
+        }
+        cli::Command::Scan { .. } | cli::Command::Clean { .. } => {
+            unreachable!("scan and clean run the main scan flow")
+        }
+    }
+}
+
+/// 根据配置创建线程池构建器，`None` 保持 rayon 的默认行为
+fn thread_pool_builder(threads: Option<usize>) -> rayon::ThreadPoolBuilder {
+    let builder = rayon::ThreadPoolBuilder::new();
+    match threads {
+        Some(threads) => builder.num_threads(threads),
+        None => builder,
+    }
+}
+
 /// 初始化插件系统
 fn initialize_plugins() -> Result<PluginRegistry> {
     let mut registry = PluginRegistry::new();
@@ -115,14 +415,14 @@ fn initialize_plugins() -> Result<PluginRegistry> {
 }
 
 /// 设置日志系统，将日志输出到文件
-fn setup_logging() -> Result<PathBuf> {
-    // 创建日志目录
-    let log_dir = get_log_directory()?;
-    fs::create_dir_all(&log_dir)?;
-
-    // 生成日志文件名
-    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let log_file = log_dir.join(format!("npmclean_{}.log", timestamp));
+///
+/// 日志只是辅助功能：当数据目录和临时目录都不可写时，不安装任何日志记录器
+/// 并返回 `None`，程序照常运行。
+fn setup_logging() -> Option<PathBuf> {
+    let Some((log_dir, log_file)) = open_log_file(&log_directory_candidates()) else {
+        eprintln!("Warning: No writable log directory found, file logging is disabled");
+        return None;
+    };
 
     // 配置文件日志记录器
     let file_logger = fern::Dispatch::new()
@@ -140,25 +440,91 @@ fn setup_logging() -> Result<PathBuf> {
         } else {
             LevelFilter::Info
         })
-        .chain(fern::log_file(log_file)?);
+        .chain(log_file);
 
     // 应用日志配置
-    file_logger.apply()?;
+    if let Err(e) = file_logger.apply() {
+        eprintln!(
+            "Warning: Failed to initialize logging ({}), file logging is disabled",
+            e
+        );
+        return None;
+    }
+
+    Some(log_dir)
+}
+
+/// 在候选目录中依次尝试创建日志文件，返回第一个成功的目录和文件
+fn open_log_file(candidates: &[PathBuf]) -> Option<(PathBuf, fs::File)> {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+
+    candidates.iter().find_map(|log_dir| {
+        fs::create_dir_all(log_dir).ok()?;
+        let log_file = log_dir.join(format!("npmclean_{}.log", timestamp));
+        let file = fern::log_file(log_file).ok()?;
+        Some((log_dir.clone(), file))
+    })
+}
 
-    Ok(log_dir)
+/// 日志目录候选列表：优先使用本地数据目录，其次使用临时目录
+fn log_directory_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Some(data_dir) = dirs::data_local_dir() {
+        candidates.push(data_dir.join("npmclean").join("logs"));
+    }
+    candidates.push(std::env::temp_dir().join("npmclean").join("logs"));
+
+    candidates
 }
 
-/// 获取日志目录路径
-fn get_log_directory() -> Result<PathBuf> {
-    let mut log_dir = dirs::data_local_dir()
-        .ok_or_else(|| anyhow::anyhow!("Could not determine local data directory"))?
-        .join("npmclean")
-        .join("logs");
+/// 在出错时提示日志位置
+fn report_log_location(log_dir: Option<&PathBuf>) {
+    match log_dir {
+        Some(log_dir) => eprintln!("Detailed logs can be found at {}", log_dir.display()),
+        None => eprintln!("File logging is disabled, no detailed logs are available"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_log_file_falls_back_to_next_candidate() {
+        let dir = tempfile::tempdir().unwrap();
+        // 父路径是普通文件，无法在其下创建目录
+        let blocker = dir.path().join("blocker");
+        fs::write(&blocker, "").unwrap();
+        let writable = dir.path().join("logs");
 
-    // 如果不存在则使用临时目录
-    if !log_dir.exists() && fs::create_dir_all(&log_dir).is_err() {
-        log_dir = std::env::temp_dir().join("npmclean").join("logs");
+        let (log_dir, _file) = open_log_file(&[blocker.join("logs"), writable.clone()]).unwrap();
+
+        assert_eq!(log_dir, writable);
     }
 
-    Ok(log_dir)
+    #[test]
+    fn test_thread_pool_uses_configured_threads() {
+        let pool = thread_pool_builder(Some(3)).build().unwrap();
+        assert_eq!(pool.install(rayon::current_num_threads), 3);
+
+        let pool = thread_pool_builder(None).build().unwrap();
+        assert_eq!(
+            pool.install(rayon::current_num_threads),
+            rayon::ThreadPoolBuilder::new()
+                .build()
+                .unwrap()
+                .current_num_threads()
+        );
+    }
+
+    #[test]
+    fn test_open_log_file_without_writable_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("blocker");
+        fs::write(&blocker, "").unwrap();
+
+        assert!(open_log_file(&[blocker.join("logs")]).is_none());
+        assert!(open_log_file(&[]).is_none());
+    }
 }
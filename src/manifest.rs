@@ -0,0 +1,285 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::project::{CleanTarget, TargetType};
+use crate::utils::fs_utils::remove_directory_counting;
+
+/// 目标被移到暂存位置时附加到目录名后的后缀
+///
+/// 与两阶段删除的后缀不同，启动时的残留清除不会删除暂存的目标。
+pub const STAGED_SUFFIX: &str = ".npmclean-staged";
+
+/// 恢复清单中的一行（NDJSON）：目标的原路径和暂存路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub original: PathBuf,
+    pub staged: PathBuf,
+    pub target_type: TargetType,
+    pub size: Option<u64>,
+    pub timestamp: String,
+}
+
+/// 恢复清单：目标不再直接删除，而是重命名到同一目录下的暂存位置并记录下来，
+/// 之后可以用 `npmclean restore --manifest <path>` 移回原处
+pub struct Manifest {
+    file: Mutex<File>,
+}
+
+impl Manifest {
+    /// 以追加模式打开清单文件，不存在时创建
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("Failed to open manifest {}", path.display()))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// 先写入清单再把目标移到暂存位置，返回暂存路径
+    ///
+    /// 暂存位置与目标在同一目录下，重命名是原子操作且不会跨卷。
+    pub fn stage(&self, target: &CleanTarget) -> Result<PathBuf> {
+        let original = std::path::absolute(&target.path)?;
+        let staged = staging_path(&original)?;
+        let entry = ManifestEntry {
+            original: original.clone(),
+            staged: staged.clone(),
+            target_type: target.target_type.clone(),
+            size: target.size,
+            timestamp: chrono::Local::now().to_rfc3339(),
+        };
+        let line = serde_json::to_string(&entry)?;
+
+        {
+            let mut file = self.file.lock().unwrap();
+            writeln!(file, "{}", line)?;
+            file.sync_data()?;
+        }
+
+        fs::rename(&original, &staged).context(format!(
+            "Failed to move {} to {}",
+            original.display(),
+            staged.display()
+        ))?;
+        Ok(staged)
+    }
+}
+
+/// 目标旁边尚未被占用的暂存路径：`<name>.npmclean-staged`，已存在时追加序号
+fn staging_path(path: &Path) -> Result<PathBuf> {
+    let Some(name) = path.file_name() else {
+        bail!("Invalid directory path: {}", path.display());
+    };
+
+    (0..)
+        .map(|n| {
+            let mut staged = name.to_os_string();
+            staged.push(STAGED_SUFFIX);
+            if n > 0 {
+                staged.push(format!("-{}", n));
+            }
+            path.with_file_name(staged)
+        })
+        .find(|staged| fs::symlink_metadata(staged).is_err())
+        .context("No free staging path")
+}
+
+/// 按清单恢复的结果
+#[derive(Debug, Default)]
+pub struct RestoreSummary {
+    /// 已移回原处的目标
+    pub restored: Vec<PathBuf>,
+    /// 暂存目录已不存在（已恢复或已被删除）的条目数
+    pub already_gone: usize,
+    /// 无法恢复的目标及原因（例如原路径已被重新创建）
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// 把清单中仍在暂存位置的目标移回原路径
+///
+/// 可以重复执行：已恢复的条目暂存目录不存在，会被跳过。无法解析的行被忽略。
+pub fn restore(manifest_path: &Path) -> Result<RestoreSummary> {
+    let file = File::open(manifest_path).context(format!(
+        "Failed to open manifest {}",
+        manifest_path.display()
+    ))?;
+
+    let mut summary = RestoreSummary::default();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let Ok(entry) = serde_json::from_str::<ManifestEntry>(&line) else {
+            continue;
+        };
+
+        if fs::symlink_metadata(&entry.staged).is_err() {
+            summary.already_gone += 1;
+            continue;
+        }
+        if fs::symlink_metadata(&entry.original).is_ok() {
+            summary.failed.push((
+                entry.original,
+                "the original path exists again, not overwriting it".to_string(),
+            ));
+            continue;
+        }
+
+        match fs::rename(&entry.staged, &entry.original) {
+            Ok(()) => summary.restored.push(entry.original),
+            Err(e) => summary.failed.push((entry.original, e.to_string())),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// 按清单永久删除暂存目录的结果
+#[derive(Debug, Default)]
+pub struct PurgeSummary {
+    /// 已删除的暂存目录
+    pub purged: Vec<PathBuf>,
+    /// 释放的字节数
+    pub bytes_freed: u64,
+    /// 暂存目录已不存在（已恢复或已被删除）的条目数
+    pub already_gone: usize,
+    /// 无法删除的暂存目录及原因
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// 永久删除清单中仍在暂存位置的目标，之后无法再恢复
+///
+/// 只删除清单中记录的、名称带暂存后缀的目录。可以重复执行。
+pub fn purge(manifest_path: &Path) -> Result<PurgeSummary> {
+    let file = File::open(manifest_path).context(format!(
+        "Failed to open manifest {}",
+        manifest_path.display()
+    ))?;
+
+    let mut summary = PurgeSummary::default();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let Ok(entry) = serde_json::from_str::<ManifestEntry>(&line) else {
+            continue;
+        };
+
+        let is_staged = entry
+            .staged
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().contains(STAGED_SUFFIX));
+        if !is_staged || fs::symlink_metadata(&entry.staged).is_err() {
+            summary.already_gone += 1;
+            continue;
+        }
+
+        match remove_directory_counting(&entry.staged) {
+            Ok(freed) => {
+                summary.bytes_freed += freed;
+                summary.purged.push(entry.staged);
+            }
+            Err(e) => summary.failed.push((entry.staged, e.to_string())),
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaner::Cleaner;
+    use crate::config::Config;
+    use crate::project::Project;
+
+    #[test]
+    fn test_restore_moves_staged_targets_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_file = dir.path().join("restore.ndjson");
+        let dist = dir.path().join("dist");
+        fs::create_dir_all(dist.join("assets")).unwrap();
+        fs::write(dist.join("index.html"), "<html></html>").unwrap();
+        fs::write(dist.join("assets/app.js"), "console.log(1)").unwrap();
+
+        let mut project = Project::new(dir.path().to_path_buf());
+        project.detected_targets.push(CleanTarget {
+            path: dist.clone(),
+            target_type: TargetType::BuildDir,
+            size: Some(27),
+        });
+
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let manifest = Manifest::open(&manifest_file).unwrap();
+        let results = Cleaner::new(&config)
+            .with_manifest(&manifest)
+            .clean(vec![project])
+            .unwrap();
+        assert_eq!(results.cleaned_targets, 1);
+        // 暂存的目标没有释放空间
+        assert_eq!(results.total_bytes_removed, 0);
+        assert_eq!(results.staged_bytes, 27);
+        assert!(!dist.exists());
+        assert!(dir.path().join("dist.npmclean-staged").exists());
+
+        let summary = restore(&manifest_file).unwrap();
+        assert_eq!(summary.restored, vec![dist.clone()]);
+        assert!(summary.failed.is_empty());
+        assert_eq!(
+            fs::read_to_string(dist.join("index.html")).unwrap(),
+            "<html></html>"
+        );
+        assert_eq!(
+            fs::read_to_string(dist.join("assets/app.js")).unwrap(),
+            "console.log(1)"
+        );
+        assert!(!dir.path().join("dist.npmclean-staged").exists());
+
+        // 再次恢复时没有需要处理的条目
+        let summary = restore(&manifest_file).unwrap();
+        assert!(summary.restored.is_empty());
+        assert_eq!(summary.already_gone, 1);
+    }
+
+    #[test]
+    fn test_purge_deletes_staged_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_file = dir.path().join("restore.ndjson");
+        let dist = dir.path().join("dist");
+        fs::create_dir_all(&dist).unwrap();
+        fs::write(dist.join("index.html"), vec![b'x'; 40]).unwrap();
+
+        let manifest = Manifest::open(&manifest_file).unwrap();
+        let staged = manifest
+            .stage(&CleanTarget {
+                path: dist.clone(),
+                target_type: TargetType::BuildDir,
+                size: Some(40),
+            })
+            .unwrap();
+        assert!(staged.exists());
+
+        let summary = purge(&manifest_file).unwrap();
+        assert_eq!(summary.purged, vec![staged.clone()]);
+        assert_eq!(summary.bytes_freed, 40);
+        assert!(!staged.exists());
+        assert!(!dist.exists());
+
+        let summary = purge(&manifest_file).unwrap();
+        assert!(summary.purged.is_empty());
+        assert_eq!(summary.already_gone, 1);
+    }
+}
@@ -3,7 +3,7 @@ use log::info;
 use std::any::Any;
 use std::collections::HashMap;
 
-use crate::plugins::{HookType, Plugin};
+use crate::plugins::{CONTEXT_PROJECT, HookType, Plugin};
 use crate::project::{Project, ProjectDetector, ProjectType};
 
 /// 示例插件
@@ -50,13 +50,13 @@ impl Plugin for ExamplePlugin {
                 info!("Example plugin: After cleaning");
             }
             HookType::BeforeCleanProject => {
-                if let Some(project) = context.get("project") {
-                    if let Some(project) = project.downcast_ref::<Project>() {
-                        info!(
-                            "Example plugin: Preparing to clean project {}",
-                            project.path.display()
-                        );
-                    }
+                if let Some(project) = context.get(CONTEXT_PROJECT)
+                    && let Some(project) = project.downcast_ref::<Project>()
+                {
+                    info!(
+                        "Example plugin: Preparing to clean project {}",
+                        project.path.display()
+                    );
                 }
             }
             HookType::AfterCleanProject => {
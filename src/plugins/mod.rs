@@ -2,23 +2,140 @@
 mod examples;
 mod registry;
 
+use std::any::Any;
+use std::collections::HashMap;
+
 pub use examples::ExamplePlugin;
 pub use registry::{Plugin, PluginRegistry};
 
+use crate::cleaner::CleanResults;
+use crate::config::Config;
+use crate::project::{CleanTarget, Project};
+
+/// 钩子上下文中的键：`Config`
+pub const CONTEXT_CONFIG: &str = "config";
+/// 钩子上下文中的键：`Vec<Project>`
+pub const CONTEXT_PROJECTS: &str = "projects";
+/// 钩子上下文中的键：当前的 `Project`
+pub const CONTEXT_PROJECT: &str = "project";
+/// 钩子上下文中的键：当前的 `CleanTarget`
+pub const CONTEXT_TARGET: &str = "target";
+/// 钩子上下文中的键：`CleanResults`
+pub const CONTEXT_RESULTS: &str = "results";
+
 /// 插件钩子类型
+///
+/// 每种钩子的上下文内容是固定的，插件可以放心地按键取值并 `downcast_ref`。
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub enum HookType {
-    /// 在清理开始之前调用
+    /// 在清理开始之前调用，上下文包含 `config` 和 `projects`
     BeforeCleaning,
-    /// 在清理结束之后调用
+    /// 在清理结束之后调用，上下文包含 `config` 和 `results`
     AfterCleaning,
-    /// 在每个项目清理之前调用
+    /// 在每个项目清理之前调用，上下文包含 `project`
     BeforeCleanProject,
-    /// 在每个项目清理之后调用
+    /// 在每个项目清理之后调用，上下文包含 `project`
     AfterCleanProject,
-    /// 在每个目标清理之前调用
+    /// 在每个目标清理之前调用，上下文包含 `project` 和 `target`
     BeforeCleanTarget,
-    /// 在每个目标清理之后调用
+    /// 在每个目标清理之后调用，上下文包含 `project` 和 `target`
     AfterCleanTarget,
 }
+
+/// 钩子上下文
+pub type HookContext = HashMap<String, Box<dyn Any>>;
+
+/// 构建 `BeforeCleaning` 钩子的上下文
+pub fn before_cleaning_context(config: &Config, projects: &[Project]) -> HookContext {
+    let mut context = HookContext::new();
+    context.insert(CONTEXT_CONFIG.to_string(), Box::new(config.clone()));
+    context.insert(CONTEXT_PROJECTS.to_string(), Box::new(projects.to_vec()));
+    context
+}
+
+/// 构建 `AfterCleaning` 钩子的上下文
+pub fn after_cleaning_context(config: &Config, results: &CleanResults) -> HookContext {
+    let mut context = HookContext::new();
+    context.insert(CONTEXT_CONFIG.to_string(), Box::new(config.clone()));
+    context.insert(CONTEXT_RESULTS.to_string(), Box::new(results.clone()));
+    context
+}
+
+/// 构建项目级钩子的上下文
+pub fn project_context(project: &Project) -> HookContext {
+    let mut context = HookContext::new();
+    context.insert(CONTEXT_PROJECT.to_string(), Box::new(project.clone()));
+    context
+}
+
+/// 构建目标级钩子的上下文
+pub fn target_context(project: &Project, target: &CleanTarget) -> HookContext {
+    let mut context = project_context(project);
+    context.insert(CONTEXT_TARGET.to_string(), Box::new(target.clone()));
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    /// 记录 `BeforeCleaning` 中看到的项目路径
+    struct RecordingPlugin {
+        seen: Arc<Mutex<Vec<PathBuf>>>,
+    }
+
+    impl Plugin for RecordingPlugin {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn version(&self) -> &str {
+            "0.0.0"
+        }
+
+        fn description(&self) -> &str {
+            "Records the projects it is given"
+        }
+
+        fn execute_hook(&self, hook_type: HookType, context: &HookContext) -> Result<()> {
+            if let HookType::BeforeCleaning = hook_type {
+                let projects = context
+                    .get(CONTEXT_PROJECTS)
+                    .and_then(|p| p.downcast_ref::<Vec<Project>>())
+                    .expect("projects should be in the BeforeCleaning context");
+                let mut seen = self.seen.lock().unwrap();
+                seen.extend(projects.iter().map(|p| p.path.clone()));
+
+                assert!(context.get(CONTEXT_CONFIG).is_some());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plugin_reads_projects_before_cleaning() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = PluginRegistry::new();
+        registry
+            .register(Box::new(RecordingPlugin { seen: seen.clone() }))
+            .unwrap();
+
+        let projects = vec![
+            Project::new(PathBuf::from("/work/a")),
+            Project::new(PathBuf::from("/work/b")),
+        ];
+        let context = before_cleaning_context(&Config::default(), &projects);
+        registry
+            .execute_hook(HookType::BeforeCleaning, &context)
+            .unwrap();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![PathBuf::from("/work/a"), PathBuf::from("/work/b")]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use log::info;
+
+use crate::cleaner::CleanResults;
+use crate::utils::command::CommandRunner;
+use crate::utils::parse::parse_command_line;
+
+/// 清理后命令可用的环境变量
+pub fn post_clean_env(results: &CleanResults) -> Vec<(String, String)> {
+    vec![
+        (
+            "NPMCLEAN_FREED_BYTES".to_string(),
+            results.total_bytes_removed.to_string(),
+        ),
+        (
+            "NPMCLEAN_PROJECT_COUNT".to_string(),
+            results.cleaned_projects.to_string(),
+        ),
+        (
+            "NPMCLEAN_TARGET_COUNT".to_string(),
+            results.cleaned_targets.to_string(),
+        ),
+    ]
+}
+
+/// 清理成功后运行用户配置的命令，dry run 时只打印命令
+///
+/// 用户取消清理或没有清理任何目标时不运行。
+/// 命令不经过 shell 执行，需要管道等 shell 特性时可以写成 `sh -c '...'`。
+pub fn run_post_clean_command(
+    command_line: &str,
+    results: &CleanResults,
+    dry_run: bool,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    let argv = parse_command_line(command_line)?;
+    if results.cancelled || results.cleaned_targets == 0 {
+        info!("Nothing was cleaned, skipping post-clean command");
+        return Ok(());
+    }
+    if dry_run {
+        println!("[DRY RUN] Would run post-clean command: {}", command_line);
+        return Ok(());
+    }
+
+    info!("Running post-clean command: {}", command_line);
+    let output = runner.run(&argv[0], &argv[1..], &post_clean_env(results))?;
+    print!("{}", output);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaner::Cleaner;
+    use crate::config::Config;
+    use crate::confirm::CleaningSummary;
+    use crate::project::{CleanTarget, Project, TargetType};
+    use crate::utils::command::CommandError;
+    use std::cell::RefCell;
+    use std::fs;
+
+    /// 一次调用的程序、参数和环境变量
+    type Call = (String, Vec<String>, Vec<(String, String)>);
+
+    /// 记录调用而不启动进程的命令执行器
+    #[derive(Default)]
+    struct RecordingRunner {
+        calls: RefCell<Vec<Call>>,
+    }
+
+    impl CommandRunner for RecordingRunner {
+        fn run(
+            &self,
+            program: &str,
+            args: &[String],
+            envs: &[(String, String)],
+        ) -> Result<String, CommandError> {
+            self.calls
+                .borrow_mut()
+                .push((program.to_string(), args.to_vec(), envs.to_vec()));
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn test_post_clean_command_receives_results_in_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        project.detected_targets.push(CleanTarget {
+            path: dir.path().join("dist"),
+            target_type: TargetType::BuildDir,
+            size: Some(2048),
+        });
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+
+        let runner = RecordingRunner::default();
+        run_post_clean_command("notify-send 'npmclean done'", &results, false, &runner).unwrap();
+
+        let calls = runner.calls.borrow();
+        assert_eq!(calls.len(), 1);
+        let (program, args, envs) = &calls[0];
+        assert_eq!(program, "notify-send");
+        assert_eq!(args, &["npmclean done"]);
+        let env = |key: &str| envs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        assert_eq!(env("NPMCLEAN_FREED_BYTES"), Some("2048"));
+        assert_eq!(env("NPMCLEAN_PROJECT_COUNT"), Some("1"));
+        assert_eq!(env("NPMCLEAN_TARGET_COUNT"), Some("1"));
+
+        // dry run 只打印命令，不执行
+        let runner = RecordingRunner::default();
+        run_post_clean_command("notify-send done", &results, true, &runner).unwrap();
+        assert!(runner.calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_post_clean_command_skipped_when_nothing_was_cleaned() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = || {
+            let mut project = Project::new(dir.path().to_path_buf());
+            fs::create_dir_all(dir.path().join("dist")).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path: dir.path().join("dist"),
+                target_type: TargetType::BuildDir,
+                size: Some(2048),
+            });
+            project
+        };
+
+        // 用户拒绝确认
+        let declining = |_: &CleaningSummary| false;
+        let results = Cleaner::new(&Config::default())
+            .with_confirmer(&declining)
+            .clean(vec![project()])
+            .unwrap();
+        assert!(results.cancelled);
+        let runner = RecordingRunner::default();
+        run_post_clean_command("notify-send done", &results, false, &runner).unwrap();
+        assert!(runner.calls.borrow().is_empty());
+
+        // 没有任何目标
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config)
+            .clean(vec![Project::new(dir.path().to_path_buf())])
+            .unwrap();
+        let runner = RecordingRunner::default();
+        run_post_clean_command("notify-send done", &results, false, &runner).unwrap();
+        assert!(runner.calls.borrow().is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::utils::command::{CommandError, run_command};
+use crate::utils::fs_utils::{FreeSpaceProbe, SystemFreeSpace, format_size};
+
+/// 单项检查的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// 单项预检结果
+#[derive(Debug, Clone)]
+pub struct PreflightCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// 预检报告
+#[derive(Debug, Clone)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+    /// 合并所有来源后的有效配置，配置无效时为 None
+    pub effective_config: Option<Config>,
+}
+
+impl PreflightReport {
+    /// 所有检查都没有失败
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+
+    fn push(&mut self, name: &str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(PreflightCheck {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        });
+    }
+}
+
+/// 运行预检：验证配置、扫描路径和外部工具，不扫描也不清理
+pub fn run_preflight(config: Result<Config>, scan_path: &Path) -> PreflightReport {
+    let mut report = PreflightReport {
+        checks: Vec::new(),
+        effective_config: None,
+    };
+
+    let risky_mode = config.as_ref().map(|c| c.two_phase_delete).unwrap_or(false);
+    match config {
+        Ok(config) => {
+            report.push("config", CheckStatus::Pass, "Configuration is valid");
+            report.effective_config = Some(config);
+        }
+        Err(e) => report.push("config", CheckStatus::Fail, format!("{:#}", e)),
+    }
+
+    check_scan_path(&mut report, scan_path);
+    if scan_path.is_dir() {
+        check_free_space(&mut report, scan_path, risky_mode, &SystemFreeSpace);
+    }
+    check_tools(&mut report);
+
+    report
+}
+
+/// 检查扫描路径存在、是目录并且可写
+fn check_scan_path(report: &mut PreflightReport, scan_path: &Path) {
+    if !scan_path.exists() {
+        report.push(
+            "scan path",
+            CheckStatus::Fail,
+            format!("{} does not exist", scan_path.display()),
+        );
+        return;
+    }
+
+    if !scan_path.is_dir() {
+        report.push(
+            "scan path",
+            CheckStatus::Fail,
+            format!("{} is not a directory", scan_path.display()),
+        );
+        return;
+    }
+
+    report.push(
+        "scan path",
+        CheckStatus::Pass,
+        format!("{} exists", scan_path.display()),
+    );
+
+    // 通过实际创建文件来检测写权限，比检查权限位更可靠
+    let probe = scan_path.join(format!(".npmclean-preflight-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            report.push(
+                "writable",
+                CheckStatus::Pass,
+                format!("{} is writable", scan_path.display()),
+            );
+        }
+        Err(e) => report.push(
+            "writable",
+            CheckStatus::Fail,
+            format!("{} is not writable: {}", scan_path.display(), e),
+        ),
+    }
+}
+
+/// 检查扫描路径所在卷的可用空间；只有风险模式（两阶段删除）需要它
+fn check_free_space(
+    report: &mut PreflightReport,
+    scan_path: &Path,
+    risky_mode: bool,
+    probe: &dyn FreeSpaceProbe,
+) {
+    match probe.available_space(scan_path) {
+        Some(space) => report.push(
+            "free space",
+            CheckStatus::Pass,
+            format!("{} available", format_size(space)),
+        ),
+        None if risky_mode => report.push(
+            "free space",
+            CheckStatus::Warn,
+            "Unknown on this volume, two-phase delete may fail unexpectedly",
+        ),
+        None => report.push("free space", CheckStatus::Pass, "Unknown (not required)"),
+    }
+}
+
+/// 检查外部工具是否可用；目前没有必需的工具，git 仅作提示
+fn check_tools(report: &mut PreflightReport) {
+    match run_command("git", ["--version"], None) {
+        Ok(version) => report.push("git", CheckStatus::Pass, version.trim()),
+        Err(CommandError::NotFound { .. }) => {
+            report.push("git", CheckStatus::Warn, "Not found in PATH (optional)")
+        }
+        Err(e) => report.push("git", CheckStatus::Warn, format!("{} (optional)", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preflight_passes_for_valid_setup() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let report = run_preflight(Ok(Config::default()), dir.path());
+
+        assert!(report.passed());
+        assert!(report.effective_config.is_some());
+        assert!(
+            report
+                .checks
+                .iter()
+                .filter(|c| c.name != "git")
+                .all(|c| c.status == CheckStatus::Pass)
+        );
+        // 探测文件不应残留
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_preflight_fails_for_unwritable_path() {
+        // /proc 即使对 root 也不可创建普通文件
+        let report = run_preflight(Ok(Config::default()), Path::new("/proc"));
+
+        assert!(!report.passed());
+        let writable = report.checks.iter().find(|c| c.name == "writable").unwrap();
+        assert_eq!(writable.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_preflight_warns_for_unknown_free_space_in_risky_mode() {
+        struct UnknownFreeSpace;
+
+        impl FreeSpaceProbe for UnknownFreeSpace {
+            fn available_space(&self, _path: &Path) -> Option<u64> {
+                None
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut report = PreflightReport {
+            checks: Vec::new(),
+            effective_config: None,
+        };
+
+        check_free_space(&mut report, dir.path(), true, &UnknownFreeSpace);
+        check_free_space(&mut report, dir.path(), false, &UnknownFreeSpace);
+
+        assert_eq!(report.checks[0].status, CheckStatus::Warn);
+        assert_eq!(report.checks[1].status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_preflight_fails_for_invalid_config() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let report = run_preflight(Err(anyhow::anyhow!("bad yaml")), dir.path());
+
+        assert!(!report.passed());
+        assert!(report.effective_config.is_none());
+    }
+}
@@ -1,4 +1,8 @@
 use anyhow::Result;
+use regex::Regex;
+use serde_json::Value;
+use std::fs;
+use std::path::{Component, Path};
 
 use crate::project::{Project, ProjectDetector, ProjectType};
 
@@ -198,8 +202,22 @@ impl ProjectDetector for NuxtJsDetector {
         Ok(false)
     }
 
-    fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
-        vec![".nuxt".to_string(), "dist".to_string()]
+    fn get_build_dirs(&self, project: &Project) -> Vec<String> {
+        let major = project
+            .package_info
+            .as_ref()
+            .and_then(|info| info.dependency_major_version("nuxt"));
+
+        // Nuxt 2 输出到 dist，Nuxt 3（Nitro）输出到 .output；版本未知时都清理
+        match major {
+            Some(2) => vec![".nuxt".to_string(), "dist".to_string()],
+            Some(major) if major >= 3 => vec![".nuxt".to_string(), ".output".to_string()],
+            _ => vec![
+                ".nuxt".to_string(),
+                ".output".to_string(),
+                "dist".to_string(),
+            ],
+        }
     }
 
     fn get_cache_dirs(&self, _project: &Project) -> Vec<String> {
@@ -211,14 +229,631 @@ impl ProjectDetector for NuxtJsDetector {
     }
 }
 
-/// 获取所有项目检测器
+/// SolidJS / SolidStart 项目检测器
+pub struct SolidDetector;
+
+impl SolidDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ProjectDetector for SolidDetector {
+    fn detect(&self, project: &mut Project) -> Result<bool> {
+        let package_info = match &project.package_info {
+            Some(info) => info,
+            None => return Ok(false),
+        };
+
+        // 检查是否是 SolidJS 或 SolidStart 项目
+        let is_solid = ["solid-js", "@solidjs/start", "solid-start"]
+            .iter()
+            .any(|dep| {
+                package_info.dependencies.contains_key(*dep)
+                    || package_info.dev_dependencies.contains_key(*dep)
+            });
+
+        if is_solid {
+            project.project_type = ProjectType::Solid;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+        vec![".solid".to_string(), "dist".to_string()]
+    }
+
+    fn get_priority(&self) -> u8 {
+        95
+    }
+}
+
+/// Qwik 项目检测器
+pub struct QwikDetector;
+
+impl QwikDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ProjectDetector for QwikDetector {
+    fn detect(&self, project: &mut Project) -> Result<bool> {
+        let package_info = match &project.package_info {
+            Some(info) => info,
+            None => return Ok(false),
+        };
+
+        // 检查是否是 Qwik 项目
+        let is_qwik = package_info.dependencies.contains_key("@builder.io/qwik")
+            || package_info
+                .dev_dependencies
+                .contains_key("@builder.io/qwik");
+
+        if is_qwik {
+            project.project_type = ProjectType::Qwik;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+        // server 是 Qwik City SSR 构建的输出目录
+        vec!["dist".to_string(), "server".to_string()]
+    }
+
+    fn get_cache_dirs(&self, _project: &Project) -> Vec<String> {
+        vec![".qwik".to_string()]
+    }
+
+    fn get_priority(&self) -> u8 {
+        95
+    }
+}
+
+/// SvelteKit 项目检测器
+pub struct SvelteKitDetector;
+
+impl SvelteKitDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ProjectDetector for SvelteKitDetector {
+    fn detect(&self, project: &mut Project) -> Result<bool> {
+        // 依赖中包含 @sveltejs/kit，或者存在 svelte.config.js
+        let has_kit = project.package_info.as_ref().is_some_and(|info| {
+            info.dependencies.contains_key("@sveltejs/kit")
+                || info.dev_dependencies.contains_key("@sveltejs/kit")
+        });
+
+        if has_kit || project.path.join("svelte.config.js").exists() {
+            project.project_type = ProjectType::SvelteKit;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+        vec![".svelte-kit".to_string(), "build".to_string()]
+    }
+
+    fn get_cache_dirs(&self, _project: &Project) -> Vec<String> {
+        vec![".vite".to_string()]
+    }
+
+    fn get_priority(&self) -> u8 {
+        95
+    }
+}
+
+/// Astro 项目检测器
+pub struct AstroDetector;
+
+impl AstroDetector {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 从 astro.config.* 中尽力读取 `outDir`，只接受项目内的相对路径
+    fn configured_out_dir(project: &Project) -> Option<String> {
+        let pattern = Regex::new(r#"outDir\s*:\s*['"`]([^'"`]+)['"`]"#).ok()?;
+
+        ASTRO_CONFIG_FILES.iter().find_map(|name| {
+            let content = fs::read_to_string(project.path.join(name)).ok()?;
+            let out_dir = pattern.captures(&content)?[1].trim().to_string();
+            let out_dir = out_dir.trim_start_matches("./").trim_end_matches('/');
+
+            let path = Path::new(out_dir);
+            let inside_project = !out_dir.is_empty()
+                && path.is_relative()
+                && path.components().all(|c| matches!(c, Component::Normal(_)));
+            inside_project.then(|| out_dir.to_string())
+        })
+    }
+}
+
+/// Astro 配置文件名
+const ASTRO_CONFIG_FILES: [&str; 4] = [
+    "astro.config.mjs",
+    "astro.config.ts",
+    "astro.config.js",
+    "astro.config.mts",
+];
+
+impl ProjectDetector for AstroDetector {
+    fn detect(&self, project: &mut Project) -> Result<bool> {
+        let has_astro = project.package_info.as_ref().is_some_and(|info| {
+            info.dependencies.contains_key("astro") || info.dev_dependencies.contains_key("astro")
+        });
+
+        if has_astro
+            || ASTRO_CONFIG_FILES
+                .iter()
+                .any(|name| project.path.join(name).exists())
+        {
+            project.project_type = ProjectType::Astro;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    fn get_build_dirs(&self, project: &Project) -> Vec<String> {
+        vec![Self::configured_out_dir(project).unwrap_or_else(|| "dist".to_string())]
+    }
+
+    fn get_cache_dirs(&self, _project: &Project) -> Vec<String> {
+        // .astro 存放生成的类型定义，总是可以重新生成
+        vec![".astro".to_string()]
+    }
+
+    fn get_priority(&self) -> u8 {
+        95
+    }
+}
+
+/// Gatsby 项目检测器
+pub struct GatsbyDetector;
+
+impl GatsbyDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Gatsby 配置文件名
+const GATSBY_CONFIG_FILES: [&str; 3] =
+    ["gatsby-config.js", "gatsby-config.ts", "gatsby-config.mjs"];
+
+impl ProjectDetector for GatsbyDetector {
+    fn detect(&self, project: &mut Project) -> Result<bool> {
+        let has_gatsby = project.package_info.as_ref().is_some_and(|info| {
+            info.dependencies.contains_key("gatsby") || info.dev_dependencies.contains_key("gatsby")
+        });
+
+        if has_gatsby
+            || GATSBY_CONFIG_FILES
+                .iter()
+                .any(|name| project.path.join(name).exists())
+        {
+            project.project_type = ProjectType::Gatsby;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+        vec!["public".to_string()]
+    }
+
+    fn get_cache_dirs(&self, _project: &Project) -> Vec<String> {
+        vec![".cache".to_string()]
+    }
+
+    fn get_priority(&self) -> u8 {
+        90
+    }
+}
+
+/// Expo / React Native 项目检测器
+///
+/// 原生构建输出位于 `android/app/build` 和 `ios/build`，是多级路径。
+pub struct ExpoDetector;
+
+impl ExpoDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ProjectDetector for ExpoDetector {
+    fn detect(&self, project: &mut Project) -> Result<bool> {
+        let is_react_native = project.package_info.as_ref().is_some_and(|info| {
+            ["expo", "react-native"].iter().any(|name| {
+                info.dependencies.contains_key(*name) || info.dev_dependencies.contains_key(*name)
+            })
+        });
+
+        if is_react_native {
+            project.project_type = ProjectType::ReactNative;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+        vec!["android/app/build".to_string(), "ios/build".to_string()]
+    }
+
+    fn get_cache_dirs(&self, _project: &Project) -> Vec<String> {
+        vec![".expo".to_string(), ".metro".to_string()]
+    }
+
+    fn get_priority(&self) -> u8 {
+        90
+    }
+}
+
+/// Deno Fresh 项目检测器
+///
+/// Fresh 项目通常没有 package.json，依据 `fresh.gen.ts` 或 `deno.json` 中的
+/// `$fresh/` 导入判断。
+pub struct FreshDetector;
+
+impl FreshDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ProjectDetector for FreshDetector {
+    fn detect(&self, project: &mut Project) -> Result<bool> {
+        let is_fresh = project.path.join("fresh.gen.ts").exists()
+            || ["deno.json", "deno.jsonc"].iter().any(|name| {
+                fs::read_to_string(project.path.join(name))
+                    .is_ok_and(|content| content.contains("$fresh/"))
+            });
+
+        if is_fresh {
+            project.project_type = ProjectType::Fresh;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+        vec!["_fresh".to_string()]
+    }
+
+    fn get_cache_dirs(&self, _project: &Project) -> Vec<String> {
+        // vendor 是 Deno 的依赖 vendoring 目录，可以重新生成
+        vec!["vendor".to_string()]
+    }
+
+    fn get_priority(&self) -> u8 {
+        95
+    }
+}
+
+/// 工作区（monorepo）根目录检测
+///
+/// 依次读取 `pnpm-workspace.yaml` 的 `packages`、`package.json` 的 `workspaces`
+/// 和 `lerna.json` 的 `packages`，记录成员目录模式。工作区检测是附加的：
+/// 不参与检测器的优先级竞争，根目录的框架检测和默认目标不受影响。
+pub struct WorkspaceDetector;
+
+impl WorkspaceDetector {
+    /// 读取工作区声明的成员目录模式，不是工作区根目录时返回 `None`
+    pub fn member_globs(path: &Path) -> Option<Vec<String>> {
+        if let Ok(content) = fs::read_to_string(path.join("pnpm-workspace.yaml")) {
+            let yaml: serde_yaml::Value = serde_yaml::from_str(&content).ok()?;
+            return string_list(yaml.get("packages").and_then(|v| v.as_sequence()));
+        }
+
+        if let Ok(content) = fs::read_to_string(path.join("package.json"))
+            && let Ok(json) = serde_json::from_str::<Value>(&content)
+            && let Some(workspaces) = json.get("workspaces")
+        {
+            // yarn 允许 `{ "packages": [...], "nohoist": [...] }` 形式
+            let packages = workspaces.get("packages").unwrap_or(workspaces);
+            return json_string_list(packages);
+        }
+
+        if let Ok(content) = fs::read_to_string(path.join("lerna.json")) {
+            let json: Value = serde_json::from_str(&content).ok()?;
+            // lerna 未声明 packages 时默认为 packages/*
+            return match json.get("packages") {
+                Some(packages) => json_string_list(packages),
+                None => Some(vec!["packages/*".to_string()]),
+            };
+        }
+
+        None
+    }
+}
+
+/// YAML 字符串列表，忽略非字符串项
+fn string_list(items: Option<&Vec<serde_yaml::Value>>) -> Option<Vec<String>> {
+    Some(
+        items?
+            .iter()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect(),
+    )
+}
+
+/// JSON 字符串数组，忽略非字符串项
+fn json_string_list(value: &Value) -> Option<Vec<String>> {
+    Some(
+        value
+            .as_array()?
+            .iter()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect(),
+    )
+}
+
+/// 按优先级稳定排序检测器：数字越小越先运行，第一个匹配的检测器决定项目类型
+pub fn sort_by_priority(detectors: &mut [Box<dyn ProjectDetector>]) {
+    detectors.sort_by_key(|detector| detector.get_priority());
+}
+
+/// 获取所有项目检测器，已按优先级排序，默认检测器（优先级最低）在最后
 pub fn get_all_detectors() -> Vec<Box<dyn ProjectDetector>> {
-    vec![
+    let mut detectors: Vec<Box<dyn ProjectDetector>> = vec![
         Box::new(NextJsDetector::new()),
         Box::new(NuxtJsDetector::new()),
         Box::new(AngularDetector::new()),
+        Box::new(SolidDetector::new()),
+        Box::new(QwikDetector::new()),
+        Box::new(SvelteKitDetector::new()),
+        Box::new(AstroDetector::new()),
+        Box::new(FreshDetector::new()),
+        // Gatsby 项目也依赖 React，优先级必须高于 React 检测器
+        Box::new(GatsbyDetector::new()),
+        // React Native 项目也依赖 React，优先级必须高于 React 检测器
+        Box::new(ExpoDetector::new()),
         Box::new(VueDetector::new()),
         Box::new(ReactDetector::new()),
         Box::new(crate::project::detector::DefaultDetector::new()),
-    ]
+    ];
+    sort_by_priority(&mut detectors);
+    detectors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::{DefaultDetector, PackageInfo};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    /// 创建带有给定依赖的项目
+    fn project_with_deps(dependencies: &[&str], dev_dependencies: &[&str]) -> Project {
+        let to_map = |deps: &[&str]| -> HashMap<String, String> {
+            deps.iter()
+                .map(|d| (d.to_string(), "^1.0.0".to_string()))
+                .collect()
+        };
+
+        let mut project = Project::new(PathBuf::from("/nonexistent/project"));
+        project.package_info = Some(PackageInfo {
+            name: "test".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: to_map(dependencies),
+            dev_dependencies: to_map(dev_dependencies),
+        });
+        project
+    }
+
+    #[test]
+    fn test_nuxt_build_dirs_follow_major_version() {
+        let detector = NuxtJsDetector::new();
+        let with_nuxt_version = |version: &str| {
+            let mut project = project_with_deps(&["nuxt"], &[]);
+            if let Some(info) = project.package_info.as_mut() {
+                info.dependencies
+                    .insert("nuxt".to_string(), version.to_string());
+            }
+            project
+        };
+
+        let mut nuxt3 = with_nuxt_version("^3.8.0");
+        assert!(detector.detect(&mut nuxt3).unwrap());
+        assert_eq!(detector.get_build_dirs(&nuxt3), vec![".nuxt", ".output"]);
+
+        let nuxt2 = with_nuxt_version("~2.15.8");
+        assert_eq!(detector.get_build_dirs(&nuxt2), vec![".nuxt", "dist"]);
+
+        // 无法确定版本时全部清理
+        let unknown = with_nuxt_version("latest");
+        assert_eq!(
+            detector.get_build_dirs(&unknown),
+            vec![".nuxt", ".output", "dist"]
+        );
+    }
+
+    #[test]
+    fn test_workspace_member_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(WorkspaceDetector::member_globs(dir.path()), None);
+
+        // lerna 未声明 packages 时使用默认值
+        fs::write(dir.path().join("lerna.json"), r#"{"version": "1.0.0"}"#).unwrap();
+        assert_eq!(
+            WorkspaceDetector::member_globs(dir.path()),
+            Some(vec!["packages/*".to_string()])
+        );
+
+        // package.json 的 workspaces 优先于 lerna.json，支持 yarn 的对象形式
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"workspaces": {"packages": ["apps/*", "libs/**"], "nohoist": ["**/x"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            WorkspaceDetector::member_globs(dir.path()).unwrap(),
+            ["apps/*", "libs/**"]
+        );
+    }
+
+    #[test]
+    fn test_gatsby_detected_before_react() {
+        let mut project = project_with_deps(&["gatsby", "react", "react-dom"], &[]);
+        let detector = get_all_detectors()
+            .into_iter()
+            .find(|d| d.detect(&mut project).unwrap())
+            .unwrap();
+        assert_eq!(project.project_type, ProjectType::Gatsby);
+        assert_eq!(detector.get_build_dirs(&project), vec!["public"]);
+        assert_eq!(detector.get_cache_dirs(&project), vec![".cache"]);
+
+        // 只有配置文件也能识别
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("gatsby-config.js"), "module.exports = {}").unwrap();
+        let mut project = Project::new(dir.path().to_path_buf());
+        assert!(GatsbyDetector::new().detect(&mut project).unwrap());
+        assert_eq!(project.project_type, ProjectType::Gatsby);
+
+        let mut react = project_with_deps(&["react"], &[]);
+        assert!(!GatsbyDetector::new().detect(&mut react).unwrap());
+    }
+
+    #[test]
+    fn test_expo_and_react_native_detected_before_react() {
+        for deps in [&["expo", "react"][..], &["react-native", "react"][..]] {
+            let mut project = project_with_deps(deps, &[]);
+            let detector = get_all_detectors()
+                .into_iter()
+                .find(|d| d.detect(&mut project).unwrap())
+                .unwrap();
+            assert_eq!(project.project_type, ProjectType::ReactNative);
+            assert_eq!(
+                detector.get_build_dirs(&project),
+                vec!["android/app/build", "ios/build"]
+            );
+            assert_eq!(detector.get_cache_dirs(&project), vec![".expo", ".metro"]);
+        }
+
+        let mut react = project_with_deps(&["react"], &[]);
+        assert!(!ExpoDetector::new().detect(&mut react).unwrap());
+    }
+
+    #[test]
+    fn test_solid_detection() {
+        let detector = SolidDetector::new();
+
+        let mut solid = project_with_deps(&["solid-js"], &[]);
+        assert!(detector.detect(&mut solid).unwrap());
+        assert_eq!(solid.project_type, ProjectType::Solid);
+
+        let mut solid_start = project_with_deps(&[], &["@solidjs/start"]);
+        assert!(detector.detect(&mut solid_start).unwrap());
+        assert_eq!(
+            detector.get_build_dirs(&solid_start),
+            vec![".solid", "dist"]
+        );
+
+        let mut react = project_with_deps(&["react"], &[]);
+        assert!(!detector.detect(&mut react).unwrap());
+    }
+
+    #[test]
+    fn test_qwik_detection() {
+        let detector = QwikDetector::new();
+
+        let mut qwik = project_with_deps(&[], &["@builder.io/qwik"]);
+        assert!(detector.detect(&mut qwik).unwrap());
+        assert_eq!(qwik.project_type, ProjectType::Qwik);
+        assert_eq!(detector.get_build_dirs(&qwik), vec!["dist", "server"]);
+        assert_eq!(detector.get_cache_dirs(&qwik), vec![".qwik"]);
+
+        let mut vue = project_with_deps(&["vue"], &[]);
+        assert!(!detector.detect(&mut vue).unwrap());
+    }
+
+    #[test]
+    fn test_sveltekit_detection() {
+        let detector = SvelteKitDetector::new();
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"name": "site", "devDependencies": {"@sveltejs/kit": "^2.0.0", "svelte": "^4.0.0"}}"#,
+        )
+        .unwrap();
+        let mut kit = Project::new(dir.path().to_path_buf());
+        kit.package_info = Some(DefaultDetector::parse_package_json(dir.path()).unwrap());
+
+        assert!(detector.detect(&mut kit).unwrap());
+        assert_eq!(kit.project_type, ProjectType::SvelteKit);
+        assert_eq!(detector.get_build_dirs(&kit), vec![".svelte-kit", "build"]);
+        assert_eq!(detector.get_cache_dirs(&kit), vec![".vite"]);
+
+        // 只有 svelte.config.js 也能识别
+        let config_only = tempfile::tempdir().unwrap();
+        fs::write(
+            config_only.path().join("svelte.config.js"),
+            "export default {};",
+        )
+        .unwrap();
+        let mut project = Project::new(config_only.path().to_path_buf());
+        assert!(detector.detect(&mut project).unwrap());
+
+        let mut react = project_with_deps(&["react"], &[]);
+        assert!(!detector.detect(&mut react).unwrap());
+    }
+
+    #[test]
+    fn test_astro_detected_from_dependency() {
+        let detector = AstroDetector::new();
+        let mut project = project_with_deps(&[], &["astro"]);
+        assert!(detector.detect(&mut project).unwrap());
+        assert_eq!(project.project_type, ProjectType::Astro);
+        assert_eq!(detector.get_build_dirs(&project), vec!["dist"]);
+        assert_eq!(detector.get_cache_dirs(&project), vec![".astro"]);
+
+        // 优先于默认的 Node 检测器
+        assert!(
+            detector.get_priority()
+                < crate::project::detector::DefaultDetector::new().get_priority()
+        );
+
+        let mut react = project_with_deps(&["react"], &[]);
+        assert!(!detector.detect(&mut react).unwrap());
+    }
+
+    #[test]
+    fn test_astro_custom_out_dir() {
+        let detector = AstroDetector::new();
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("astro.config.mjs"),
+            "import { defineConfig } from 'astro/config';\n\nexport default defineConfig({\n  outDir: './build',\n});\n",
+        )
+        .unwrap();
+
+        let mut project = Project::new(dir.path().to_path_buf());
+        assert!(detector.detect(&mut project).unwrap());
+        assert_eq!(project.project_type, ProjectType::Astro);
+        assert_eq!(detector.get_build_dirs(&project), vec!["build"]);
+        assert_eq!(detector.get_cache_dirs(&project), vec![".astro"]);
+
+        // 未配置或指向项目外时回退到 dist
+        fs::write(
+            dir.path().join("astro.config.mjs"),
+            "export default defineConfig({ outDir: '../elsewhere' });",
+        )
+        .unwrap();
+        assert_eq!(detector.get_build_dirs(&project), vec!["dist"]);
+    }
 }
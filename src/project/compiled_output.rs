@@ -0,0 +1,279 @@
+//! TypeScript 编译输出目录（CLI 工具常用的 `lib`、`bin` 等）
+//!
+//! `lib` 和 `bin` 在很多项目中是手写的源码，不能只凭目录名清理。只有下列配置确认
+//! 目录是生成的时才把它当作构建目录：
+//! - tsconfig.json 的 `compilerOptions.outDir`
+//! - package.json 的 `bin` 指向某个目录中的 `.js` 文件，源码目录中有对应的 `.ts` 文件，
+//!   且该目录被项目的 `.gitignore` 忽略（手写的 `bin/cli.js` 包装脚本会被提交，不会被忽略）
+
+use ignore::gitignore::GitignoreBuilder;
+use regex::Regex;
+use serde_json::Value;
+use std::fs;
+use std::path::{Component, Path};
+
+use crate::project::Project;
+use crate::project::test_tools::project_relative_dir;
+
+/// 未配置 `rootDir` 时假定的 TypeScript 源码目录
+const DEFAULT_ROOT_DIR: &str = "src";
+
+/// 编译产物的扩展名及对应的 TypeScript 源文件扩展名
+const COMPILED_EXTENSIONS: [(&str, &[&str]); 3] =
+    [("js", &["ts", "tsx"]), ("mjs", &["mts"]), ("cjs", &["cts"])];
+
+/// 配置确认为编译输出的目录（相对于项目根目录）
+pub fn build_dirs(project: &Project) -> Vec<String> {
+    let root_dir = tsconfig_option(project, "rootDir")
+        .and_then(|dir| project_relative_dir(&dir))
+        .unwrap_or_else(|| DEFAULT_ROOT_DIR.to_string());
+
+    let mut dirs = Vec::new();
+    if let Some(out_dir) =
+        tsconfig_option(project, "outDir").and_then(|dir| project_relative_dir(&dir))
+    {
+        dirs.push(out_dir);
+    }
+    for dir in bin_output_dirs(project, &root_dir) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    // 原地编译（输出目录就是源码目录或包含源码目录）时不能清理
+    dirs.retain(|dir| !Path::new(&root_dir).starts_with(dir));
+    dirs
+}
+
+/// 从 tsconfig.json 中尽力读取 `compilerOptions` 的字符串选项
+///
+/// tsconfig 允许注释和尾随逗号，不能按严格 JSON 解析，这里去掉注释后用正则匹配。
+fn tsconfig_option(project: &Project, option: &str) -> Option<String> {
+    let content = fs::read_to_string(project.path.join("tsconfig.json")).ok()?;
+    let content = strip_comments(&content);
+    let pattern = Regex::new(&format!(r#""{}"\s*:\s*"([^"]+)""#, option)).ok()?;
+    Some(pattern.captures(&content)?[1].to_string())
+}
+
+/// 去掉 `//` 行注释和 `/* */` 块注释，字符串中的内容原样保留
+fn strip_comments(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                output.push(c);
+                while let Some(c) = chars.next() {
+                    output.push(c);
+                    match c {
+                        '\\' => output.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = None;
+                for c in chars.by_ref() {
+                    if previous == Some('*') && c == '/' {
+                        break;
+                    }
+                    previous = Some(c);
+                }
+                output.push(' ');
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+/// package.json 的 `bin` 指向的、有 TypeScript 源文件对应且被 `.gitignore` 忽略的输出目录
+fn bin_output_dirs(project: &Project, root_dir: &str) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(project.path.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = serde_json::from_str::<Value>(&content) else {
+        return Vec::new();
+    };
+
+    let entries: Vec<&str> = match &manifest["bin"] {
+        Value::String(entry) => vec![entry.as_str()],
+        Value::Object(entries) => entries.values().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+
+    let mut dirs: Vec<String> = Vec::new();
+    for entry in entries {
+        let Some(entry) = project_relative_dir(entry) else {
+            continue;
+        };
+        let entry = Path::new(&entry);
+        let Some(Component::Normal(out_dir)) = entry.components().next() else {
+            continue;
+        };
+        let Ok(relative) = entry.strip_prefix(out_dir) else {
+            continue;
+        };
+        let out_dir = out_dir.to_string_lossy().to_string();
+        if relative.as_os_str().is_empty() || dirs.contains(&out_dir) {
+            continue;
+        }
+
+        if has_typescript_source(&project.path.join(root_dir), relative)
+            && is_gitignored(&project.path, &out_dir)
+        {
+            dirs.push(out_dir);
+        }
+    }
+    dirs
+}
+
+/// 项目根目录的 `.gitignore` 是否忽略目录 `dir`
+fn is_gitignored(project_path: &Path, dir: &str) -> bool {
+    let mut builder = GitignoreBuilder::new(project_path);
+    if builder.add(project_path.join(".gitignore")).is_some() {
+        return false;
+    }
+    builder.build().is_ok_and(|gitignore| {
+        gitignore
+            .matched_path_or_any_parents(project_path.join(dir), true)
+            .is_ignore()
+    })
+}
+
+/// 输出文件 `relative` 在源码目录中是否有对应的 TypeScript 源文件
+fn has_typescript_source(root_dir: &Path, relative: &Path) -> bool {
+    let Some(extension) = relative.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    COMPILED_EXTENSIONS
+        .iter()
+        .filter(|(compiled, _)| *compiled == extension)
+        .flat_map(|(_, sources)| sources.iter())
+        .any(|source| root_dir.join(relative.with_extension(source)).is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lib_is_generated_only_when_tsconfig_out_dir_says_so() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = Project::new(dir.path().to_path_buf());
+        fs::create_dir_all(dir.path().join("lib")).unwrap();
+        fs::write(dir.path().join("lib/index.js"), "module.exports = {}").unwrap();
+        assert!(build_dirs(&project).is_empty());
+
+        fs::write(
+            dir.path().join("tsconfig.json"),
+            r#"{
+  // 编译到 lib
+  "compilerOptions": { "outDir": "./lib/", "rootDir": "src", },
+}"#,
+        )
+        .unwrap();
+        assert_eq!(build_dirs(&project), ["lib"]);
+
+        // 原地编译时输出目录就是源码目录
+        fs::write(
+            dir.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "outDir": "lib", "rootDir": "lib" } }"#,
+        )
+        .unwrap();
+        assert!(build_dirs(&project).is_empty());
+
+        fs::write(
+            dir.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "outDir": "../shared/lib" } }"#,
+        )
+        .unwrap();
+        assert!(build_dirs(&project).is_empty());
+    }
+
+    #[test]
+    fn test_commented_out_out_dir_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = Project::new(dir.path().to_path_buf());
+        fs::write(
+            dir.path().join("tsconfig.json"),
+            r#"{
+  "compilerOptions": {
+    // "outDir": "lib",
+    /* "outDir": "bin", */
+    "baseUrl": "http://example.com//x" // 字符串中的 // 不是注释
+  }
+}"#,
+        )
+        .unwrap();
+        assert!(build_dirs(&project).is_empty());
+
+        fs::write(
+            dir.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { /* "outDir": "bin", */ "outDir": "lib" } }"#,
+        )
+        .unwrap();
+        assert_eq!(build_dirs(&project), ["lib"]);
+    }
+
+    #[test]
+    fn test_bin_is_generated_only_with_typescript_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = Project::new(dir.path().to_path_buf());
+        fs::create_dir_all(dir.path().join("bin")).unwrap();
+        fs::write(dir.path().join("bin/cli.js"), "#!/usr/bin/env node").unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{ "name": "tool", "bin": { "tool": "./bin/cli.js" } }"#,
+        )
+        .unwrap();
+
+        // 手写的 bin 脚本没有对应的源文件
+        assert!(build_dirs(&project).is_empty());
+
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/cli.ts"), "console.log(1)").unwrap();
+        // 有同名源文件但 bin 没有被忽略：可能是提交到仓库的包装脚本
+        assert!(build_dirs(&project).is_empty());
+
+        fs::write(dir.path().join(".gitignore"), "node_modules\n/bin\n").unwrap();
+        assert_eq!(build_dirs(&project), ["bin"]);
+    }
+
+    #[test]
+    fn test_checked_in_bin_wrapper_is_not_a_build_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = Project::new(dir.path().to_path_buf());
+        fs::create_dir_all(dir.path().join("bin")).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("bin/cli.js"),
+            "#!/usr/bin/env node\nrequire('../dist/cli')",
+        )
+        .unwrap();
+        fs::write(dir.path().join("src/cli.ts"), "console.log(1)").unwrap();
+        fs::write(dir.path().join(".gitignore"), "node_modules\ndist\n").unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{ "name": "tool", "bin": { "tool": "bin/cli.js" } }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "outDir": "dist", "rootDir": "src" } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(build_dirs(&project), ["dist"]);
+    }
+}
@@ -19,13 +19,20 @@ pub trait ProjectDetector: Send + Sync {
         Vec::new() // 默认实现，返回空列表
     }
 
+    /// 获取 node_modules 内部可单独清理的缓存目录（相对于 node_modules）
+    fn get_node_modules_cache_dirs(&self, _project: &Project) -> Vec<String> {
+        vec![".cache".to_string(), ".vite".to_string()]
+    }
+
     /// 获取项目对应的代码覆盖率目录
     fn get_coverage_dirs(&self, _project: &Project) -> Vec<String> {
         vec!["coverage".to_string()] // 默认实现，返回标准覆盖率目录
     }
 
-    /// 获取检测器优先级，数字越小优先级越高
-    #[allow(dead_code)]
+    /// 获取检测器优先级，数字越小优先级越高、越先运行；相同时保持注册顺序
+    ///
+    /// 框架检测器（如 Next.js 为 80）应小于其依赖的通用框架（React 为 100），
+    /// 默认检测器为 200，总是最后运行。
     fn get_priority(&self) -> u8 {
         100
     }
@@ -79,9 +86,10 @@ impl ProjectDetector for DefaultDetector {
             return Ok(false);
         }
 
-        // 解析 package.json
-        let package_info = Self::parse_package_json(&project.path)?;
-        project.package_info = Some(package_info);
+        // 解析 package.json（扫描器通常已经预先解析过）
+        if project.package_info.is_none() {
+            project.package_info = Some(Self::parse_package_json(&project.path)?);
+        }
         project.project_type = ProjectType::NodeJs;
 
         Ok(true)
@@ -104,12 +112,12 @@ impl ProjectDetector for DefaultDetector {
 fn extract_dependencies(json: &Value, field_name: &str) -> HashMap<String, String> {
     let mut result = HashMap::new();
 
-    if let Some(deps) = json.get(field_name) {
-        if let Some(deps_obj) = deps.as_object() {
-            for (key, value) in deps_obj {
-                if let Some(version) = value.as_str() {
-                    result.insert(key.clone(), version.to_string());
-                }
+    if let Some(deps) = json.get(field_name)
+        && let Some(deps_obj) = deps.as_object()
+    {
+        for (key, value) in deps_obj {
+            if let Some(version) = value.as_str() {
+                result.insert(key.clone(), version.to_string());
             }
         }
     }
@@ -1,12 +1,17 @@
 pub mod analyzers;
+pub mod compiled_output;
 mod detector;
+pub mod monorepo_tools;
+pub mod test_tools;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 // 重导出
-pub use detector::ProjectDetector;
+pub use detector::{DefaultDetector, ProjectDetector};
 
 /// 项目类型枚举
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,11 +22,87 @@ pub enum ProjectType {
     Angular,
     NextJs,
     NuxtJs,
+    Solid,
+    Qwik,
+    Fresh,
+    SvelteKit,
+    Astro,
+    Gatsby,
+    /// Expo 或 React Native 应用
+    ReactNative,
+    /// pnpm/npm/yarn workspace 或 lerna 仓库的根目录
+    Monorepo,
     Unknown,
 }
 
+impl fmt::Display for ProjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProjectType::NodeJs => "Node.js",
+            ProjectType::React => "React",
+            ProjectType::Vue => "Vue",
+            ProjectType::Angular => "Angular",
+            ProjectType::NextJs => "Next.js",
+            ProjectType::NuxtJs => "Nuxt.js",
+            ProjectType::Solid => "Solid",
+            ProjectType::Qwik => "Qwik",
+            ProjectType::Fresh => "Fresh",
+            ProjectType::SvelteKit => "SvelteKit",
+            ProjectType::Astro => "Astro",
+            ProjectType::Gatsby => "Gatsby",
+            ProjectType::ReactNative => "React Native",
+            ProjectType::Monorepo => "Monorepo",
+            ProjectType::Unknown => "Unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ProjectType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "node" | "nodejs" | "node.js" => Ok(ProjectType::NodeJs),
+            "react" | "reactjs" | "react.js" => Ok(ProjectType::React),
+            "vue" | "vuejs" | "vue.js" => Ok(ProjectType::Vue),
+            "angular" => Ok(ProjectType::Angular),
+            "next" | "nextjs" | "next.js" => Ok(ProjectType::NextJs),
+            "nuxt" | "nuxtjs" | "nuxt.js" => Ok(ProjectType::NuxtJs),
+            "solid" | "solidjs" | "solid-start" | "solidstart" => Ok(ProjectType::Solid),
+            "qwik" => Ok(ProjectType::Qwik),
+            "fresh" => Ok(ProjectType::Fresh),
+            "svelte" | "sveltekit" | "svelte-kit" => Ok(ProjectType::SvelteKit),
+            "astro" => Ok(ProjectType::Astro),
+            "gatsby" | "gatsbyjs" => Ok(ProjectType::Gatsby),
+            "react native" | "react-native" | "reactnative" | "rn" | "expo" => {
+                Ok(ProjectType::ReactNative)
+            }
+            "monorepo" | "workspace" => Ok(ProjectType::Monorepo),
+            "unknown" => Ok(ProjectType::Unknown),
+            _ => anyhow::bail!(
+                "Unknown project type '{}': expected node, react, vue, angular, next, nuxt, solid, qwik, fresh, sveltekit, astro, gatsby, react-native, monorepo or unknown",
+                s.trim()
+            ),
+        }
+    }
+}
+
+impl Serialize for ProjectType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProjectType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// 清理目标类型
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TargetType {
     NodeModules,
     BuildDir,
@@ -42,9 +123,45 @@ impl fmt::Display for TargetType {
     }
 }
 
+impl FromStr for TargetType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(name) = s.strip_prefix("custom:") {
+            return Ok(TargetType::Custom(name.trim().to_string()));
+        }
+
+        match s.to_lowercase().as_str() {
+            "node_modules" | "node-modules" => Ok(TargetType::NodeModules),
+            "build" => Ok(TargetType::BuildDir),
+            "cache" => Ok(TargetType::CacheDir),
+            "coverage" => Ok(TargetType::Coverage),
+            _ => anyhow::bail!(
+                "Unknown target type '{}': expected node_modules, build, cache, coverage or custom:<name>",
+                s
+            ),
+        }
+    }
+}
+
+// 序列化为与 Display 相同的字符串，便于在配置文件中作为键使用
+impl Serialize for TargetType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TargetType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// 项目大小信息
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SizeInfo {
     pub total_size: u64,
     pub node_modules_size: u64,
@@ -63,22 +180,60 @@ pub struct PackageInfo {
     pub dev_dependencies: HashMap<String, String>,
 }
 
+impl PackageInfo {
+    /// 依赖（包括开发依赖）版本范围中的主版本号，例如 `^3.8.0` 为 3
+    ///
+    /// 未声明该依赖或版本无法解析（如 `latest`、`workspace:*`）时返回 `None`。
+    pub fn dependency_major_version(&self, name: &str) -> Option<u64> {
+        let spec = self
+            .dependencies
+            .get(name)
+            .or_else(|| self.dev_dependencies.get(name))?;
+        let digits: String = spec
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    }
+}
+
 /// 清理目标
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CleanTarget {
     pub path: PathBuf,
+    #[serde(rename = "type")]
     pub target_type: TargetType,
     pub size: Option<u64>,
 }
 
+/// 被过滤规则跳过的目标及原因
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedTarget {
+    pub target: CleanTarget,
+    pub reason: String,
+}
+
 /// 项目模型
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Project {
     pub path: PathBuf,
     pub project_type: ProjectType,
+    #[serde(skip)]
     pub package_info: Option<PackageInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub size_info: Option<SizeInfo>,
     pub detected_targets: Vec<CleanTarget>,
+    pub skipped_targets: Vec<SkippedTarget>,
+    /// 工作区根目录声明的成员目录模式（如 `packages/*`），其他项目为空
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub workspace_members: Vec<String>,
+    /// lockfile 的哈希（开启统计时计算），用于估算共享依赖存储可节省的空间
+    #[serde(skip)]
+    pub lockfile_hash: Option<u64>,
+    /// node_modules 中悬空或循环的符号链接（开启 `check_broken_links` 时检查），说明安装已损坏
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub broken_links: Vec<PathBuf>,
 }
 
 impl Project {
@@ -89,6 +244,10 @@ impl Project {
             package_info: None,
             size_info: None,
             detected_targets: Vec::new(),
+            skipped_targets: Vec::new(),
+            workspace_members: Vec::new(),
+            lockfile_hash: None,
+            broken_links: Vec::new(),
         }
     }
 
@@ -96,4 +255,67 @@ impl Project {
     pub fn has_package_json(path: &Path) -> bool {
         path.join("package.json").exists()
     }
+
+    /// 检查路径是否是项目目录：包含 package.json，或者是 Deno 项目
+    pub fn is_project_dir(path: &Path) -> bool {
+        Self::has_package_json(path)
+            || ["deno.json", "deno.jsonc", "fresh.gen.ts"]
+                .iter()
+                .any(|marker| path.join(marker).exists())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_type_round_trip() {
+        for target_type in [
+            TargetType::NodeModules,
+            TargetType::BuildDir,
+            TargetType::CacheDir,
+            TargetType::Coverage,
+            TargetType::Custom(".turbo".to_string()),
+        ] {
+            let parsed: TargetType = target_type.to_string().parse().unwrap();
+            assert_eq!(parsed, target_type);
+        }
+        assert!("binaries".parse::<TargetType>().is_err());
+    }
+
+    #[test]
+    fn test_project_type_aliases() {
+        for alias in ["nextjs", "next", "NEXT", "Next.js"] {
+            assert_eq!(alias.parse::<ProjectType>().unwrap(), ProjectType::NextJs);
+        }
+        assert_eq!("Node".parse::<ProjectType>().unwrap(), ProjectType::NodeJs);
+        assert_eq!("nuxt".parse::<ProjectType>().unwrap(), ProjectType::NuxtJs);
+        assert!("not-a-framework".parse::<ProjectType>().is_err());
+    }
+
+    #[test]
+    fn test_project_type_display_round_trip() {
+        for project_type in [
+            ProjectType::NodeJs,
+            ProjectType::React,
+            ProjectType::Vue,
+            ProjectType::Angular,
+            ProjectType::NextJs,
+            ProjectType::NuxtJs,
+            ProjectType::Solid,
+            ProjectType::Qwik,
+            ProjectType::Fresh,
+            ProjectType::SvelteKit,
+            ProjectType::Astro,
+            ProjectType::Gatsby,
+            ProjectType::ReactNative,
+            ProjectType::Monorepo,
+            ProjectType::Unknown,
+        ] {
+            let parsed: ProjectType = project_type.to_string().parse().unwrap();
+            assert_eq!(parsed, project_type);
+        }
+        assert_eq!(ProjectType::NextJs.to_string(), "Next.js");
+    }
 }
@@ -0,0 +1,53 @@
+//! 单仓构建工具（Turborepo、Nx）的任务缓存目录
+//!
+//! 这些工具可以用在任何类型的项目上，根据项目根目录下的配置文件检测，
+//! 由扫描器合并到框架检测器给出的缓存目录列表中。
+
+use crate::project::Project;
+
+/// 单仓工具的配置文件及其在项目根目录下的缓存目录
+const ROOT_CACHE_DIRS: [(&str, &str); 2] = [("turbo.json", ".turbo"), ("nx.json", ".nx/cache")];
+
+/// 单仓工具的配置文件及其在 node_modules 内部的缓存目录（相对于 node_modules）
+const NODE_MODULES_CACHE_DIRS: [(&str, &str); 1] = [("nx.json", ".cache/nx")];
+
+/// 单仓工具在项目根目录下的缓存目录
+pub fn cache_dirs(project: &Project) -> Vec<String> {
+    configured_dirs(project, &ROOT_CACHE_DIRS)
+}
+
+/// 单仓工具在 node_modules 内部的缓存目录（相对于 node_modules）
+pub fn node_modules_cache_dirs(project: &Project) -> Vec<String> {
+    configured_dirs(project, &NODE_MODULES_CACHE_DIRS)
+}
+
+/// 项目中存在配置文件的工具对应的目录
+fn configured_dirs(project: &Project, tools: &[(&str, &str)]) -> Vec<String> {
+    tools
+        .iter()
+        .filter(|(config_file, _)| project.path.join(config_file).is_file())
+        .map(|(_, dir)| dir.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_cache_dirs_follow_config_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = Project::new(dir.path().to_path_buf());
+        assert!(cache_dirs(&project).is_empty());
+        assert!(node_modules_cache_dirs(&project).is_empty());
+
+        fs::write(dir.path().join("turbo.json"), "{}").unwrap();
+        assert_eq!(cache_dirs(&project), [".turbo"]);
+        assert!(node_modules_cache_dirs(&project).is_empty());
+
+        fs::write(dir.path().join("nx.json"), "{}").unwrap();
+        assert_eq!(cache_dirs(&project), [".turbo", ".nx/cache"]);
+        assert_eq!(node_modules_cache_dirs(&project), [".cache/nx"]);
+    }
+}
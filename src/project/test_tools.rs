@@ -0,0 +1,204 @@
+//! 测试工具（Jest、Vitest、Playwright）留下的缓存和报告目录
+//!
+//! 这些目录与项目框架无关，根据 package.json 中的依赖检测，
+//! 由扫描器合并到框架检测器给出的目录列表中。
+
+use regex::Regex;
+use serde_json::Value;
+use std::fs;
+use std::path::{Component, Path};
+
+use crate::project::Project;
+
+/// Jest 配置文件名
+const JEST_CONFIG_FILES: [&str; 5] = [
+    "jest.config.js",
+    "jest.config.ts",
+    "jest.config.mjs",
+    "jest.config.cjs",
+    "jest.config.json",
+];
+
+/// Playwright 在项目根目录生成的测试结果和 HTML 报告目录
+const PLAYWRIGHT_OUTPUT_DIRS: [&str; 2] = ["test-results", "playwright-report"];
+
+/// 项目是否依赖任一指定的包（包括开发依赖）
+fn uses_any(project: &Project, packages: &[&str]) -> bool {
+    project.package_info.as_ref().is_some_and(|info| {
+        packages.iter().any(|name| {
+            info.dependencies.contains_key(*name) || info.dev_dependencies.contains_key(*name)
+        })
+    })
+}
+
+/// 测试工具在项目根目录下的缓存目录：Jest 配置的 `cacheDirectory`（位于 node_modules 之外时）
+pub fn cache_dirs(project: &Project) -> Vec<String> {
+    jest_cache_directory(project)
+        .and_then(|dir| project_relative_dir(&dir))
+        .filter(|dir| !dir.starts_with("node_modules/"))
+        .into_iter()
+        .collect()
+}
+
+/// 测试工具在项目根目录下的报告目录，按覆盖率目录处理：Playwright 的测试结果和报告
+pub fn coverage_dirs(project: &Project) -> Vec<String> {
+    if !uses_any(project, &["@playwright/test", "playwright"]) {
+        return Vec::new();
+    }
+    PLAYWRIGHT_OUTPUT_DIRS
+        .iter()
+        .map(|d| d.to_string())
+        .collect()
+}
+
+/// 测试工具在 node_modules 内部的缓存目录（相对于 node_modules）
+pub fn node_modules_cache_dirs(project: &Project) -> Vec<String> {
+    let mut dirs = Vec::new();
+    if uses_any(project, &["jest"]) {
+        // 配置了 cacheDirectory 时默认位置不再使用
+        match jest_cache_directory(project) {
+            Some(dir) => {
+                if let Some(inside) = project_relative_dir(&dir)
+                    .as_deref()
+                    .and_then(|dir| dir.strip_prefix("node_modules/"))
+                {
+                    dirs.push(inside.to_string());
+                }
+            }
+            None => dirs.push(".cache/jest".to_string()),
+        }
+    }
+    if uses_any(project, &["vitest"]) {
+        dirs.push(".vitest".to_string());
+    }
+    dirs
+}
+
+/// 把 `extra` 中的目录追加到 `dirs`，跳过已存在或位于已有目录之内的条目，避免注册嵌套目标
+pub fn merge_dirs(dirs: &mut Vec<String>, extra: Vec<String>) {
+    for dir in extra {
+        let covered = dirs
+            .iter()
+            .any(|existing| Path::new(&dir).starts_with(existing));
+        if !covered {
+            dirs.push(dir);
+        }
+    }
+}
+
+/// 读取 Jest 配置的 `cacheDirectory` 原始值
+///
+/// 依次检查 package.json 的 `jest` 字段和 jest.config.*，后者只尽力匹配字符串字面量。
+fn jest_cache_directory(project: &Project) -> Option<String> {
+    if !uses_any(project, &["jest"]) {
+        return None;
+    }
+
+    let from_package_json = fs::read_to_string(project.path.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|json| {
+            json.get("jest")?
+                .get("cacheDirectory")?
+                .as_str()
+                .map(str::to_string)
+        });
+
+    from_package_json.or_else(|| {
+        let pattern = Regex::new(r#"["']?cacheDirectory["']?\s*:\s*['"`]([^'"`]+)['"`]"#).ok()?;
+        JEST_CONFIG_FILES.iter().find_map(|name| {
+            let content = fs::read_to_string(project.path.join(name)).ok()?;
+            Some(pattern.captures(&content)?[1].to_string())
+        })
+    })
+}
+
+/// 规范化配置中的目录：去掉 `<rootDir>/` 和 `./` 前缀，拒绝指向项目之外的路径
+pub(crate) fn project_relative_dir(value: &str) -> Option<String> {
+    let dir = value
+        .trim()
+        .trim_start_matches("<rootDir>/")
+        .trim_start_matches("./")
+        .trim_end_matches('/');
+
+    let path = Path::new(dir);
+    let inside_project = !dir.is_empty()
+        && path.is_relative()
+        && path.components().all(|c| matches!(c, Component::Normal(_)));
+    inside_project.then(|| dir.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::PackageInfo;
+    use std::collections::HashMap;
+
+    fn project_with_dev_deps(path: &Path, dev_dependencies: &[&str]) -> Project {
+        let mut project = Project::new(path.to_path_buf());
+        project.package_info = Some(PackageInfo {
+            name: "test".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: HashMap::new(),
+            dev_dependencies: dev_dependencies
+                .iter()
+                .map(|d| (d.to_string(), "^1.0.0".to_string()))
+                .collect(),
+        });
+        project
+    }
+
+    #[test]
+    fn test_jest_cache_directory_from_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project_with_dev_deps(dir.path(), &["jest", "vitest"]);
+        assert!(cache_dirs(&project).is_empty());
+        assert_eq!(
+            node_modules_cache_dirs(&project),
+            [".cache/jest", ".vitest"]
+        );
+
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"jest": {"cacheDirectory": "<rootDir>/.jest-cache"}}"#,
+        )
+        .unwrap();
+        assert_eq!(cache_dirs(&project), [".jest-cache"]);
+        assert_eq!(node_modules_cache_dirs(&project), [".vitest"]);
+
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::write(
+            dir.path().join("jest.config.js"),
+            "module.exports = { cacheDirectory: './node_modules/.cache/jest-ci' };",
+        )
+        .unwrap();
+        assert!(cache_dirs(&project).is_empty());
+        assert_eq!(
+            node_modules_cache_dirs(&project),
+            [".cache/jest-ci", ".vitest"]
+        );
+
+        // 项目之外的缓存目录不会被清理
+        fs::write(
+            dir.path().join("jest.config.js"),
+            "module.exports = { cacheDirectory: '/tmp/jest' };",
+        )
+        .unwrap();
+        assert!(cache_dirs(&project).is_empty());
+        assert_eq!(node_modules_cache_dirs(&project), [".vitest"]);
+    }
+
+    #[test]
+    fn test_merge_dirs_skips_nested_entries() {
+        let mut dirs = vec![".cache".to_string(), ".vite".to_string()];
+        merge_dirs(
+            &mut dirs,
+            vec![
+                ".cache/jest".to_string(),
+                ".vitest".to_string(),
+                ".vite".to_string(),
+            ],
+        );
+        assert_eq!(dirs, [".cache", ".vite", ".vitest"]);
+    }
+}
@@ -0,0 +1,814 @@
+use anyhow::{Context, Result, bail};
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::{CleanResults, OutcomeStatus};
+use crate::config::Config;
+use crate::project::TargetType;
+
+/// 汇总报告的格式版本
+pub const MERGED_REPORT_VERSION: u32 = 1;
+
+/// 从任意格式报告中归一化出的一条目标记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRecord {
+    pub project: Option<String>,
+    pub project_type: String,
+    pub target_type: String,
+    pub bytes: u64,
+}
+
+/// 单个输入报告的解析结果
+#[derive(Debug, Clone)]
+pub struct ParsedReport {
+    pub source: PathBuf,
+    pub version: Option<u64>,
+    pub records: Vec<ReportRecord>,
+    /// 报告中声明的项目数（若有）
+    pub declared_projects: Option<usize>,
+}
+
+/// 单个输入报告在汇总中的摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSummary {
+    pub path: PathBuf,
+    pub version: Option<u64>,
+    pub projects: usize,
+    pub total_bytes: u64,
+}
+
+/// 无法解析而被跳过的报告
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedReport {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// 多份报告合并后的汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedReport {
+    pub version: u32,
+    pub reports: usize,
+    pub total_projects: usize,
+    pub total_bytes: u64,
+    pub by_project_type: BTreeMap<String, u64>,
+    pub by_target_type: BTreeMap<String, u64>,
+    pub sources: Vec<SourceSummary>,
+    pub skipped: Vec<SkippedReport>,
+}
+
+/// 读取并合并多份报告，无法解析的报告会被记录并跳过
+pub fn merge_report_files(paths: &[PathBuf]) -> Result<MergedReport> {
+    let mut parsed = Vec::new();
+    let mut skipped = Vec::new();
+
+    for path in paths {
+        match parse_report_file(path) {
+            Ok(report) => parsed.push(report),
+            Err(e) => {
+                warn!("Skipping report {}: {:#}", path.display(), e);
+                skipped.push(SkippedReport {
+                    path: path.clone(),
+                    error: format!("{:#}", e),
+                });
+            }
+        }
+    }
+
+    if parsed.is_empty() {
+        bail!("None of the {} report(s) could be parsed", paths.len());
+    }
+
+    let mut merged = merge_reports(&parsed);
+    merged.skipped = skipped;
+    Ok(merged)
+}
+
+/// 合并已解析的报告
+pub fn merge_reports(reports: &[ParsedReport]) -> MergedReport {
+    let mut merged = MergedReport {
+        version: MERGED_REPORT_VERSION,
+        reports: reports.len(),
+        total_projects: 0,
+        total_bytes: 0,
+        by_project_type: BTreeMap::new(),
+        by_target_type: BTreeMap::new(),
+        sources: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    for report in reports {
+        let mut total_bytes = 0;
+        for record in &report.records {
+            total_bytes += record.bytes;
+            *merged
+                .by_project_type
+                .entry(record.project_type.clone())
+                .or_insert(0) += record.bytes;
+            *merged
+                .by_target_type
+                .entry(record.target_type.clone())
+                .or_insert(0) += record.bytes;
+        }
+
+        // 优先使用报告声明的项目数，否则按记录中的项目路径去重统计
+        let projects = report.declared_projects.unwrap_or_else(|| {
+            report
+                .records
+                .iter()
+                .filter_map(|r| r.project.as_deref())
+                .collect::<HashSet<_>>()
+                .len()
+        });
+
+        merged.total_projects += projects;
+        merged.total_bytes += total_bytes;
+        merged.sources.push(SourceSummary {
+            path: report.source.clone(),
+            version: report.version,
+            projects,
+            total_bytes,
+        });
+    }
+
+    merged
+}
+
+/// 将汇总写入 JSON 文件
+pub fn write_merged_report(report: &MergedReport, output: &Path) -> Result<()> {
+    let content = serde_json::to_string_pretty(report)?;
+    fs::write(output, content).context(format!(
+        "Failed to write merged report: {}",
+        output.display()
+    ))?;
+    info!("Merged report written to {}", output.display());
+    Ok(())
+}
+
+/// `--report` 输出中的一行，对应一个已清理的目标
+#[derive(Debug, Serialize)]
+struct CleanedTargetRow<'a> {
+    project_path: &'a Path,
+    target_path: &'a Path,
+    target_type: &'a TargetType,
+    bytes_removed: u64,
+    dry_run: bool,
+}
+
+/// 将清理结果写入 NDJSON 文件，每个已清理（或 dry run 时将会清理）的目标一行，
+/// 失败、已不存在和暂存到恢复清单的目标不写入
+pub fn write_ndjson_report(results: &CleanResults, dry_run: bool, output: &Path) -> Result<()> {
+    let file = fs::File::create(output)
+        .context(format!("Failed to create report: {}", output.display()))?;
+    let mut out = BufWriter::new(file);
+
+    for outcome in results
+        .outcomes
+        .iter()
+        .filter(|outcome| outcome.status == OutcomeStatus::Cleaned)
+    {
+        let row = CleanedTargetRow {
+            project_path: &outcome.project_path,
+            target_path: &outcome.target_path,
+            target_type: &outcome.target_type,
+            bytes_removed: outcome.bytes_removed,
+            dry_run,
+        };
+        serde_json::to_writer(&mut out, &row)?;
+        writeln!(out)?;
+    }
+
+    out.flush()
+        .context(format!("Failed to write report: {}", output.display()))?;
+    info!("Report written to {}", output.display());
+    Ok(())
+}
+
+/// GitHub Actions 提供的步骤摘要文件路径的环境变量
+pub const GITHUB_STEP_SUMMARY: &str = "GITHUB_STEP_SUMMARY";
+
+/// 写入步骤摘要的 Markdown：用户取消清理时没有可报告的内容，返回 None；
+/// dry run 的标题注明是预览，避免被误读为已经释放的空间
+pub fn github_step_summary(results: &CleanResults, config: &Config) -> Option<String> {
+    if results.cancelled {
+        return None;
+    }
+
+    let heading = if config.dry_run {
+        "### npmclean preview (dry run, nothing was deleted)"
+    } else {
+        "### npmclean cleanup"
+    };
+    Some(format!(
+        "{}\n\n{}",
+        heading,
+        crate::cli::format_markdown(results, config)
+    ))
+}
+
+/// 在 GitHub Actions 中把 Markdown 摘要追加到步骤摘要文件，使清理结果显示在工作流摘要页面
+///
+/// `path` 为 `GITHUB_STEP_SUMMARY` 的值，为 None 时不做任何事，返回是否写入了摘要。
+pub fn append_github_step_summary(path: Option<&Path>, markdown: &str) -> Result<bool> {
+    let Some(path) = path else {
+        return Ok(false);
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Failed to open step summary: {}", path.display()))?;
+    write!(file, "{}", markdown)?;
+    if !markdown.ends_with('\n') {
+        writeln!(file)?;
+    }
+    info!("Appended cleanup summary to {}", path.display());
+    Ok(true)
+}
+
+/// 解析单个报告文件，格式由扩展名决定，未知扩展名按内容推断
+pub fn parse_report_file(path: &Path) -> Result<ParsedReport> {
+    let content =
+        fs::read_to_string(path).context(format!("Failed to read report: {}", path.display()))?;
+
+    let mut report = match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => parse_csv_report(&content)?,
+        Some("ndjson") | Some("jsonl") => parse_ndjson_report(&content)?,
+        _ => match serde_json::from_str::<Value>(&content) {
+            Ok(value) => parse_json_report(&value)?,
+            // 整体不是合法 JSON 时尝试按 NDJSON 解析
+            Err(_) => parse_ndjson_report(&content)?,
+        },
+    };
+
+    report.source = path.to_path_buf();
+    Ok(report)
+}
+
+/// 解析 JSON 报告：支持包含 `projects` 的运行报告，以及记录数组
+///
+/// dry run 的报告没有释放任何空间，作为无法解析的报告跳过。
+fn parse_json_report(value: &Value) -> Result<ParsedReport> {
+    let mut report = empty_report();
+
+    match value {
+        Value::Array(rows) => {
+            if rows.iter().any(is_dry_run) {
+                bail!("Dry-run report: nothing was freed");
+            }
+            report
+                .records
+                .extend(rows.iter().filter_map(record_from_row));
+        }
+        Value::Object(obj) => {
+            if is_dry_run(value) {
+                bail!("Dry-run report: nothing was freed");
+            }
+            report.version = obj.get("version").and_then(Value::as_u64);
+
+            let outcomes = obj
+                .get("results")
+                .and_then(|results| results.get("outcomes"))
+                .and_then(Value::as_array);
+            if let Some(projects) = obj.get("projects").and_then(Value::as_array) {
+                report.declared_projects = Some(projects.len());
+                match outcomes {
+                    // 有清理结果时以每个目标的实际结果为准
+                    Some(outcomes) => report
+                        .records
+                        .extend(records_from_outcomes(projects, outcomes)),
+                    None => {
+                        for project in projects {
+                            report.records.extend(records_from_project(project));
+                        }
+                    }
+                }
+            } else if let Some(rows) = obj
+                .get("targets")
+                .or_else(|| obj.get("records"))
+                .and_then(Value::as_array)
+            {
+                report
+                    .records
+                    .extend(rows.iter().filter_map(record_from_row));
+            } else if let Some(total) = total_bytes_field(value) {
+                // 只有总量的旧格式报告
+                report.records.push(ReportRecord {
+                    project: None,
+                    project_type: "unknown".to_string(),
+                    target_type: "unknown".to_string(),
+                    bytes: total,
+                });
+            } else {
+                bail!("Unrecognized report structure");
+            }
+        }
+        _ => bail!("Report must be a JSON object or array"),
+    }
+
+    Ok(report)
+}
+
+/// 解析 NDJSON 报告，每行一条目标记录
+fn parse_ndjson_report(content: &str) -> Result<ParsedReport> {
+    let mut report = empty_report();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let row: Value =
+            serde_json::from_str(line).context(format!("Invalid JSON on line {}", i + 1))?;
+        if is_dry_run(&row) {
+            bail!("Dry-run report: nothing was freed");
+        }
+        if report.version.is_none() {
+            report.version = row.get("version").and_then(Value::as_u64);
+        }
+        report.records.extend(record_from_row(&row));
+    }
+
+    Ok(report)
+}
+
+/// 解析 CSV 报告，第一行必须是表头
+fn parse_csv_report(content: &str) -> Result<ParsedReport> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = match lines.next() {
+        Some(header) => split_csv_line(header),
+        None => bail!("CSV report is empty"),
+    };
+
+    let mut report = empty_report();
+    for line in lines {
+        let row = Value::Object(
+            header
+                .iter()
+                .cloned()
+                .zip(split_csv_line(line).into_iter().map(Value::String))
+                .collect(),
+        );
+        if is_dry_run(&row) {
+            bail!("Dry-run report: nothing was freed");
+        }
+        report.records.extend(record_from_row(&row));
+    }
+
+    Ok(report)
+}
+
+/// 将一个带 `targets` 的项目对象展开为记录
+fn records_from_project(project: &Value) -> Vec<ReportRecord> {
+    let path = string_field(project, &["path", "project_path", "project"]);
+    let project_type =
+        string_field(project, &["project_type", "type"]).unwrap_or_else(|| "unknown".to_string());
+
+    let targets = project
+        .get("targets")
+        .or_else(|| project.get("detected_targets"))
+        .and_then(Value::as_array);
+
+    match targets {
+        Some(targets) => targets
+            .iter()
+            .filter(|target| is_counted(target))
+            .map(|target| ReportRecord {
+                project: path.clone(),
+                project_type: project_type.clone(),
+                target_type: string_field(target, &["target_type", "type"])
+                    .unwrap_or_else(|| "unknown".to_string()),
+                bytes: bytes_field(target),
+            })
+            .collect(),
+        None => vec![ReportRecord {
+            project: path,
+            project_type,
+            target_type: "unknown".to_string(),
+            bytes: bytes_field(project),
+        }],
+    }
+}
+
+/// 清理结果中实际删除的目标，项目类型取自报告中的项目列表
+fn records_from_outcomes(projects: &[Value], outcomes: &[Value]) -> Vec<ReportRecord> {
+    let project_types: BTreeMap<String, String> = projects
+        .iter()
+        .filter_map(|project| {
+            Some((
+                string_field(project, &["path", "project_path", "project"])?,
+                string_field(project, &["project_type", "type"])?,
+            ))
+        })
+        .collect();
+
+    outcomes
+        .iter()
+        .filter_map(record_from_row)
+        .map(|mut record| {
+            if let Some(project_type) = record
+                .project
+                .as_ref()
+                .and_then(|path| project_types.get(path))
+            {
+                record.project_type = project_type.clone();
+            }
+            record
+        })
+        .collect()
+}
+
+/// 从单行记录中提取字段，兼容不同版本的字段名；未选中或未清理的目标返回 None
+fn record_from_row(row: &Value) -> Option<ReportRecord> {
+    is_counted(row).then(|| ReportRecord {
+        project: string_field(row, &["project_path", "project", "path"]),
+        project_type: string_field(row, &["project_type"]).unwrap_or_else(|| "unknown".to_string()),
+        target_type: string_field(row, &["target_type", "type"])
+            .unwrap_or_else(|| "unknown".to_string()),
+        bytes: bytes_field(row),
+    })
+}
+
+/// 记录是否计入释放的空间：没有被标记为未选中（`selected: false`），
+/// 且带 `status` 时状态为已清理
+fn is_counted(row: &Value) -> bool {
+    let selected = row.get("selected").and_then(as_bool_lenient) != Some(false);
+    let cleaned = row
+        .get("status")
+        .and_then(Value::as_str)
+        .is_none_or(|status| status.eq_ignore_ascii_case("cleaned"));
+    selected && cleaned
+}
+
+/// 报告或记录是否来自 dry run（CSV 中布尔值是字符串）
+fn is_dry_run(value: &Value) -> bool {
+    value.get("dry_run").and_then(as_bool_lenient) == Some(true)
+}
+
+fn empty_report() -> ParsedReport {
+    ParsedReport {
+        source: PathBuf::new(),
+        version: None,
+        records: Vec::new(),
+        declared_projects: None,
+    }
+}
+
+fn string_field(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| value.get(*key))
+        .find_map(|v| match v {
+            Value::String(s) if !s.is_empty() => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+}
+
+fn bytes_field(value: &Value) -> u64 {
+    ["bytes_removed", "bytes", "size", "total_size"]
+        .iter()
+        .filter_map(|key| value.get(*key))
+        .find_map(as_u64_lenient)
+        .unwrap_or(0)
+}
+
+fn total_bytes_field(value: &Value) -> Option<u64> {
+    value
+        .get("total_bytes_removed")
+        .or_else(|| value.get("total_bytes"))
+        .or_else(|| {
+            value
+                .get("results")
+                .and_then(|r| r.get("total_bytes_removed"))
+        })
+        .and_then(as_u64_lenient)
+}
+
+/// 数字字段在 CSV 中是字符串，在 JSON 中可能是整数或浮点数
+fn as_u64_lenient(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64().or_else(|| n.as_f64().map(|f| f as u64)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// 布尔字段在 CSV 中是字符串
+fn as_bool_lenient(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::String(s) => s.trim().to_lowercase().parse().ok(),
+        _ => None,
+    }
+}
+
+/// 拆分一行 CSV，支持双引号包裹和 `""` 转义
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaner::Cleaner;
+    use crate::config::Config;
+    use crate::project::{CleanTarget, Project};
+
+    #[test]
+    fn test_markdown_summary_appended_to_github_step_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let summary = dir.path().join("step_summary.md");
+        fs::write(&summary, "## Previous step\n").unwrap();
+
+        let config = Config {
+            dry_run: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(Vec::new()).unwrap();
+        let markdown = crate::cli::format_markdown(&results, &config);
+
+        assert!(append_github_step_summary(Some(&summary), &markdown).unwrap());
+        let content = fs::read_to_string(&summary).unwrap();
+        assert!(content.starts_with("## Previous step\n"));
+        assert!(content.ends_with(&markdown));
+        assert!(!append_github_step_summary(None, &markdown).unwrap());
+    }
+
+    #[test]
+    fn test_github_step_summary_labels_previews_and_skips_cancelled_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = || {
+            let mut project = Project::new(dir.path().to_path_buf());
+            fs::create_dir_all(dir.path().join("dist")).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path: dir.path().join("dist"),
+                target_type: TargetType::BuildDir,
+                size: Some(100),
+            });
+            project
+        };
+
+        let config = Config {
+            dry_run: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project()]).unwrap();
+        let summary = github_step_summary(&results, &config).unwrap();
+        assert!(summary.starts_with("### npmclean preview (dry run, nothing was deleted)"));
+        assert!(summary.contains("would be freed"));
+
+        let declining = |_: &crate::confirm::CleaningSummary| false;
+        let config = Config::default();
+        let results = Cleaner::new(&config)
+            .with_confirmer(&declining)
+            .clean(vec![project()])
+            .unwrap();
+        assert_eq!(github_step_summary(&results, &config), None);
+        assert!(dir.path().join("dist").exists());
+    }
+
+    #[test]
+    fn test_dry_run_writes_ndjson_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().join("app"));
+        for (name, target_type, size) in [
+            ("node_modules", TargetType::NodeModules, 1000),
+            ("dist", TargetType::BuildDir, 200),
+        ] {
+            let path = project.path.join(name);
+            fs::create_dir_all(&path).unwrap();
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type,
+                size: Some(size),
+            });
+        }
+
+        let config = Config {
+            dry_run: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+        let output = dir.path().join("report.ndjson");
+        write_ndjson_report(&results, config.dry_run, &output).unwrap();
+
+        let rows: Vec<Value> = fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        let mut bytes: Vec<u64> = rows
+            .iter()
+            .map(|row| row["bytes_removed"].as_u64().unwrap())
+            .collect();
+        bytes.sort();
+        assert_eq!(bytes, [200, 1000]);
+        for row in &rows {
+            assert_eq!(row["dry_run"], true);
+            assert_eq!(
+                row["project_path"],
+                dir.path().join("app").to_str().unwrap()
+            );
+            assert!(
+                row["target_path"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with(dir.path().join("app").to_str().unwrap())
+            );
+            assert!(["node_modules", "build"].contains(&row["target_type"].as_str().unwrap()));
+        }
+        // dry run 的报告没有释放空间，merge-report 不会把它计入汇总
+        assert!(parse_report_file(&output).is_err());
+        // dry run 不删除任何目录
+        assert!(dir.path().join("app/node_modules").exists());
+    }
+
+    #[test]
+    fn test_ndjson_report_skips_missing_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project::new(dir.path().join("app"));
+        fs::create_dir_all(project.path.join("dist")).unwrap();
+        for (name, target_type) in [
+            ("dist", TargetType::BuildDir),
+            ("coverage", TargetType::Coverage),
+        ] {
+            project.detected_targets.push(CleanTarget {
+                path: project.path.join(name),
+                target_type,
+                size: Some(100),
+            });
+        }
+
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let results = Cleaner::new(&config).clean(vec![project]).unwrap();
+        assert!(
+            results
+                .outcomes
+                .iter()
+                .any(|outcome| outcome.status == OutcomeStatus::Missing)
+        );
+        let output = dir.path().join("report.ndjson");
+        write_ndjson_report(&results, config.dry_run, &output).unwrap();
+
+        let rows: Vec<Value> = fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["target_type"], "build");
+        assert_eq!(rows[0]["bytes_removed"], 100);
+    }
+
+    #[test]
+    fn test_merge_two_reports() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let json_report = dir.path().join("machine-a.json");
+        fs::write(
+            &json_report,
+            r#"{
+                "version": 1,
+                "projects": [
+                    {"path": "/a/app", "project_type": "react", "targets": [
+                        {"path": "/a/app/node_modules", "target_type": "node_modules", "size": 1000},
+                        {"path": "/a/app/build", "target_type": "build", "size": 200}
+                    ]},
+                    {"path": "/a/site", "project_type": "vue", "targets": [
+                        {"path": "/a/site/node_modules", "target_type": "node_modules", "size": 500}
+                    ]}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let csv_report = dir.path().join("machine-b.csv");
+        fs::write(
+            &csv_report,
+            "project_path,project_type,target_type,bytes_removed,extra\n\
+             /b/app,react,node_modules,3000,x\n\
+             /b/app,react,cache,\"40\",y\n",
+        )
+        .unwrap();
+
+        let merged = merge_report_files(&[json_report, csv_report]).unwrap();
+
+        assert_eq!(merged.reports, 2);
+        assert_eq!(merged.total_projects, 3);
+        assert_eq!(merged.total_bytes, 4740);
+        assert_eq!(merged.by_project_type["react"], 4240);
+        assert_eq!(merged.by_project_type["vue"], 500);
+        assert_eq!(merged.by_target_type["node_modules"], 4500);
+        assert_eq!(merged.sources[0].version, Some(1));
+        assert_eq!(merged.sources[1].version, None);
+        assert!(merged.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_merge_counts_only_selected_and_cleaned_targets() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // 被排除的目标和 dry run 的报告不计入释放的空间
+        let planned = dir.path().join("planned.json");
+        fs::write(
+            &planned,
+            r#"{"version": 1, "dry_run": false, "projects": [
+                {"path": "/a/app", "project_type": "react", "targets": [
+                    {"path": "/a/app/node_modules", "target_type": "node_modules", "size": 5000, "selected": true},
+                    {"path": "/a/app/dist", "target_type": "build", "size": 7000, "selected": false}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let cleaned = dir.path().join("cleaned.json");
+        fs::write(
+            &cleaned,
+            r#"{"version": 1, "dry_run": false, "projects": [
+                {"path": "/b/app", "project_type": "vue", "targets": [
+                    {"path": "/b/app/node_modules", "target_type": "node_modules", "size": 5000, "selected": true},
+                    {"path": "/b/app/.cache", "target_type": "cache", "size": 300, "selected": true},
+                    {"path": "/b/app/dist", "target_type": "build", "size": 7000, "selected": false}
+                ]}
+            ], "results": {"outcomes": [
+                {"project_path": "/b/app", "target_path": "/b/app/node_modules", "target_type": "node_modules", "bytes_removed": 5000, "status": "cleaned"},
+                {"project_path": "/b/app", "target_path": "/b/app/.cache", "target_type": "cache", "bytes_removed": 0, "status": "missing"}
+            ]}}"#,
+        )
+        .unwrap();
+
+        let rows = dir.path().join("rows.ndjson");
+        fs::write(
+            &rows,
+            "{\"project_path\":\"/c\",\"target_type\":\"build\",\"bytes_removed\":40,\"status\":\"cleaned\"}\n\
+             {\"project_path\":\"/c\",\"target_type\":\"cache\",\"bytes_removed\":0,\"status\":\"staged\"}\n",
+        )
+        .unwrap();
+
+        let preview = dir.path().join("preview.ndjson");
+        fs::write(
+            &preview,
+            "{\"project_path\":\"/d\",\"target_type\":\"build\",\"bytes_removed\":900,\"dry_run\":true}\n",
+        )
+        .unwrap();
+
+        let merged = merge_report_files(&[planned, cleaned, rows, preview.clone()]).unwrap();
+        assert_eq!(merged.sources[0].total_bytes, 5000);
+        assert_eq!(merged.sources[1].total_bytes, 5000);
+        assert_eq!(merged.by_project_type["vue"], 5000);
+        assert_eq!(merged.sources[2].total_bytes, 40);
+        assert_eq!(merged.total_bytes, 10_040);
+        assert_eq!(merged.skipped.len(), 1);
+        assert_eq!(merged.skipped[0].path, preview);
+        assert!(merged.skipped[0].error.contains("Dry-run"));
+    }
+
+    #[test]
+    fn test_unparseable_report_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let good = dir.path().join("good.ndjson");
+        fs::write(
+            &good,
+            "{\"project_path\":\"/p\",\"target_type\":\"build\",\"bytes\":7}\n",
+        )
+        .unwrap();
+        let bad = dir.path().join("bad.json");
+        fs::write(&bad, "not a report").unwrap();
+
+        let merged = merge_report_files(&[good, bad.clone()]).unwrap();
+
+        assert_eq!(merged.total_bytes, 7);
+        assert_eq!(merged.by_project_type["unknown"], 7);
+        assert_eq!(merged.skipped.len(), 1);
+        assert_eq!(merged.skipped[0].path, bad);
+    }
+}
@@ -1,40 +1,236 @@
-use anyhow::Result;
-use log::{debug, info};
+use anyhow::{Context, Result, bail};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use log::{debug, info, warn};
 use rayon::prelude::*;
-use std::collections::HashSet;
+use regex::Regex;
+use serde_json::Value;
 use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::BufRead;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
 
 use crate::config::Config;
-use crate::project::analyzers::get_all_detectors;
-use crate::project::{CleanTarget, Project, SizeInfo, TargetType};
-use crate::utils::fs_utils::calculate_directory_size;
+use crate::manifest::STAGED_SUFFIX;
+use crate::project::analyzers::{WorkspaceDetector, get_all_detectors};
+use crate::project::{
+    CleanTarget, DefaultDetector, Project, ProjectDetector, ProjectType, SizeInfo, SkippedTarget,
+    TargetType,
+};
+use crate::project::{compiled_output, monorepo_tools, test_tools};
+use crate::utils::fs_utils::{
+    calculate_directory_size, calculate_directory_sizes, find_trash_dirs,
+};
+
+/// 扫描过程的统计，用于排查找到的项目比预期少的原因
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// 遍历过的目录数
+    pub directories_visited: usize,
+    /// 跳过的 node_modules 目录数
+    pub skipped_node_modules: usize,
+    /// 因匹配排除模式而跳过的目录数
+    pub skipped_excluded: usize,
+    /// 找到的项目目录数
+    pub projects_found: usize,
+    /// 被 `--match-regex`、`--older-than`、`--type` 或 `--exclude-type` 过滤掉的项目数
+    pub projects_filtered: usize,
+    /// 分析失败的项目数
+    pub analysis_failures: usize,
+}
+
+/// 扫描结果：项目列表和扫描统计
+#[derive(Debug, Clone)]
+pub struct ScanReport {
+    pub projects: Vec<Project>,
+    pub stats: ScanStats,
+    /// 根目录下没有找到项目、但直接子目录中有项目时给出的提示
+    pub hint: Option<String>,
+}
 
 pub struct Scanner<'a> {
     config: &'a Config,
+    match_regex: Option<Regex>,
+    exclude: ExcludeMatcher,
+    /// 按优先级排列的项目检测器，最后一个是默认检测器
+    detectors: Vec<Box<dyn ProjectDetector>>,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(config: &'a Config) -> Self {
-        Self { config }
+    pub fn new(config: &'a Config) -> Result<Self> {
+        let match_regex = match &config.match_regex {
+            Some(pattern) => Some(
+                Regex::new(pattern)
+                    .context(format!("Invalid --match-regex pattern '{}'", pattern))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            config,
+            match_regex,
+            exclude: ExcludeMatcher::new(&config.exclude, &config.scan_roots)?,
+            detectors: get_all_detectors(),
+        })
+    }
+
+    /// 添加自定义项目检测器（例如来自插件），按优先级与内置检测器合并
+    ///
+    /// 优先级相同时排在已有检测器之后；默认检测器始终最后运行。
+    pub fn with_detector(mut self, detector: Box<dyn ProjectDetector>) -> Self {
+        let priority = detector.get_priority();
+        let index = self
+            .detectors
+            .partition_point(|existing| existing.get_priority() <= priority)
+            .min(self.detectors.len() - 1);
+        self.detectors.insert(index, detector);
+        self
+    }
+
+    /// 添加多个自定义项目检测器，见 `with_detector`
+    pub fn with_detectors(self, detectors: Vec<Box<dyn ProjectDetector>>) -> Self {
+        detectors
+            .into_iter()
+            .fold(self, |scanner, detector| scanner.with_detector(detector))
     }
 
     /// 扫描指定路径下的项目
-    pub fn scan(&self, root_path: &Path) -> Result<Vec<Project>> {
-        info!("Scanning directory: {}", root_path.display());
+    pub fn scan<P: AsRef<Path>>(&self, roots: &[P]) -> Result<ScanReport> {
+        for root_path in roots {
+            let root_path = root_path.as_ref();
+            if !self.config.is_path_allowed(root_path) {
+                bail!(
+                    "Refusing to scan {}: it is outside the allowed roots",
+                    root_path.display()
+                );
+            }
+        }
+
+        let mut stats = ScanStats::default();
+        let mut hint = None;
+        let project_paths = match &self.config.packages_manifest {
+            Some(manifest) => {
+                info!("Reading package locations from {}", manifest.display());
+                read_packages_manifest(manifest)?
+            }
+            None => {
+                let mut project_paths = Vec::new();
+                let mut seen = HashSet::new();
+                for root_path in roots {
+                    let root_path = root_path.as_ref();
+                    info!("Scanning directory: {}", root_path.display());
 
-        let project_paths = self.find_project_paths(root_path)?;
+                    let found = self.find_project_paths(root_path, &mut stats)?;
+                    if found.is_empty() && !self.config.recursive && hint.is_none() {
+                        hint = self.child_projects_hint(root_path);
+                    }
+                    // 重叠的根目录会找到相同的项目，按规范化路径去重
+                    for path in found {
+                        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+                        if seen.insert(key) {
+                            project_paths.push(path);
+                        }
+                    }
+                }
+                project_paths
+            }
+        };
         info!("Found {} potential projects", project_paths.len());
+        stats.projects_found = project_paths.len();
 
-        let projects = self.analyze_projects(project_paths)?;
+        let project_paths = self.filter_by_regex(project_paths);
+        let project_paths = self.filter_by_activity(project_paths);
+
+        let (mut projects, analysis_failures) = self.analyze_projects(project_paths)?;
+        stats.analysis_failures = analysis_failures;
+        stats.projects_filtered = stats.projects_found - stats.analysis_failures - projects.len();
+        if self.config.two_phase_delete && self.config.packages_manifest.is_none() {
+            self.attach_leftover_trash(roots, &mut projects)?;
+        }
+        if self.config.recursive {
+            self.attribute_workspace_members(&mut projects)?;
+        }
         info!("Successfully analyzed {} projects", projects.len());
 
-        Ok(projects)
+        Ok(ScanReport {
+            projects,
+            stats,
+            hint,
+        })
+    }
+
+    /// 从输入中逐行读取目录路径，每个路径直接作为清理目标，不做项目检测
+    ///
+    /// 不存在或不是目录的路径会被跳过并给出警告。
+    pub fn targets_from_reader(&self, reader: impl BufRead) -> Result<ScanReport> {
+        let mut projects = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let path = PathBuf::from(line);
+            if !path.is_dir() {
+                warn!("Skipping {}: not an existing directory", path.display());
+                eprintln!(
+                    "Warning: Skipping {}: not an existing directory",
+                    path.display()
+                );
+                continue;
+            }
+
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| line.to_string());
+            let target_type = if name == "node_modules" {
+                TargetType::NodeModules
+            } else {
+                TargetType::Custom(name)
+            };
+            let size = if self.config.stats {
+                Some(calculate_directory_size(&path)?)
+            } else {
+                None
+            };
+
+            let parent = path.parent().unwrap_or(&path).to_path_buf();
+            let mut project = Project::new(parent);
+            project.detected_targets.push(CleanTarget {
+                path,
+                target_type,
+                size,
+            });
+            projects.push(project);
+        }
+
+        info!("Read {} targets from input", projects.len());
+        let stats = ScanStats {
+            projects_found: projects.len(),
+            ..ScanStats::default()
+        };
+        Ok(ScanReport {
+            projects,
+            stats,
+            hint: None,
+        })
     }
 
-    /// 查找包含 package.json 的目录
-    fn find_project_paths(&self, root_path: &Path) -> Result<Vec<PathBuf>> {
+    /// 查找项目目录（包含 package.json 或 Deno 配置）
+    fn find_project_paths(&self, root_path: &Path, stats: &mut ScanStats) -> Result<Vec<PathBuf>> {
+        if self.config.respect_gitignore {
+            return Ok(self.find_project_paths_respecting_ignores(root_path, stats));
+        }
+
         let mut project_paths = Vec::new();
         let mut visited_dirs = HashSet::new();
         let mut queue = VecDeque::new();
@@ -48,14 +244,15 @@ impl<'a> Scanner<'a> {
             }
 
             // 检查深度限制
-            if let Some(max_depth) = self.config.max_depth {
-                if depth > max_depth {
-                    continue;
-                }
+            if let Some(max_depth) = self.config.max_depth
+                && depth > max_depth
+            {
+                continue;
             }
+            stats.directories_visited += 1;
 
             // 检查是否是项目目录
-            if Project::has_package_json(&path) {
+            if Project::is_project_dir(&path) {
                 debug!("Found project at {}", path.display());
                 project_paths.push(path.clone());
 
@@ -65,6 +262,11 @@ impl<'a> Scanner<'a> {
                 }
             }
 
+            // 已达到最大深度时不再读取子目录
+            if self.config.max_depth == Some(depth) {
+                continue;
+            }
+
             // 扫描子目录
             if let Ok(entries) = fs::read_dir(&path) {
                 for entry in entries.filter_map(Result::ok) {
@@ -72,6 +274,17 @@ impl<'a> Scanner<'a> {
                         let path = entry.path();
                         // 跳过 node_modules 目录以提高性能
                         if path.file_name().is_some_and(|name| name == "node_modules") {
+                            stats.skipped_node_modules += 1;
+                            continue;
+                        }
+                        if path.file_name().is_some_and(|name| {
+                            is_npmclean_leftover(name, &self.config.trash_suffix)
+                        }) {
+                            continue;
+                        }
+                        if self.exclude.is_match(&path) {
+                            debug!("Skipping excluded directory {}", path.display());
+                            stats.skipped_excluded += 1;
                             continue;
                         }
 
@@ -84,12 +297,175 @@ impl<'a> Scanner<'a> {
         Ok(project_paths)
     }
 
-    /// 分析项目，检测项目类型并确定清理目标
-    fn analyze_projects(&self, project_paths: Vec<PathBuf>) -> Result<Vec<Project>> {
+    /// 查找项目目录，跳过被 `.gitignore`、`.npmignore` 和全局 git 排除规则忽略的目录
+    ///
+    /// 忽略规则只影响项目的查找：清理目标（通常本身就被 gitignore）是在项目根目录下
+    /// 直接检查的，不经过这里的遍历，因此仍会被清理。
+    fn find_project_paths_respecting_ignores(
+        &self,
+        root_path: &Path,
+        stats: &mut ScanStats,
+    ) -> Vec<PathBuf> {
+        let recursive = self.config.recursive;
+        let exclude = self.exclude.clone();
+        let trash_suffix = self.config.trash_suffix.clone();
+        let skipped_node_modules = Arc::new(AtomicUsize::new(0));
+        let skipped_excluded = Arc::new(AtomicUsize::new(0));
+        let (node_modules_counter, excluded_counter) = (
+            Arc::clone(&skipped_node_modules),
+            Arc::clone(&skipped_excluded),
+        );
+        let walker = WalkBuilder::new(root_path)
+            .hidden(false)
+            .parents(true)
+            .git_ignore(true)
+            .git_global(true)
+            .git_exclude(true)
+            .require_git(false)
+            .add_custom_ignore_filename(".npmignore")
+            .max_depth(self.config.max_depth)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                if entry.depth() == 0 {
+                    return true;
+                }
+                if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                    return false;
+                }
+                // 跳过 node_modules 目录以提高性能
+                if entry.file_name() == "node_modules" {
+                    node_modules_counter.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+                if is_npmclean_leftover(entry.file_name(), &trash_suffix) {
+                    return false;
+                }
+                if exclude.is_match(entry.path()) {
+                    excluded_counter.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+                // 非递归模式下不进入项目目录的子目录
+                recursive || !entry.path().parent().is_some_and(Project::is_project_dir)
+            })
+            .build();
+
+        let mut project_paths = Vec::new();
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    debug!("Skipping unreadable entry: {}", e);
+                    continue;
+                }
+            };
+            if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                continue;
+            }
+            stats.directories_visited += 1;
+            if Project::is_project_dir(entry.path()) {
+                debug!("Found project at {}", entry.path().display());
+                project_paths.push(entry.into_path());
+            }
+        }
+
+        stats.skipped_node_modules += skipped_node_modules.load(Ordering::Relaxed);
+        stats.skipped_excluded += skipped_excluded.load(Ordering::Relaxed);
+        project_paths
+    }
+
+    /// 非递归扫描没有找到项目时，检查根目录的直接子目录，列出其中的项目并建议如何找到它们
+    fn child_projects_hint(&self, root_path: &Path) -> Option<String> {
+        let mut children: Vec<PathBuf> = fs::read_dir(root_path)
+            .ok()?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_dir()))
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name().is_some_and(|name| name != "node_modules")
+                    && !self.exclude.is_match(path)
+                    && Project::is_project_dir(path)
+            })
+            .collect();
+        if children.is_empty() {
+            return None;
+        }
+        children.sort();
+
+        // 非递归模式本身会进入非项目目录，找不到子项目通常是深度限制或忽略规则造成的
+        let suggestion = if self.config.max_depth == Some(0) {
+            "--max-depth 1"
+        } else if self.config.respect_gitignore {
+            "--no-gitignore"
+        } else {
+            "--recursive"
+        };
+        let mut hint = format!(
+            "No projects found in {}, but {} subdirector{} contain{} a project:",
+            root_path.display(),
+            children.len(),
+            if children.len() == 1 { "y" } else { "ies" },
+            if children.len() == 1 { "s" } else { "" },
+        );
+        for child in &children {
+            hint.push_str(&format!("\n  - {}", child.display()));
+        }
+        hint.push_str(&format!("\nTry running again with {}.", suggestion));
+        Some(hint)
+    }
+
+    /// 设置了 `--older-than` 时，跳过最近修改过源文件的项目
+    fn filter_by_activity(&self, project_paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let Some(threshold) = self.config.older_than else {
+            return project_paths;
+        };
+
+        project_paths
+            .into_par_iter()
+            .filter(|path| {
+                // 除 node_modules 外没有文件的项目视为不活跃
+                let Some(newest) = newest_source_mtime(path) else {
+                    return true;
+                };
+                let age = SystemTime::now()
+                    .duration_since(newest)
+                    .unwrap_or(Duration::ZERO);
+                let stale = age >= threshold;
+                if !stale {
+                    debug!(
+                        "Skipping {}: source files modified {}s ago",
+                        path.display(),
+                        age.as_secs()
+                    );
+                }
+                stale
+            })
+            .collect()
+    }
+
+    /// 只保留路径匹配 `--match-regex` 的项目
+    fn filter_by_regex(&self, project_paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let Some(regex) = &self.match_regex else {
+            return project_paths;
+        };
+
+        project_paths
+            .into_iter()
+            .filter(|path| {
+                let matched = regex.is_match(&path.to_string_lossy());
+                if !matched {
+                    debug!("Skipping {} (does not match --match-regex)", path.display());
+                }
+                matched
+            })
+            .collect()
+    }
+
+    /// 分析项目，检测项目类型并确定清理目标，返回项目和分析失败的数量
+    fn analyze_projects(&self, project_paths: Vec<PathBuf>) -> Result<(Vec<Project>, usize)> {
         // 使用 rayon 进行并行处理
-        let projects: Vec<Project> = project_paths
+        let analyzed: Vec<Option<Option<Project>>> = project_paths
             .into_par_iter()
-            .filter_map(|path| match self.analyze_project(&path) {
+            .map(|path| match self.analyze_project(&path) {
                 Ok(project) => Some(project),
                 Err(e) => {
                     debug!("Failed to analyze project at {}: {}", path.display(), e);
@@ -98,21 +474,54 @@ impl<'a> Scanner<'a> {
             })
             .collect();
 
-        Ok(projects)
+        let failures = analyzed.iter().filter(|result| result.is_none()).count();
+        Ok((analyzed.into_iter().flatten().flatten().collect(), failures))
     }
 
-    /// 分析单个项目
-    fn analyze_project(&self, project_path: &Path) -> Result<Project> {
+    /// 项目类型是否通过 `--only-type`/`--type` 和 `--exclude-type` 过滤，排除优先
+    fn is_type_selected(&self, project_type: &ProjectType) -> bool {
+        !self.config.exclude_types.contains(project_type)
+            && (self.config.project_types.is_empty()
+                || self.config.project_types.contains(project_type))
+    }
+
+    /// 重新分析单个项目目录，用于在确认之后查找新出现的目标；分析失败或项目被过滤掉时返回 `None`
+    pub fn rescan_project(&self, project_path: &Path) -> Option<Project> {
+        match self.analyze_project(project_path) {
+            Ok(project) => project,
+            Err(e) => {
+                debug!("Failed to rescan {}: {}", project_path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// 分析单个项目，项目类型被过滤掉时返回 `None`
+    fn analyze_project(&self, project_path: &Path) -> Result<Option<Project>> {
         debug!("Analyzing project at {}", project_path.display());
 
         // 创建项目实例
         let mut project = Project::new(project_path.to_path_buf());
 
-        // 获取所有项目检测器
-        let detectors = get_all_detectors();
+        // 先解析 package.json，框架检测器依赖其中的依赖信息
+        if Project::has_package_json(project_path) {
+            match DefaultDetector::parse_package_json(project_path) {
+                Ok(package_info) => project.package_info = Some(package_info),
+                Err(e) => debug!(
+                    "Failed to parse package.json at {}: {}",
+                    project_path.display(),
+                    e
+                ),
+            }
+        }
+
+        if let Some(members) = WorkspaceDetector::member_globs(project_path) {
+            project.workspace_members = members;
+        }
 
-        // 按优先级顺序尝试每个检测器
-        for detector in &detectors {
+        // 按优先级顺序尝试每个检测器，记住匹配的检测器，都不匹配时使用默认检测器
+        let mut project_detector = self.detectors[self.detectors.len() - 1].as_ref();
+        for detector in &self.detectors {
             match detector.detect(&mut project) {
                 Ok(true) => {
                     debug!(
@@ -120,6 +529,7 @@ impl<'a> Scanner<'a> {
                         project_path.display(),
                         project.project_type
                     );
+                    project_detector = detector.as_ref();
                     break;
                 }
                 Ok(false) => continue,
@@ -130,128 +540,229 @@ impl<'a> Scanner<'a> {
             }
         }
 
+        // 没有识别出框架的工作区根目录显示为 Monorepo，仍使用默认检测器的目标
+        if !project.workspace_members.is_empty()
+            && matches!(
+                project.project_type,
+                ProjectType::NodeJs | ProjectType::Unknown
+            )
+        {
+            project.project_type = ProjectType::Monorepo;
+        }
+
+        // 在确定目标和计算大小之前按类型过滤
+        if !self.is_type_selected(&project.project_type) {
+            debug!(
+                "Skipping {} ({} projects are not selected)",
+                project_path.display(),
+                project.project_type
+            );
+            return Ok(None);
+        }
+
         // 确定清理目标
-        self.determine_clean_targets(&mut project, &detectors)?;
+        self.determine_clean_targets(&mut project, project_detector)?;
+
+        if self.config.check_broken_links {
+            project.broken_links = broken_links(&project.path.join("node_modules"));
+        }
 
         // 如果需要统计，计算大小信息
         if self.config.stats {
             self.calculate_size_info(&mut project)?;
+            if self.config.dedupe_by_lockfile {
+                project.lockfile_hash = lockfile_hash(&project.path);
+            }
+        }
+
+        Ok(Some(project))
+    }
+
+    /// node_modules 内部可单独清理的目录：缓存目录作为缓存目标注册，
+    /// 开启 `--pnpm-store` 时 pnpm 的虚拟存储目录作为独立目标注册
+    fn node_modules_cache_targets(
+        &self,
+        project: &Project,
+        detector: &dyn ProjectDetector,
+        node_modules_path: &Path,
+    ) -> Result<Vec<CleanTarget>> {
+        let mut targets = Vec::new();
+
+        let store_path = node_modules_path.join(PNPM_VIRTUAL_STORE);
+        if self.config.clean_pnpm_store && store_path.is_dir() {
+            debug!("Found pnpm virtual store: {}", store_path.display());
+
+            targets.push(CleanTarget {
+                path: store_path,
+                target_type: TargetType::Custom(PNPM_VIRTUAL_STORE.to_string()),
+                size: None,
+            });
+        }
+
+        if !self.config.should_clean(&TargetType::CacheDir) {
+            return Ok(targets);
         }
 
-        Ok(project)
+        let mut cache_dirs = detector.get_node_modules_cache_dirs(project);
+        test_tools::merge_dirs(
+            &mut cache_dirs,
+            test_tools::node_modules_cache_dirs(project),
+        );
+        test_tools::merge_dirs(
+            &mut cache_dirs,
+            monorepo_tools::node_modules_cache_dirs(project),
+        );
+
+        for dir_name in cache_dirs {
+            let dir_path = node_modules_path.join(&dir_name);
+            if dir_path.is_dir() {
+                debug!("Found node_modules cache directory: {}", dir_path.display());
+
+                targets.push(CleanTarget {
+                    path: dir_path,
+                    target_type: TargetType::CacheDir,
+                    size: None,
+                });
+            }
+        }
+
+        Ok(targets)
+    }
+
+    /// node_modules 目录本身，或局部清理模式下其内部的缓存目录
+    fn node_modules_targets(
+        &self,
+        project: &Project,
+        detector: &dyn ProjectDetector,
+        node_modules_path: PathBuf,
+    ) -> Result<Vec<CleanTarget>> {
+        if !node_modules_path.exists() {
+            return Ok(Vec::new());
+        }
+        if self.config.prune_node_modules_caches {
+            // 局部清理模式：只注册 node_modules 内部的缓存目录
+            return self.node_modules_cache_targets(project, detector, &node_modules_path);
+        }
+
+        debug!(
+            "Found node_modules directory: {}",
+            node_modules_path.display()
+        );
+
+        // 不删除 node_modules 时，其中的缓存（如 webpack 的 .cache/webpack）仍可单独清理
+        let mut targets = Vec::new();
+        if !self.config.should_clean(&TargetType::NodeModules) {
+            targets.extend(self.node_modules_cache_targets(
+                project,
+                detector,
+                &node_modules_path,
+            )?);
+        }
+
+        targets.push(CleanTarget {
+            path: node_modules_path,
+            target_type: TargetType::NodeModules,
+            size: None,
+        });
+
+        Ok(targets)
     }
 
     /// 确定项目的清理目标
     fn determine_clean_targets(
         &self,
         project: &mut Project,
-        detectors: &[Box<dyn crate::project::ProjectDetector>],
+        project_detector: &dyn ProjectDetector,
     ) -> Result<()> {
         let mut targets = Vec::new();
 
-        // 找到适合当前项目的检测器
-        let project_detector = detectors
-            .iter()
-            .find(|d| {
-                if let Ok(true) = d.detect(&mut project.clone()) {
-                    return true;
-                }
-                false
-            })
-            .unwrap_or(&detectors[detectors.len() - 1]); // 使用默认检测器
-
         // 添加 node_modules - 总是检查node_modules，即使配置未启用
         // 这样我们至少能显示它，用户可以决定是否清理
         let node_modules_path = project.path.join("node_modules");
-        if node_modules_path.exists() {
-            debug!(
-                "Found node_modules directory: {}",
-                node_modules_path.display()
-            );
-
-            let size = if self.config.stats {
-                let size = calculate_directory_size(&node_modules_path)?;
-                debug!("node_modules size: {} bytes", size);
-                Some(size)
-            } else {
-                None
-            };
+        targets.extend(self.node_modules_targets(project, project_detector, node_modules_path)?);
 
-            targets.push(CleanTarget {
-                path: node_modules_path,
-                target_type: TargetType::NodeModules,
-                size,
-            });
+        // 递归扫描时工作区成员的 node_modules 归属到根项目（根 node_modules 中包含 pnpm 的
+        // .pnpm 存储），预览时一起显示
+        if self.config.recursive {
+            for member in workspace_member_dirs(&project.path, &project.workspace_members) {
+                targets.extend(self.node_modules_targets(
+                    project,
+                    project_detector,
+                    member.join("node_modules"),
+                )?);
+            }
         }
 
         // 添加构建目录
-        if self.config.clean_build_dirs {
+        if self.config.should_clean(&TargetType::BuildDir) {
             // 获取适合项目类型的构建目录
-            let build_dirs = project_detector.get_build_dirs(project);
+            let mut build_dirs = project_detector.get_build_dirs(project);
+            test_tools::merge_dirs(&mut build_dirs, compiled_output::build_dirs(project));
 
             for dir_name in build_dirs {
                 let dir_path = project.path.join(&dir_name);
                 if dir_path.exists() && dir_path.is_dir() {
                     debug!("Found build directory: {}", dir_path.display());
 
-                    let size = if self.config.stats {
-                        Some(calculate_directory_size(&dir_path)?)
-                    } else {
-                        None
-                    };
-
                     targets.push(CleanTarget {
                         path: dir_path,
                         target_type: TargetType::BuildDir,
-                        size,
+                        size: None,
                     });
                 }
             }
         }
 
-        // 添加缓存目录
-        if self.config.clean_cache_dirs {
-            let cache_dirs = project_detector.get_cache_dirs(project);
+        // 添加缓存目录（`unified_targets` 时跟随构建目录开关）
+        if self.config.should_clean(&TargetType::CacheDir) {
+            let mut cache_dirs = project_detector.get_cache_dirs(project);
+            test_tools::merge_dirs(&mut cache_dirs, test_tools::cache_dirs(project));
+            test_tools::merge_dirs(&mut cache_dirs, monorepo_tools::cache_dirs(project));
 
             for dir_name in cache_dirs {
                 let dir_path = project.path.join(&dir_name);
                 if dir_path.exists() && dir_path.is_dir() {
                     debug!("Found cache directory: {}", dir_path.display());
 
-                    let size = if self.config.stats {
-                        Some(calculate_directory_size(&dir_path)?)
-                    } else {
-                        None
-                    };
+                    targets.push(CleanTarget {
+                        path: dir_path,
+                        target_type: TargetType::CacheDir,
+                        size: None,
+                    });
+                }
+            }
+
+            if self.config.deep_cache {
+                let max_depth = self.config.max_depth.unwrap_or(DEEP_CACHE_MAX_DEPTH);
+                for dir_path in nested_cache_dirs(&project.path, max_depth) {
+                    if targets.iter().any(|t| dir_path.starts_with(&t.path)) {
+                        continue;
+                    }
+                    debug!("Found nested cache directory: {}", dir_path.display());
 
                     targets.push(CleanTarget {
                         path: dir_path,
                         target_type: TargetType::CacheDir,
-                        size,
+                        size: None,
                     });
                 }
             }
         }
 
         // 添加覆盖率目录
-        if self.config.clean_coverage_dirs {
-            let coverage_dirs = project_detector.get_coverage_dirs(project);
+        if self.config.should_clean(&TargetType::Coverage) {
+            let mut coverage_dirs = project_detector.get_coverage_dirs(project);
+            test_tools::merge_dirs(&mut coverage_dirs, test_tools::coverage_dirs(project));
 
             for dir_name in coverage_dirs {
                 let dir_path = project.path.join(&dir_name);
                 if dir_path.exists() && dir_path.is_dir() {
                     debug!("Found coverage directory: {}", dir_path.display());
 
-                    let size = if self.config.stats {
-                        Some(calculate_directory_size(&dir_path)?)
-                    } else {
-                        None
-                    };
-
                     targets.push(CleanTarget {
                         path: dir_path,
                         target_type: TargetType::Coverage,
-                        size,
+                        size: None,
                     });
                 }
             }
@@ -259,41 +770,287 @@ impl<'a> Scanner<'a> {
 
         // 处理用户指定的自定义目标
         for target_name in &self.config.custom_targets {
-            let target_path = project.path.join(target_name);
-            if target_path.exists() {
+            let target_paths = if is_glob_pattern(target_name) {
+                glob_target_paths(&project.path, target_name, self.config.ignore_case)
+            } else if self.config.ignore_case {
+                find_path_ignore_case(&project.path, target_name)
+                    .into_iter()
+                    .collect()
+            } else {
+                vec![project.path.join(target_name)]
+            };
+            for target_path in target_paths {
+                if !target_path.exists() {
+                    continue;
+                }
                 debug!("Found custom target: {}", target_path.display());
 
-                let size = if self.config.stats {
-                    Some(calculate_directory_size(&target_path)?)
-                } else {
-                    None
-                };
-
                 targets.push(CleanTarget {
                     path: target_path,
                     target_type: TargetType::Custom(target_name.clone()),
-                    size,
+                    size: None,
                 });
             }
         }
 
-        // 应用过滤规则
-        targets.retain(|target| !self.is_excluded(&target.path));
+        self.measure_targets(&mut targets)?;
+
+        // 应用过滤规则，记录被跳过的目标及原因
+        project.detected_targets.clear();
+        project.skipped_targets.clear();
+        for target in targets {
+            match self.skip_reason(&project.path, &target) {
+                Some(reason) => {
+                    debug!("Skipping {} ({})", target.path.display(), reason);
+                    project
+                        .skipped_targets
+                        .push(SkippedTarget { target, reason });
+                }
+                None => project.detected_targets.push(target),
+            }
+        }
+
+        if self.config.largest_only {
+            keep_largest_target(project, self.config);
+        }
+
+        Ok(())
+    }
+
+    /// 是否需要计算该类型目标的大小：开启统计、`--largest-only` 或设置了最小清理大小时才计算
+    ///
+    /// `--no-node-modules-size` 只影响统计，过滤规则需要的大小仍会计算。
+    fn needs_size(&self, target_type: &TargetType) -> bool {
+        let stats = self.config.stats
+            && !(self.config.skip_node_modules_size && *target_type == TargetType::NodeModules);
+        stats
+            || self.config.largest_only
+            || self.config.confirm_above.is_some()
+            || self.config.min_size_for(target_type).is_some()
+    }
+
+    /// 计算目标大小，所有目标在一次遍历中完成，
+    /// 嵌套的目标（如 node_modules 中的 .cache）不会被重复遍历
+    fn measure_targets(&self, targets: &mut [CleanTarget]) -> Result<()> {
+        let paths: Vec<&Path> = targets
+            .iter()
+            .filter(|target| self.needs_size(&target.target_type))
+            .map(|target| target.path.as_path())
+            .collect();
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let sizes = calculate_directory_sizes(&paths)?;
+        for target in targets.iter_mut() {
+            if let Some(&size) = sizes.get(&target.path) {
+                debug!("{} size: {} bytes", target.path.display(), size);
+                target.size = Some(size);
+            }
+        }
+        Ok(())
+    }
+
+    /// 判断目标是否应被过滤规则跳过，返回跳过原因
+    fn skip_reason(&self, project_path: &Path, target: &CleanTarget) -> Option<String> {
+        if self.is_excluded(&target.path) {
+            return Some("matches an exclude pattern".to_string());
+        }
+
+        // 设置了最小清理大小的类型总会计算大小，见 `needs_size`
+        if let (Some(size), Some(min_size)) =
+            (target.size, self.config.min_size_for(&target.target_type))
+            && size < min_size
+        {
+            return Some(format!(
+                "{} bytes is below the {} byte threshold",
+                size, min_size
+            ));
+        }
+
+        // 浅层统计条目数，不依赖大小统计
+        if let Some(min_entries) = self.config.node_modules_min_entries
+            && target.target_type == TargetType::NodeModules
+        {
+            let entries = fs::read_dir(&target.path).map_or(0, |entries| entries.count());
+            if entries < min_entries {
+                return Some(format!(
+                    "{} top-level entries is below the minimum of {}",
+                    entries, min_entries
+                ));
+            }
+        }
+
+        // 只保留近期创建的构建/缓存目录
+        if let Some(window) = self.config.created_within
+            && matches!(
+                target.target_type,
+                TargetType::BuildDir | TargetType::CacheDir
+            )
+            && !is_created_within(&target.path, window)
+        {
+            return Some("created before the --created-within window".to_string());
+        }
+
+        // 局部清理模式下，不允许删除受保护路径及其父目录
+        if self.config.prune_node_modules_caches && self.is_protected(project_path, &target.path) {
+            return Some("protected path".to_string());
+        }
+
+        if !self.config.clean_root_node_modules && self.is_root_node_modules(project_path, target) {
+            return Some("root node_modules is kept (--keep-root-node-modules)".to_string());
+        }
+
+        None
+    }
+
+    /// 目标是否为某个扫描根目录项目自身的 node_modules（按规范化路径比较）
+    fn is_root_node_modules(&self, project_path: &Path, target: &CleanTarget) -> bool {
+        if target.target_type != TargetType::NodeModules
+            || target.path != project_path.join("node_modules")
+        {
+            return false;
+        }
+        let Ok(project_path) = fs::canonicalize(project_path) else {
+            return false;
+        };
+        self.config
+            .scan_roots
+            .iter()
+            .filter_map(|root| fs::canonicalize(root).ok())
+            .any(|root| root == project_path)
+    }
+
+    /// 把两阶段删除中断后留下的残留目录作为清理目标，与其他目标一起预览和确认
+    ///
+    /// 残留目录归属到包含它的最深的项目，不在任何项目中时归到它所在的目录。
+    /// 排除模式、允许的根目录和受保护路径（按去掉后缀的原路径判断）同样适用。
+    fn attach_leftover_trash<P: AsRef<Path>>(
+        &self,
+        roots: &[P],
+        projects: &mut Vec<Project>,
+    ) -> Result<()> {
+        let suffix = &self.config.trash_suffix;
+        let max_depth = self.config.max_depth.unwrap_or(TRASH_MAX_DEPTH);
+        let mut seen = HashSet::new();
+
+        for root in roots {
+            let root = root.as_ref();
+            for trash in find_trash_dirs(root, suffix, max_depth, self.config.respect_gitignore) {
+                // 与项目查找一样，位于被排除目录之中的残留目录也被排除
+                let excluded = trash
+                    .ancestors()
+                    .take_while(|ancestor| *ancestor != root)
+                    .any(|ancestor| self.is_excluded(ancestor));
+                if !seen.insert(trash.clone()) || excluded || !self.config.is_path_allowed(&trash) {
+                    continue;
+                }
+                let Some(parent) = trash.parent().map(Path::to_path_buf) else {
+                    continue;
+                };
+
+                let owner = projects
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, project)| trash.starts_with(&project.path))
+                    .max_by_key(|(_, project)| project.path.components().count())
+                    .map(|(index, _)| index);
+                let project_path =
+                    owner.map_or(parent.clone(), |index| projects[index].path.clone());
+                let name = trash.file_name().unwrap_or_default().to_string_lossy();
+                let original = parent.join(&name[..name.len() - suffix.len()]);
+                // 残留目录是整个被重命名的目标，只有它本身是（或位于）受保护路径时才保留
+                let protected = self
+                    .config
+                    .protected_paths
+                    .iter()
+                    .any(|protected| original.starts_with(project_path.join(protected)));
+                if protected {
+                    debug!("Keeping leftover {}: protected path", trash.display());
+                    continue;
+                }
+
+                let mut target = CleanTarget {
+                    path: trash,
+                    target_type: TargetType::Custom(LEFTOVER_TRASH_TARGET.to_string()),
+                    size: None,
+                };
+                self.measure_targets(std::slice::from_mut(&mut target))?;
+                debug!("Found leftover trash directory {}", target.path.display());
 
-        project.detected_targets = targets;
+                match owner {
+                    Some(index) => projects[index].detected_targets.push(target),
+                    None => match projects.iter_mut().find(|p| p.path == parent) {
+                        Some(project) => project.detected_targets.push(target),
+                        None => {
+                            let mut project = Project::new(parent);
+                            project.detected_targets.push(target);
+                            projects.push(project);
+                        }
+                    },
+                }
+            }
+        }
         Ok(())
     }
 
     /// 检查路径是否在排除列表中
     fn is_excluded(&self, path: &Path) -> bool {
-        for pattern in &self.config.exclude {
-            if let Ok(glob) = globset::Glob::new(pattern) {
-                if glob.compile_matcher().is_match(path) {
-                    return true;
-                }
+        self.exclude.is_match(path)
+    }
+
+    /// 检查目标是否是受保护路径，或者包含受保护路径
+    fn is_protected(&self, project_path: &Path, target_path: &Path) -> bool {
+        self.config.protected_paths.iter().any(|protected| {
+            let protected_path = project_path.join(protected);
+            target_path.starts_with(&protected_path) || protected_path.starts_with(target_path)
+        })
+    }
+
+    /// 从成员项目中移除已归属到工作区根项目的目标，成员因此没有任何目标时不再单独列出
+    fn attribute_workspace_members(&self, projects: &mut Vec<Project>) -> Result<()> {
+        let owned: HashSet<PathBuf> = projects
+            .iter()
+            .filter(|project| !project.workspace_members.is_empty())
+            .flat_map(|project| {
+                project
+                    .detected_targets
+                    .iter()
+                    .chain(
+                        project
+                            .skipped_targets
+                            .iter()
+                            .map(|skipped| &skipped.target),
+                    )
+                    .map(|target| target.path.clone())
+            })
+            .collect();
+        if owned.is_empty() {
+            return Ok(());
+        }
+
+        projects.retain_mut(|project| {
+            if !project.workspace_members.is_empty() {
+                return true;
+            }
+            let before = project.detected_targets.len() + project.skipped_targets.len();
+            project
+                .detected_targets
+                .retain(|target| !owned.contains(&target.path));
+            project
+                .skipped_targets
+                .retain(|skipped| !owned.contains(&skipped.target.path));
+            let after = project.detected_targets.len() + project.skipped_targets.len();
+            after > 0 || before == after
+        });
+
+        if self.config.stats {
+            for project in projects.iter_mut() {
+                self.calculate_size_info(project)?;
             }
         }
-        false
+
+        Ok(())
     }
 
     /// 计算项目大小信息
@@ -329,3 +1086,2338 @@ impl<'a> Scanner<'a> {
         Ok(())
     }
 }
+
+/// 预先编译的排除模式
+///
+/// 每个路径依次用文件名、相对于（最近的）扫描根目录的路径和完整路径匹配，
+/// 因此 `build`、`packages/*/dist`、`**/coverage` 和绝对路径模式都能生效。
+#[derive(Clone)]
+struct ExcludeMatcher {
+    set: GlobSet,
+    roots: Vec<PathBuf>,
+}
+
+impl ExcludeMatcher {
+    /// 编译排除模式，排除模式始终不区分大小写；任一模式无效时报错
+    fn new(patterns: &[String], roots: &[PathBuf]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .context(format!("Invalid exclude pattern '{}'", pattern))?;
+            builder.add(glob);
+        }
+
+        Ok(Self {
+            set: builder
+                .build()
+                .context("Failed to compile exclude patterns")?,
+            roots: roots.to_vec(),
+        })
+    }
+
+    fn is_match(&self, path: &Path) -> bool {
+        if self.set.is_empty() {
+            return false;
+        }
+
+        let relative = self
+            .roots
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .min_by_key(|relative| relative.components().count());
+        path.file_name().is_some_and(|name| self.set.is_match(name))
+            || relative.is_some_and(|relative| self.set.is_match(relative))
+            || self.set.is_match(path)
+    }
+}
+
+/// 不区分大小写地解析相对路径，精确匹配优先；任一部分不存在时返回 None
+fn find_path_ignore_case(base: &Path, relative: &str) -> Option<PathBuf> {
+    let mut current = base.to_path_buf();
+    for component in Path::new(relative).components() {
+        let name = component.as_os_str();
+        let exact = current.join(name);
+        if exact.exists() {
+            current = exact;
+            continue;
+        }
+
+        let wanted = name.to_string_lossy().to_lowercase();
+        current = fs::read_dir(&current)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .find(|entry| entry.file_name().to_string_lossy().to_lowercase() == wanted)?
+            .path();
+    }
+    Some(current)
+}
+
+/// node_modules 中悬空或循环的符号链接
+///
+/// 只检查顶层包和作用域（`@scope/`）下的包，以及 `.bin` 中的可执行链接，
+/// 读取链接目标而不遍历整个目录树。
+fn broken_links(node_modules: &Path) -> Vec<PathBuf> {
+    let entries = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+            .unwrap_or_default()
+    };
+
+    let mut candidates = Vec::new();
+    for path in entries(node_modules) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let is_dir = fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir());
+        if is_dir && (name.starts_with('@') || name == ".bin") {
+            candidates.extend(entries(&path));
+        } else {
+            candidates.push(path);
+        }
+    }
+
+    let mut broken: Vec<PathBuf> = candidates
+        .into_iter()
+        .filter(|path| {
+            // 符号链接本身存在，但跟随链接失败：目标不存在或链接成环
+            fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+                && fs::metadata(path).is_err()
+        })
+        .collect();
+    broken.sort();
+    broken
+}
+
+/// 自定义目标是否包含 glob 元字符，不包含时按普通路径处理
+fn is_glob_pattern(target: &str) -> bool {
+    target.contains(['*', '?', '[', '{'])
+}
+
+/// 相对于项目根目录解析 glob 形式的自定义目标，每个匹配的路径作为一个目标
+///
+/// `*` 不跨越目录分隔符，`**` 可以匹配任意层级。不进入 node_modules 和 .git
+/// （除非模式本身以它们开头），也不进入已匹配的目录，避免产生嵌套的目标。
+fn glob_target_paths(project_path: &Path, pattern: &str, ignore_case: bool) -> Vec<PathBuf> {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let glob = match GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .case_insensitive(ignore_case)
+        .build()
+    {
+        Ok(glob) => glob.compile_matcher(),
+        Err(e) => {
+            warn!(
+                "Ignoring invalid custom target pattern '{}': {}",
+                pattern, e
+            );
+            return Vec::new();
+        }
+    };
+    // 不含 `**` 时，匹配的深度不会超过模式的层级数
+    let max_depth = if pattern.contains("**") {
+        usize::MAX
+    } else {
+        pattern.split('/').count()
+    };
+    let enters = |name: &str| pattern.starts_with(name);
+
+    let mut matches = Vec::new();
+    let mut walker = WalkDir::new(project_path)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name();
+            (name != "node_modules" || enters("node_modules")) && (name != ".git" || enters(".git"))
+        });
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let relative = entry
+            .path()
+            .strip_prefix(project_path)
+            .unwrap_or(entry.path());
+        if glob.is_match(relative) {
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+            matches.push(entry.into_path());
+        }
+    }
+    matches
+}
+
+/// 只保留项目中最大的待清理目标，其余待清理目标移到跳过列表
+///
+/// 只在会被清理的目标（类型已选中，且已通过最小大小等过滤规则）中选择，
+/// 不会被清理的目标（如未选中的 node_modules）保留原样，不参与比较。
+fn keep_largest_target(project: &mut Project, config: &Config) {
+    let Some(largest) = project
+        .detected_targets
+        .iter()
+        .enumerate()
+        .filter(|(_, target)| config.should_clean(&target.target_type))
+        .max_by_key(|(_, target)| target.size.unwrap_or(0))
+        .map(|(index, _)| index)
+    else {
+        return;
+    };
+
+    let kept = project.detected_targets.swap_remove(largest);
+    let (others, not_cleaned): (Vec<_>, Vec<_>) = project
+        .detected_targets
+        .drain(..)
+        .partition(|target| config.should_clean(&target.target_type));
+    for target in others {
+        project.skipped_targets.push(SkippedTarget {
+            target,
+            reason: "not the largest target (--largest-only)".to_string(),
+        });
+    }
+    project.detected_targets = not_cleaned;
+    project.detected_targets.push(kept);
+}
+
+/// 用于比较依赖树的 lockfile，pnpm 项目已经使用共享存储，不参与比较
+const LOCKFILES: [&str; 3] = ["package-lock.json", "yarn.lock", "bun.lockb"];
+
+/// 项目 lockfile 文件名和内容的哈希；没有 lockfile 或已使用 pnpm 时返回 None
+fn lockfile_hash(project_path: &Path) -> Option<u64> {
+    if project_path.join("pnpm-lock.yaml").exists() {
+        return None;
+    }
+
+    LOCKFILES.iter().find_map(|name| {
+        let content = fs::read(project_path.join(name)).ok()?;
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        content.hash(&mut hasher);
+        Some(hasher.finish())
+    })
+}
+
+/// 估算改用 pnpm 的共享存储后可节省的 node_modules 空间（仅供参考）
+///
+/// lockfile 相同的项目依赖树相同，每组只需保留一份：节省量为每组的总大小减去其中最大的一份。
+/// 需要开启统计，否则没有大小信息，结果为 0。
+pub fn shared_store_savings(projects: &[Project]) -> u64 {
+    let mut groups: HashMap<u64, Vec<u64>> = HashMap::new();
+    for project in projects {
+        if let (Some(hash), Some(size_info)) = (project.lockfile_hash, &project.size_info)
+            && size_info.node_modules_size > 0
+        {
+            groups
+                .entry(hash)
+                .or_default()
+                .push(size_info.node_modules_size);
+        }
+    }
+
+    groups
+        .values()
+        .filter(|sizes| sizes.len() > 1)
+        .map(|sizes| sizes.iter().sum::<u64>() - sizes.iter().max().copied().unwrap_or(0))
+        .sum()
+}
+
+/// pnpm 虚拟存储目录（相对于 node_modules）
+const PNPM_VIRTUAL_STORE: &str = ".pnpm";
+
+/// 查找工作区成员时最多遍历的目录深度
+const WORKSPACE_MAX_DEPTH: usize = 6;
+
+/// 工作区根目录下匹配成员模式且包含 package.json 的目录，`!` 开头的模式表示排除
+fn workspace_member_dirs(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+
+    let mut include = GlobSetBuilder::new();
+    let mut exclude = GlobSetBuilder::new();
+    for pattern in patterns {
+        let (builder, pattern) = match pattern.strip_prefix('!') {
+            Some(negated) => (&mut exclude, negated),
+            None => (&mut include, pattern.as_str()),
+        };
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => debug!("Ignoring invalid workspace pattern '{}': {}", pattern, e),
+        }
+    }
+    let (Ok(include), Ok(exclude)) = (include.build(), exclude.build()) else {
+        return Vec::new();
+    };
+
+    WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(WORKSPACE_MAX_DEPTH)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.file_type().is_dir()
+                && entry.file_name() != "node_modules"
+                && entry.file_name() != ".git"
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            include.is_match(relative)
+                && !exclude.is_match(relative)
+                && Project::has_package_json(entry.path())
+        })
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// `--deep-cache` 在未设置 `max_depth` 时查找 `.cache` 目录的最大深度（相对于项目根目录）
+const DEEP_CACHE_MAX_DEPTH: usize = 8;
+
+/// 项目源码树中的 `.cache` 目录，不进入 node_modules、.git 和嵌套的项目
+fn nested_cache_dirs(project_path: &Path, max_depth: usize) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut walker = WalkDir::new(project_path)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.file_type().is_dir()
+                && entry.file_name() != "node_modules"
+                && entry.file_name() != ".git"
+                && !Project::has_package_json(entry.path())
+        });
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if entry.file_name() == ".cache" {
+            // 缓存目录内部不再查找
+            walker.skip_current_dir();
+            found.push(entry.into_path());
+        }
+    }
+    found
+}
+
+/// `--drill`：项目 node_modules 中最大的 `top` 个直接子目录及其大小，按大小降序
+///
+/// 作用域目录（如 `@esbuild`）作为一个整体统计；符号链接不跟随。
+pub fn largest_packages(project_path: &Path, top: usize) -> Result<Vec<(String, u64)>> {
+    let node_modules = project_path.join("node_modules");
+    if !node_modules.is_dir() {
+        bail!("{} has no node_modules directory", project_path.display());
+    }
+
+    let entries = fs::read_dir(&node_modules)
+        .context(format!("Failed to read {}", node_modules.display()))?;
+    let mut packages = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| {
+            let size = calculate_directory_size(&entry.path())?;
+            Ok((entry.file_name().to_string_lossy().to_string(), size))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    packages.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    packages.truncate(top);
+    Ok(packages)
+}
+
+/// 是否为 npmclean 自己留下的目录（恢复清单的暂存目录或两阶段删除的残留目录），
+/// 查找项目时不进入其中，否则其中的 package.json 会被当作项目
+fn is_npmclean_leftover(name: &std::ffi::OsStr, trash_suffix: &str) -> bool {
+    let name = name.to_string_lossy();
+    name.contains(STAGED_SUFFIX) || (!trash_suffix.is_empty() && name.ends_with(trash_suffix))
+}
+
+/// 查找两阶段删除残留目录时默认最多遍历的目录深度，`max_depth` 优先
+const TRASH_MAX_DEPTH: usize = 8;
+
+/// 两阶段删除残留目录作为自定义目标时的名称
+const LEFTOVER_TRASH_TARGET: &str = "two-phase delete leftover";
+
+/// 查找项目活跃度时最多遍历的目录深度和条目数，避免在大型项目上耗时过长
+const ACTIVITY_MAX_DEPTH: usize = 8;
+const ACTIVITY_MAX_ENTRIES: usize = 20_000;
+
+/// 项目中（忽略 node_modules 和 .git）最近修改的文件的修改时间，没有文件时返回 None
+fn newest_source_mtime(project_path: &Path) -> Option<SystemTime> {
+    WalkDir::new(project_path)
+        .max_depth(ACTIVITY_MAX_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || (entry.file_name() != "node_modules" && entry.file_name() != ".git")
+        })
+        .filter_map(Result::ok)
+        .take(ACTIVITY_MAX_ENTRIES)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .max()
+}
+
+/// 检查目录是否在给定时间窗口内创建
+///
+/// 优先使用创建时间，不支持时回退到修改时间。修改时间早于创建时间时
+/// （例如从归档中恢复并保留了时间戳）取较早的那个。
+fn is_created_within(path: &Path, window: Duration) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+
+    let created = match (metadata.created(), metadata.modified()) {
+        (Ok(created), Ok(modified)) => created.min(modified),
+        (Ok(created), Err(_)) => created,
+        (Err(_), Ok(modified)) => modified,
+        (Err(_), Err(_)) => return false,
+    };
+
+    match SystemTime::now().duration_since(created) {
+        Ok(age) => age <= window,
+        // 时间戳在未来，视为刚刚创建
+        Err(_) => true,
+    }
+}
+
+/// 读取包目录清单，相对路径以清单所在目录为基准
+pub fn read_packages_manifest(manifest: &Path) -> Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(manifest).context(format!(
+        "Failed to read packages manifest: {}",
+        manifest.display()
+    ))?;
+    let base_dir = manifest.parent().unwrap_or_else(|| Path::new("."));
+
+    let paths = parse_packages_manifest(&content, base_dir).context(format!(
+        "Failed to parse packages manifest: {}",
+        manifest.display()
+    ))?;
+
+    Ok(paths
+        .into_iter()
+        .filter(|path| {
+            let exists = path.is_dir();
+            if !exists {
+                debug!("Manifest entry does not exist: {}", path.display());
+            }
+            exists
+        })
+        .collect())
+}
+
+/// 解析包目录清单
+///
+/// 支持 `pnpm list --json`（`path` 字段）、`lerna ls --json`（`location` 字段）
+/// 以及纯字符串数组。
+fn parse_packages_manifest(content: &str, base_dir: &Path) -> Result<Vec<PathBuf>> {
+    let json: Value = serde_json::from_str(content)?;
+
+    let entries = match &json {
+        Value::Array(entries) => entries,
+        Value::Object(obj) => match obj.get("packages").and_then(Value::as_array) {
+            Some(entries) => entries,
+            None => bail!("Expected a JSON array of packages"),
+        },
+        _ => bail!("Expected a JSON array of packages"),
+    };
+
+    let mut seen = HashSet::new();
+    let mut paths = Vec::new();
+
+    for entry in entries {
+        let location = match entry {
+            Value::String(path) => Some(path.as_str()),
+            Value::Object(obj) => obj
+                .get("path")
+                .or_else(|| obj.get("location"))
+                .and_then(Value::as_str),
+            _ => None,
+        };
+
+        let Some(location) = location else {
+            debug!("Ignoring manifest entry without a path: {}", entry);
+            continue;
+        };
+
+        let path = base_dir.join(location);
+        if seen.insert(path.clone()) {
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ProjectType;
+    use std::collections::HashMap;
+    use std::io;
+
+    #[test]
+    fn test_partial_node_modules_cleanup_never_targets_bin() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/.bin")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/.cache")).unwrap();
+
+        let config = Config {
+            prune_node_modules_caches: true,
+            // 即使用户显式指定，也不能清理 .bin
+            custom_targets: vec!["node_modules/.bin".to_string(), "node_modules".to_string()],
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let targets = &projects[0].detected_targets;
+
+        let bin_path = dir.path().join("node_modules/.bin");
+        assert!(
+            targets
+                .iter()
+                .all(|t| !t.path.starts_with(&bin_path) && !bin_path.starts_with(&t.path))
+        );
+        assert!(
+            targets
+                .iter()
+                .any(|t| t.path.ends_with("node_modules/.cache")
+                    && t.target_type == TargetType::CacheDir)
+        );
+        assert!(
+            targets
+                .iter()
+                .all(|t| t.target_type != TargetType::NodeModules)
+        );
+    }
+
+    #[test]
+    fn test_webpack_cache_is_cache_target_when_node_modules_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"devDependencies": {"webpack": "^5.0.0"}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/.cache/webpack")).unwrap();
+
+        let scan = |clean_node_modules: bool| {
+            let config = Config {
+                clean_node_modules,
+                ..Config::default()
+            };
+            Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects
+        };
+
+        let projects = scan(false);
+        let targets = &projects[0].detected_targets;
+        assert!(
+            targets
+                .iter()
+                .any(|t| t.path.ends_with("node_modules/.cache")
+                    && t.target_type == TargetType::CacheDir)
+        );
+
+        // 删除整个 node_modules 时不再单独注册其中的缓存
+        let projects = scan(true);
+        let targets = &projects[0].detected_targets;
+        assert!(
+            targets
+                .iter()
+                .all(|t| t.target_type != TargetType::CacheDir)
+        );
+    }
+
+    #[test]
+    fn test_pnpm_virtual_store_is_separate_target() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        let store = dir.path().join("node_modules/.pnpm/left-pad@1.3.0");
+        fs::create_dir_all(&store).unwrap();
+        fs::write(store.join("index.js"), vec![0u8; 100]).unwrap();
+
+        let scan = |clean_pnpm_store: bool, prune_node_modules_caches: bool| {
+            let config = Config {
+                stats: true,
+                clean_node_modules: false,
+                clean_pnpm_store,
+                prune_node_modules_caches,
+                ..Config::default()
+            };
+            Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects
+        };
+
+        for prune in [false, true] {
+            let projects = scan(true, prune);
+            let store_target = projects[0]
+                .detected_targets
+                .iter()
+                .find(|t| t.target_type == TargetType::Custom(".pnpm".to_string()))
+                .unwrap();
+            assert_eq!(store_target.path, dir.path().join("node_modules/.pnpm"));
+            assert_eq!(store_target.size, Some(100));
+        }
+
+        let projects = scan(false, false);
+        assert!(
+            projects[0]
+                .detected_targets
+                .iter()
+                .all(|t| !t.path.ends_with(".pnpm"))
+        );
+    }
+
+    #[test]
+    fn test_older_than_skips_recently_modified_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(90 * 86_400);
+        let touch = |path: &Path, time: SystemTime| {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        };
+
+        for name in ["active", "stale"] {
+            let project = dir.path().join(name);
+            fs::create_dir_all(project.join("src")).unwrap();
+            fs::create_dir_all(project.join("node_modules/pkg")).unwrap();
+            fs::write(project.join("package.json"), "{}").unwrap();
+            fs::write(project.join("src/index.js"), "x").unwrap();
+            // node_modules 中新安装的文件不算活跃
+            fs::write(project.join("node_modules/pkg/index.js"), "x").unwrap();
+            touch(&project.join("package.json"), long_ago);
+        }
+        touch(&dir.path().join("stale/src/index.js"), long_ago);
+
+        let scan = |older_than| {
+            let config = Config {
+                recursive: true,
+                older_than,
+                ..Config::default()
+            };
+            let mut names: Vec<_> = Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects
+                .iter()
+                .map(|p| p.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(scan(Some(Duration::from_secs(30 * 86_400))), vec!["stale"]);
+        assert_eq!(scan(None), vec!["active", "stale"]);
+        assert_eq!(
+            newest_source_mtime(&dir.path().join("stale")),
+            Some(long_ago)
+        );
+    }
+
+    #[test]
+    fn test_created_within_keeps_only_recent_build_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::create_dir_all(dir.path().join("build")).unwrap();
+
+        // 将 build 目录的时间戳回拨到 30 天前
+        let old = SystemTime::now() - Duration::from_secs(30 * 86_400);
+        fs::File::open(dir.path().join("build"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let config = Config {
+            created_within: Some(Duration::from_secs(3600)),
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let targets = &projects[0].detected_targets;
+
+        assert!(targets.iter().any(|t| t.path.ends_with("dist")));
+        assert!(!targets.iter().any(|t| t.path.ends_with("build")));
+    }
+
+    #[test]
+    fn test_node_modules_min_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, entries) in [("empty", 0), ("tiny", 2), ("full", 50)] {
+            let project = dir.path().join(name);
+            let node_modules = project.join("node_modules");
+            fs::create_dir_all(&node_modules).unwrap();
+            fs::write(project.join("package.json"), "{}").unwrap();
+            for i in 0..entries {
+                fs::create_dir_all(node_modules.join(format!("pkg-{}", i))).unwrap();
+            }
+        }
+
+        let config = Config {
+            recursive: true,
+            node_modules_min_entries: Some(5),
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        let has_node_modules = |name: &str| {
+            projects
+                .iter()
+                .find(|p| p.path.ends_with(name))
+                .unwrap()
+                .detected_targets
+                .iter()
+                .any(|t| t.target_type == TargetType::NodeModules)
+        };
+        assert!(!has_node_modules("empty"));
+        assert!(!has_node_modules("tiny"));
+        assert!(has_node_modules("full"));
+    }
+
+    #[test]
+    fn test_min_size_applies_without_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for (name, size) in [("dist", 4096), ("build", 100)] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("file"), vec![0u8; size]).unwrap();
+        }
+
+        let config = Config {
+            min_size: Some(1024),
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let targets = &projects[0].detected_targets;
+
+        // 未开启 stats 时也会为阈值计算大小
+        assert!(!config.stats);
+        assert_eq!(targets.len(), 1);
+        assert!(targets[0].path.ends_with("dist"));
+        assert_eq!(targets[0].size, Some(4096));
+        assert!(
+            projects[0].skipped_targets[0]
+                .target
+                .path
+                .ends_with("build")
+        );
+    }
+
+    #[test]
+    fn test_min_size_by_type_is_applied_per_type() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for (name, size) in [("node_modules", 500), ("dist", 50), ("build", 5)] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("file"), vec![0u8; size]).unwrap();
+        }
+
+        let config = Config {
+            stats: true,
+            min_size: Some(10),
+            min_size_by_type: HashMap::from([(TargetType::NodeModules, 1000)]),
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let targets = &projects[0].detected_targets;
+
+        // node_modules 低于按类型的阈值，build 低于全局阈值
+        assert!(!targets.iter().any(|t| t.path.ends_with("node_modules")));
+        assert!(targets.iter().any(|t| t.path.ends_with("dist")));
+        assert!(!targets.iter().any(|t| t.path.ends_with("build")));
+    }
+
+    #[test]
+    fn test_largest_only_keeps_single_biggest_target() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for (name, size) in [
+            ("node_modules", 500),
+            ("dist", 5000),
+            (".cache", 50),
+            ("coverage", 10),
+        ] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("file"), vec![0u8; size]).unwrap();
+        }
+
+        // 未开启 --stats 时也会计算大小
+        let config = Config {
+            largest_only: true,
+            clean_cache_dirs: true,
+            clean_coverage_dirs: true,
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        let kept: Vec<_> = projects[0].detected_targets.iter().collect();
+        assert_eq!(kept.len(), 1);
+        assert!(kept[0].path.ends_with("dist"));
+        assert_eq!(kept[0].size, Some(5000));
+        assert_eq!(projects[0].skipped_targets.len(), 3);
+    }
+
+    #[test]
+    fn test_root_node_modules_skipped_when_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        for project in ["", "packages/app"] {
+            fs::create_dir_all(dir.path().join(project).join("node_modules/pkg")).unwrap();
+            fs::write(dir.path().join(project).join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+
+        let config = Config {
+            recursive: true,
+            clean_root_node_modules: false,
+            // 扫描根目录以相对形式给出时也按规范化路径匹配
+            scan_roots: vec![dir.path().join("packages/..")],
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        let root = projects.iter().find(|p| p.path == dir.path()).unwrap();
+        let detected: Vec<&Path> = root
+            .detected_targets
+            .iter()
+            .map(|t| t.path.as_path())
+            .collect();
+        assert_eq!(detected, [dir.path().join("dist")]);
+        assert_eq!(
+            root.skipped_targets[0].target.path,
+            dir.path().join("node_modules")
+        );
+        assert!(root.skipped_targets[0].reason.contains("kept"));
+
+        // 子项目的 node_modules 照常清理
+        let app = projects
+            .iter()
+            .find(|p| p.path.ends_with("packages/app"))
+            .unwrap();
+        assert_eq!(
+            app.detected_targets[0].path,
+            dir.path().join("packages/app/node_modules")
+        );
+    }
+
+    #[test]
+    fn test_largest_only_ignores_targets_that_will_not_be_cleaned() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for (name, size) in [("node_modules", 9000), ("dist", 50), (".cache", 500)] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("file"), vec![0u8; size]).unwrap();
+        }
+
+        // node_modules 最大但不会被清理；.cache 低于最小清理大小
+        let config = Config {
+            largest_only: true,
+            clean_node_modules: false,
+            clean_cache_dirs: true,
+            min_size_by_type: HashMap::from([(TargetType::CacheDir, 1000)]),
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        let cleaned: Vec<&CleanTarget> = projects[0]
+            .detected_targets
+            .iter()
+            .filter(|t| config.should_clean(&t.target_type))
+            .collect();
+        assert_eq!(cleaned.len(), 1);
+        assert!(cleaned[0].path.ends_with("dist"));
+    }
+
+    #[test]
+    fn test_single_pass_sizes_match_per_target_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        // node_modules 中嵌套 .cache 和 .pnpm，两者都作为独立目标注册
+        for (sub, files) in [
+            ("node_modules/pkg/lib", 400),
+            ("node_modules/.cache/webpack", 300),
+            ("node_modules/.pnpm/pkg@1.0.0", 300),
+            ("dist/assets", 200),
+            ("coverage", 100),
+        ] {
+            let sub_dir = dir.path().join(sub);
+            fs::create_dir_all(&sub_dir).unwrap();
+            for file in 0..files {
+                fs::write(sub_dir.join(format!("f{}", file)), vec![0u8; file % 97]).unwrap();
+            }
+        }
+
+        let config = Config {
+            stats: true,
+            clean_node_modules: false,
+            clean_pnpm_store: true,
+            ..Config::default()
+        };
+        let started = std::time::Instant::now();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        debug!(
+            "Single pass sizing of 1300 files took {:?}",
+            started.elapsed()
+        );
+
+        let project = &projects[0];
+        let all_targets: Vec<_> = project
+            .detected_targets
+            .iter()
+            .chain(
+                project
+                    .skipped_targets
+                    .iter()
+                    .map(|skipped| &skipped.target),
+            )
+            .collect();
+        assert_eq!(all_targets.len(), 5);
+        for target in all_targets {
+            assert_eq!(
+                target.size,
+                Some(calculate_directory_size(&target.path).unwrap()),
+                "{}",
+                target.path.display()
+            );
+        }
+
+        let naive_total: u64 = project
+            .detected_targets
+            .iter()
+            .map(|target| calculate_directory_size(&target.path).unwrap())
+            .sum();
+        assert_eq!(project.size_info.as_ref().unwrap().total_size, naive_total);
+    }
+
+    #[test]
+    fn test_stats_without_node_modules_size() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for (name, size) in [("node_modules", 4096), ("dist", 300)] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("file"), vec![0u8; size]).unwrap();
+        }
+
+        let config = Config {
+            stats: true,
+            skip_node_modules_size: true,
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        let size_of = |name: &str| {
+            projects[0]
+                .detected_targets
+                .iter()
+                .find(|t| t.path.ends_with(name))
+                .unwrap()
+                .size
+        };
+        assert_eq!(size_of("node_modules"), None);
+        assert_eq!(size_of("dist"), Some(300));
+
+        // 总量不包含大小未知的 node_modules
+        let size_info = projects[0].size_info.as_ref().unwrap();
+        assert_eq!(size_info.total_size, 300);
+        assert_eq!(size_info.node_modules_size, 0);
+    }
+
+    #[test]
+    fn test_fresh_project_detected_without_package_json() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("deno.json"), r#"{"imports": {}}"#).unwrap();
+        fs::write(dir.path().join("fresh.gen.ts"), "export default {};").unwrap();
+        fs::create_dir_all(dir.path().join("_fresh")).unwrap();
+        fs::create_dir_all(dir.path().join("vendor")).unwrap();
+
+        let config = Config::default();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let targets = &projects[0].detected_targets;
+
+        assert_eq!(projects[0].project_type, ProjectType::Fresh);
+        assert!(
+            targets
+                .iter()
+                .any(|t| t.path.ends_with("_fresh") && t.target_type == TargetType::BuildDir)
+        );
+        assert!(
+            targets
+                .iter()
+                .any(|t| t.path.ends_with("vendor") && t.target_type == TargetType::CacheDir)
+        );
+    }
+
+    #[test]
+    fn test_scan_refuses_root_outside_allowed_roots() {
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::create_dir_all(allowed.path().join("app")).unwrap();
+        fs::write(allowed.path().join("app/package.json"), "{}").unwrap();
+
+        let config = Config {
+            allowed_roots: vec![allowed.path().to_path_buf()],
+            ..Config::default()
+        };
+        let scanner = Scanner::new(&config).unwrap();
+
+        assert!(scanner.scan(&[&allowed.path().join("app")]).is_ok());
+        assert!(scanner.scan(&[outside.path()]).is_err());
+    }
+
+    #[test]
+    fn test_targets_from_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_modules = dir.path().join("app/node_modules");
+        let cache = dir.path().join("lib/.cache");
+        fs::create_dir_all(&node_modules).unwrap();
+        fs::create_dir_all(&cache).unwrap();
+
+        let input = format!(
+            "{}\n\n{}\n{}\n",
+            node_modules.display(),
+            dir.path().join("missing").display(),
+            cache.display()
+        );
+        let config = Config::default();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .targets_from_reader(io::Cursor::new(input))
+            .unwrap()
+            .projects;
+
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0].path, dir.path().join("app"));
+        assert_eq!(
+            projects[0].detected_targets[0].target_type,
+            TargetType::NodeModules
+        );
+        assert_eq!(
+            projects[1].detected_targets[0].target_type,
+            TargetType::Custom(".cache".to_string())
+        );
+
+        // 通过清理器按常规流程删除
+        let config = Config {
+            force: true,
+            ..Config::default()
+        };
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(projects)
+            .unwrap();
+        assert_eq!(results.cleaned_targets, 2);
+        assert!(!node_modules.exists());
+        assert!(!cache.exists());
+    }
+
+    #[test]
+    fn test_exclude_patterns_match_name_relative_and_absolute_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let web = dir.path().join("web");
+        fs::create_dir_all(&web).unwrap();
+        fs::write(web.join("package.json"), "{}").unwrap();
+        for name in ["build", "dist", "coverage"] {
+            fs::create_dir_all(web.join(name)).unwrap();
+        }
+
+        let remaining = |pattern: String| {
+            let config = Config {
+                exclude: vec![pattern],
+                scan_roots: vec![dir.path().to_path_buf()],
+                recursive: true,
+                ..Config::default()
+            };
+            let projects = Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects;
+            let mut names: Vec<String> = projects[0]
+                .detected_targets
+                .iter()
+                .filter(|t| t.target_type != TargetType::NodeModules)
+                .map(|t| t.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // 只写目录名
+        assert_eq!(remaining("build".to_string()), ["coverage", "dist"]);
+        assert_eq!(remaining("**/coverage".to_string()), ["build", "dist"]);
+        // 相对于扫描根目录
+        assert_eq!(remaining("web/dist".to_string()), ["build", "coverage"]);
+        // 绝对路径
+        assert_eq!(
+            remaining(web.join("dist").display().to_string()),
+            ["build", "coverage"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_exclude_pattern_fails_at_construction() {
+        let config = Config {
+            exclude: vec!["dist".to_string(), "packages/[ab".to_string()],
+            ..Config::default()
+        };
+        let error = Scanner::new(&config).err().unwrap();
+        assert!(
+            format!("{:#}", error).contains("Invalid exclude pattern 'packages/[ab'"),
+            "{:#}",
+            error
+        );
+
+        // 有效模式的匹配结果不变
+        let matcher = ExcludeMatcher::new(&["dist".to_string()], &[]).unwrap();
+        assert!(matcher.is_match(Path::new("/work/app/dist")));
+        assert!(!matcher.is_match(Path::new("/work/app/build")));
+    }
+
+    #[test]
+    fn test_exclude_pattern_ignores_case() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::create_dir_all(dir.path().join("build")).unwrap();
+
+        let config = Config {
+            exclude: vec!["**/DIST".to_string()],
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let targets = &projects[0].detected_targets;
+
+        assert!(!targets.iter().any(|t| t.path.ends_with("dist")));
+        assert!(targets.iter().any(|t| t.path.ends_with("build")));
+    }
+
+    #[test]
+    fn test_custom_target_glob_matches_every_package() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for name in [
+            "packages/a/dist",
+            "packages/b/dist",
+            "packages/c/src/dist",
+            "apps/web/.cache",
+            "apps/web/src/.cache",
+            "node_modules/pkg/.cache",
+        ] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+
+        let custom_targets = |pattern: &str| {
+            let config = Config {
+                custom_targets: vec![pattern.to_string()],
+                ..Config::default()
+            };
+            let projects = Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects;
+            let mut paths: Vec<_> = projects[0]
+                .detected_targets
+                .iter()
+                .filter(|t| t.target_type == TargetType::Custom(pattern.to_string()))
+                .map(|t| t.path.clone())
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            custom_targets("packages/*/dist"),
+            [
+                dir.path().join("packages/a/dist"),
+                dir.path().join("packages/b/dist"),
+            ]
+        );
+        // `**` 匹配任意层级，但不进入 node_modules
+        assert_eq!(
+            custom_targets("**/.cache"),
+            [
+                dir.path().join("apps/web/.cache"),
+                dir.path().join("apps/web/src/.cache"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlink_flags_corrupted_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_modules = dir.path().join("node_modules");
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(node_modules.join("@scope")).unwrap();
+        fs::create_dir_all(node_modules.join("healthy")).unwrap();
+        std::os::unix::fs::symlink(node_modules.join("healthy"), node_modules.join("linked"))
+            .unwrap();
+        std::os::unix::fs::symlink(
+            node_modules.join(".pnpm/missing"),
+            node_modules.join("@scope/gone"),
+        )
+        .unwrap();
+
+        let scan = |check_broken_links| {
+            let config = Config {
+                check_broken_links,
+                ..Config::default()
+            };
+            Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects
+        };
+
+        assert!(scan(false)[0].broken_links.is_empty());
+        let projects = scan(true);
+        assert_eq!(
+            projects[0].broken_links,
+            vec![node_modules.join("@scope/gone")]
+        );
+        let warning = crate::cli::format_broken_installs(&projects).unwrap();
+        assert!(warning.contains("1 projects have dangling symlinks"));
+        assert!(warning.contains(&dir.path().display().to_string()));
+    }
+
+    // 大小写不敏感的文件系统上精确匹配就能找到目标
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_custom_target_ignore_case() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join(".Turbo")).unwrap();
+
+        let mut config = Config {
+            custom_targets: vec![".turbo".to_string()],
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        assert!(projects[0].detected_targets.is_empty());
+
+        config.ignore_case = true;
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        assert!(
+            projects[0]
+                .detected_targets
+                .iter()
+                .any(|t| t.path == dir.path().join(".Turbo"))
+        );
+    }
+
+    #[test]
+    fn test_match_regex_selects_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["shop", "shop-legacy", "blog-archived", "blog"] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("package.json"), "{}").unwrap();
+        }
+
+        let config = Config {
+            recursive: true,
+            match_regex: Some("-(legacy|archived)$".to_string()),
+            ..Config::default()
+        };
+        let mut names: Vec<_> = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects
+            .iter()
+            .map(|p| p.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["blog-archived", "shop-legacy"]);
+    }
+
+    #[test]
+    fn test_gitignored_directories_are_not_scanned() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "temp/\n").unwrap();
+        for name in ["packages/web", "temp/scratch"] {
+            fs::create_dir_all(dir.path().join(name).join("dist")).unwrap();
+            fs::write(dir.path().join(name).join("package.json"), "{}").unwrap();
+        }
+        // 项目自己的 .gitignore 忽略了 dist，但它仍然是清理目标
+        fs::write(dir.path().join("packages/web/.gitignore"), "dist/\n").unwrap();
+
+        let scan = |respect_gitignore: bool| {
+            let config = Config {
+                recursive: true,
+                respect_gitignore,
+                ..Config::default()
+            };
+            Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects
+        };
+
+        let projects = scan(true);
+        assert_eq!(projects.len(), 1);
+        assert!(projects[0].path.ends_with("packages/web"));
+        assert_eq!(projects[0].detected_targets.len(), 1);
+        assert!(
+            !projects
+                .iter()
+                .any(|p| p.path.starts_with(dir.path().join("temp")))
+        );
+
+        assert_eq!(scan(false).len(), 2);
+    }
+
+    #[test]
+    fn test_staged_and_trash_dirs_are_not_scanned_for_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "app",
+            "app/packages.npmclean-staged/old",
+            "legacy.npmclean-trash/tool",
+        ] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("package.json"), "{}").unwrap();
+        }
+
+        for respect_gitignore in [true, false] {
+            let config = Config {
+                recursive: true,
+                respect_gitignore,
+                ..Config::default()
+            };
+            let report = Scanner::new(&config).unwrap().scan(&[dir.path()]).unwrap();
+            let paths: Vec<&Path> = report.projects.iter().map(|p| p.path.as_path()).collect();
+            assert_eq!(paths, [dir.path().join("app")]);
+        }
+    }
+
+    #[test]
+    fn test_scan_stats_count_synthetic_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        for project in ["app", "libs/ui", "legacy"] {
+            fs::create_dir_all(dir.path().join(project)).unwrap();
+            fs::write(dir.path().join(project).join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(dir.path().join("app/node_modules/pkg")).unwrap();
+
+        for respect_gitignore in [true, false] {
+            let config = Config {
+                recursive: true,
+                respect_gitignore,
+                exclude: vec!["**/legacy".to_string()],
+                match_regex: Some("app$".to_string()),
+                ..Config::default()
+            };
+            let report = Scanner::new(&config).unwrap().scan(&[dir.path()]).unwrap();
+
+            assert_eq!(report.projects.len(), 1);
+            assert_eq!(
+                report.stats,
+                ScanStats {
+                    // 根目录、app、libs、libs/ui
+                    directories_visited: 4,
+                    skipped_node_modules: 2,
+                    skipped_excluded: 1,
+                    projects_found: 2,
+                    projects_filtered: 1,
+                    analysis_failures: 0,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_workspace_members_node_modules_grouped_under_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package.json"), r#"{"name": "repo"}"#).unwrap();
+        fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'packages/*'\n  - 'apps/*'\n  - '!apps/legacy'\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("node_modules/.pnpm")).unwrap();
+        for member in ["packages/a", "packages/b", "apps/web", "apps/legacy"] {
+            fs::create_dir_all(root.join(member).join("node_modules")).unwrap();
+            fs::write(root.join(member).join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(root.join("apps/web/dist")).unwrap();
+
+        let expected: Vec<PathBuf> = ["", "apps/web", "packages/a", "packages/b"]
+            .iter()
+            .map(|member| root.join(member).join("node_modules"))
+            .collect();
+
+        for recursive in [true, false] {
+            let config = Config {
+                recursive,
+                ..Config::default()
+            };
+            let projects = Scanner::new(&config)
+                .unwrap()
+                .scan(&[root])
+                .unwrap()
+                .projects;
+
+            let monorepo = projects
+                .iter()
+                .find(|p| p.project_type == ProjectType::Monorepo)
+                .unwrap();
+            assert_eq!(monorepo.path, root);
+            let node_modules: Vec<PathBuf> = monorepo
+                .detected_targets
+                .iter()
+                .filter(|t| t.target_type == TargetType::NodeModules)
+                .map(|t| t.path.clone())
+                .collect();
+            // 不递归时只清理根目录自己的 node_modules
+            if recursive {
+                assert_eq!(node_modules, expected);
+            } else {
+                assert_eq!(node_modules, [root.join("node_modules")]);
+            }
+
+            // 成员的 node_modules 不会重复出现在成员项目中
+            let other_targets: Vec<&CleanTarget> = projects
+                .iter()
+                .filter(|p| p.project_type != ProjectType::Monorepo)
+                .flat_map(|p| &p.detected_targets)
+                .collect();
+            assert!(other_targets.iter().all(|t| !expected.contains(&t.path)));
+            if recursive {
+                // 只有构建目录的成员仍单独列出，被排除的成员保留自己的 node_modules
+                let mut paths: Vec<&Path> = projects.iter().map(|p| p.path.as_path()).collect();
+                paths.sort();
+                assert_eq!(
+                    paths,
+                    [root, &root.join("apps/legacy"), &root.join("apps/web")]
+                );
+                assert_eq!(other_targets.len(), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_workspace_root_keeps_framework_and_default_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*"], "dependencies": {"next": "^14.0.0"}}"#,
+        )
+        .unwrap();
+        for target in [".next", "node_modules"] {
+            fs::create_dir_all(root.join(target)).unwrap();
+        }
+
+        let config = Config::default();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[root])
+            .unwrap()
+            .projects;
+        assert_eq!(projects[0].project_type, ProjectType::NextJs);
+        assert_eq!(projects[0].workspace_members, ["packages/*"]);
+        assert!(
+            projects[0]
+                .detected_targets
+                .iter()
+                .any(|t| t.path == root.join(".next"))
+        );
+
+        // 没有框架的工作区根目录仍清理默认的构建目录
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*"]}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("dist")).unwrap();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[root])
+            .unwrap()
+            .projects;
+        assert_eq!(projects[0].project_type, ProjectType::Monorepo);
+        assert!(
+            projects[0]
+                .detected_targets
+                .iter()
+                .any(|t| t.path == root.join("dist"))
+        );
+    }
+
+    #[test]
+    fn test_only_type_discards_other_project_types() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, dependency) in [("shop", "react"), ("blog", "vue")] {
+            let root = dir.path().join(name);
+            fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+            fs::write(
+                root.join("package.json"),
+                format!(r#"{{"dependencies": {{"{}": "^3.0.0"}}}}"#, dependency),
+            )
+            .unwrap();
+        }
+
+        let scan = |exclude_types: Vec<ProjectType>| {
+            let config = Config {
+                stats: true,
+                project_types: vec!["react".parse().unwrap()],
+                exclude_types,
+                ..Config::default()
+            };
+            Scanner::new(&config).unwrap().scan(&[dir.path()]).unwrap()
+        };
+
+        let report = scan(Vec::new());
+        assert_eq!(report.projects.len(), 1);
+        assert_eq!(report.projects[0].project_type, ProjectType::React);
+        assert!(
+            report
+                .projects
+                .iter()
+                .flat_map(|p| &p.detected_targets)
+                .all(|t| !t.path.starts_with(dir.path().join("blog")))
+        );
+        assert_eq!(report.stats.projects_filtered, 1);
+        assert_eq!(report.stats.analysis_failures, 0);
+
+        // 同时被选中和排除时以排除为准
+        let report = scan(vec![ProjectType::React]);
+        assert!(report.projects.is_empty());
+        assert_eq!(report.stats.projects_filtered, 2);
+    }
+
+    #[test]
+    fn test_excluded_type_contributes_no_cleaned_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, dependency) in [("admin", "@angular/core"), ("site", "react")] {
+            let root = dir.path().join(name);
+            fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+            fs::write(
+                root.join("package.json"),
+                format!(r#"{{"dependencies": {{"{}": "^17.0.0"}}}}"#, dependency),
+            )
+            .unwrap();
+        }
+
+        let config = Config {
+            force: true,
+            exclude_types: vec!["ANGULAR".parse().unwrap()],
+            ..Config::default()
+        };
+        let report = Scanner::new(&config).unwrap().scan(&[dir.path()]).unwrap();
+        assert_eq!(report.stats.projects_filtered, 1);
+        assert!(
+            report
+                .projects
+                .iter()
+                .all(|p| p.project_type != ProjectType::Angular)
+        );
+
+        let results = crate::cleaner::Cleaner::new(&config)
+            .clean(report.projects)
+            .unwrap();
+        assert_eq!(results.cleaned_targets, 1);
+        assert!(dir.path().join("admin/node_modules").exists());
+        assert!(!dir.path().join("site/node_modules").exists());
+    }
+
+    /// 统计 `detect` 调用次数的检测器包装
+    struct CountingDetector {
+        inner: Box<dyn ProjectDetector>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ProjectDetector for CountingDetector {
+        fn detect(&self, project: &mut Project) -> Result<bool> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.detect(project)
+        }
+
+        fn get_build_dirs(&self, project: &Project) -> Vec<String> {
+            self.inner.get_build_dirs(project)
+        }
+    }
+
+    #[test]
+    fn test_detectors_run_once_per_project() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b", "c"] {
+            fs::create_dir_all(dir.path().join(name).join("dist")).unwrap();
+            fs::write(dir.path().join(name).join("package.json"), "{}").unwrap();
+        }
+
+        let config = Config::default();
+        let mut scanner = Scanner::new(&config).unwrap();
+        let calls: Vec<Arc<AtomicUsize>> = scanner
+            .detectors
+            .iter()
+            .map(|_| Arc::new(AtomicUsize::new(0)))
+            .collect();
+        scanner.detectors = get_all_detectors()
+            .into_iter()
+            .zip(&calls)
+            .map(|(inner, calls)| {
+                Box::new(CountingDetector {
+                    inner,
+                    calls: Arc::clone(calls),
+                }) as Box<dyn ProjectDetector>
+            })
+            .collect();
+
+        let projects = scanner.scan(&[dir.path()]).unwrap().projects;
+        assert_eq!(projects.len(), 3);
+        // 普通 Node.js 项目依次经过每个检测器，每个检测器对每个项目只调用一次
+        for calls in &calls {
+            assert_eq!(calls.load(Ordering::SeqCst), 3);
+        }
+        // 匹配到的默认检测器仍用于确定构建目录
+        assert!(
+            projects
+                .iter()
+                .all(|p| p.detected_targets.iter().any(|t| t.path.ends_with("dist")))
+        );
+    }
+
+    /// 识别带有 `remix.config.js` 的项目的自定义检测器
+    struct RemixDetector;
+
+    impl ProjectDetector for RemixDetector {
+        fn detect(&self, project: &mut Project) -> Result<bool> {
+            Ok(project.path.join("remix.config.js").exists())
+        }
+
+        fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+            vec!["public/build".to_string()]
+        }
+
+        // Remix 项目也依赖 React，需要先于 React 检测器运行
+        fn get_priority(&self) -> u8 {
+            70
+        }
+    }
+
+    #[test]
+    fn test_custom_detector_influences_scan_results() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"react": "^18.0.0"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("remix.config.js"), "module.exports = {};").unwrap();
+        fs::create_dir_all(dir.path().join("public/build")).unwrap();
+
+        let config = Config::default();
+        let has_remix_build = |scanner: Scanner| {
+            scanner.scan(&[dir.path()]).unwrap().projects[0]
+                .detected_targets
+                .iter()
+                .any(|t| t.path == dir.path().join("public/build"))
+        };
+
+        // 内置的 React 检测器不知道 public/build
+        assert!(!has_remix_build(Scanner::new(&config).unwrap()));
+        // 自定义检测器按优先级先于 React 检测器运行
+        assert!(has_remix_build(
+            Scanner::new(&config)
+                .unwrap()
+                .with_detector(Box::new(RemixDetector))
+        ));
+    }
+
+    /// 总是匹配、构建目录和优先级可配置的检测器
+    struct FixedDetector {
+        build_dir: &'static str,
+        priority: u8,
+    }
+
+    impl ProjectDetector for FixedDetector {
+        fn detect(&self, _project: &mut Project) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn get_build_dirs(&self, _project: &Project) -> Vec<String> {
+            vec![self.build_dir.to_string()]
+        }
+
+        fn get_priority(&self) -> u8 {
+            self.priority
+        }
+    }
+
+    #[test]
+    fn test_highest_priority_matching_detector_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for name in ["low", "high", "dist"] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+
+        let config = Config::default();
+        // 添加顺序与优先级相反，数字较小的检测器仍然先运行
+        let scanner = Scanner::new(&config)
+            .unwrap()
+            .with_detector(Box::new(FixedDetector {
+                build_dir: "low",
+                priority: 60,
+            }))
+            .with_detector(Box::new(FixedDetector {
+                build_dir: "high",
+                priority: 40,
+            }));
+        let projects = scanner.scan(&[dir.path()]).unwrap().projects;
+
+        let build_dirs: Vec<_> = projects[0]
+            .detected_targets
+            .iter()
+            .filter(|t| t.target_type == TargetType::BuildDir)
+            .map(|t| t.path.clone())
+            .collect();
+        assert_eq!(build_dirs, vec![dir.path().join("high")]);
+    }
+
+    #[test]
+    fn test_plugin_detectors_produce_targets_during_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"react": "^18.0.0"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("example.config.js"), "module.exports = {};").unwrap();
+        fs::create_dir_all(dir.path().join("example-build")).unwrap();
+
+        let mut registry = crate::plugins::PluginRegistry::new();
+        registry
+            .register(Box::new(crate::plugins::ExamplePlugin::new()))
+            .unwrap();
+
+        let config = Config::default();
+        let scanner = Scanner::new(&config)
+            .unwrap()
+            .with_detectors(registry.get_project_detectors());
+        // 默认检测器仍然最后运行，插件检测器按优先级排在内置检测器之间
+        let priorities: Vec<u8> = scanner.detectors.iter().map(|d| d.get_priority()).collect();
+        assert!(priorities.is_sorted());
+        assert_eq!(priorities.last(), Some(&200));
+
+        let projects = scanner.scan(&[dir.path()]).unwrap().projects;
+        assert!(
+            projects[0]
+                .detected_targets
+                .iter()
+                .any(|t| t.path == dir.path().join("example-build"))
+        );
+    }
+
+    #[test]
+    fn test_max_depth_limits_project_search() {
+        let dir = tempfile::tempdir().unwrap();
+        for project in ["app", "group/nested"] {
+            fs::create_dir_all(dir.path().join(project)).unwrap();
+            fs::write(dir.path().join(project).join("package.json"), "{}").unwrap();
+        }
+
+        for respect_gitignore in [true, false] {
+            let found = |max_depth| {
+                let config = Config {
+                    max_depth,
+                    respect_gitignore,
+                    ..Config::default()
+                };
+                let mut paths: Vec<PathBuf> = Scanner::new(&config)
+                    .unwrap()
+                    .scan(&[dir.path()])
+                    .unwrap()
+                    .projects
+                    .into_iter()
+                    .map(|p| p.path)
+                    .collect();
+                paths.sort();
+                paths
+            };
+
+            // 0 只检查根目录本身，1 再检查直接子目录
+            assert!(found(Some(0)).is_empty());
+            assert_eq!(found(Some(1)), vec![dir.path().join("app")]);
+            assert_eq!(
+                found(None),
+                vec![dir.path().join("app"), dir.path().join("group/nested")]
+            );
+        }
+    }
+
+    #[test]
+    fn test_hint_lists_child_projects_when_root_has_none() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["api", "web"] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+
+        let config = Config {
+            max_depth: Some(0),
+            ..Config::default()
+        };
+        let report = Scanner::new(&config).unwrap().scan(&[dir.path()]).unwrap();
+        assert!(report.projects.is_empty());
+        let hint = report.hint.unwrap();
+        assert!(hint.contains("2 subdirectories contain a project"));
+        assert!(hint.contains(&dir.path().join("api").display().to_string()));
+        assert!(hint.contains(&dir.path().join("web").display().to_string()));
+        assert!(!hint.contains("docs"));
+        assert!(hint.contains("--max-depth 1"));
+
+        // 找到项目时不给提示
+        let report = Scanner::new(&Config::default())
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap();
+        assert_eq!(report.projects.len(), 2);
+        assert!(report.hint.is_none());
+    }
+
+    #[test]
+    fn test_gatsby_public_dir_is_build_target() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"gatsby": "^5.0.0", "react": "^18.0.0"}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("public")).unwrap();
+        fs::create_dir_all(dir.path().join(".cache")).unwrap();
+
+        let config = Config {
+            clean_cache_dirs: true,
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        assert_eq!(projects[0].project_type, ProjectType::Gatsby);
+        let target_type = |name: &str| {
+            projects[0]
+                .detected_targets
+                .iter()
+                .find(|t| t.path == dir.path().join(name))
+                .map(|t| t.target_type.clone())
+        };
+        assert_eq!(target_type("public"), Some(TargetType::BuildDir));
+        assert_eq!(target_type(".cache"), Some(TargetType::CacheDir));
+    }
+
+    #[test]
+    fn test_largest_packages_ranks_node_modules_children_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_modules = dir.path().join("node_modules");
+        for (package, size) in [
+            ("left-pad", 10),
+            ("electron", 5000),
+            ("@esbuild/linux-x64", 2000),
+            ("@esbuild/darwin-arm64", 1500),
+            ("react", 300),
+        ] {
+            fs::create_dir_all(node_modules.join(package)).unwrap();
+            fs::write(
+                node_modules.join(package).join("index.js"),
+                vec![b'x'; size],
+            )
+            .unwrap();
+        }
+        fs::write(node_modules.join(".package-lock.json"), "{}").unwrap();
+
+        let packages = largest_packages(dir.path(), 3).unwrap();
+        assert_eq!(
+            packages,
+            vec![
+                ("electron".to_string(), 5000),
+                ("@esbuild".to_string(), 3500),
+                ("react".to_string(), 300),
+            ]
+        );
+
+        assert!(largest_packages(&dir.path().join("missing"), 3).is_err());
+    }
+
+    #[test]
+    fn test_leftover_trash_dirs_become_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for name in [
+            "node_modules.npmclean-trash/pkg",
+            "packages/web/dist.npmclean-trash",
+            "backup/dist.npmclean-trash",
+            "photos.old",
+            "node_modules/.bin.npmclean-trash",
+        ] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+
+        let scan = |config: &Config| {
+            Scanner::new(config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects
+        };
+        let leftovers = |projects: &[Project]| {
+            let mut paths: Vec<PathBuf> = projects
+                .iter()
+                .flat_map(|project| &project.detected_targets)
+                .filter(|target| {
+                    target.target_type == TargetType::Custom(LEFTOVER_TRASH_TARGET.to_string())
+                })
+                .map(|target| target.path.clone())
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        // 只有开启两阶段删除时才查找残留目录
+        assert!(leftovers(&scan(&Config::default())).is_empty());
+
+        let config = Config {
+            two_phase_delete: true,
+            exclude: vec!["backup".to_string()],
+            ..Config::default()
+        };
+        let projects = scan(&config);
+        assert_eq!(
+            leftovers(&projects),
+            vec![
+                dir.path().join("node_modules.npmclean-trash"),
+                dir.path().join("packages/web/dist.npmclean-trash"),
+            ]
+        );
+        // 残留目录归属到包含它的项目，不会单独成为项目
+        assert_eq!(projects.len(), 1);
+    }
+
+    #[test]
+    fn test_react_native_nested_build_dirs_are_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"expo": "^51.0.0", "react-native": "0.74.0"}}"#,
+        )
+        .unwrap();
+        for name in [
+            "android/app/build/outputs",
+            "ios/build",
+            ".expo",
+            "android/app/src",
+        ] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+
+        let projects = Scanner::new(&Config::default())
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        assert_eq!(projects[0].project_type, ProjectType::ReactNative);
+        let target_type = |name: &str| {
+            projects[0]
+                .detected_targets
+                .iter()
+                .find(|t| t.path == dir.path().join(name))
+                .map(|t| t.target_type.clone())
+        };
+        assert_eq!(target_type("android/app/build"), Some(TargetType::BuildDir));
+        assert_eq!(target_type("ios/build"), Some(TargetType::BuildDir));
+        assert_eq!(target_type(".expo"), Some(TargetType::CacheDir));
+        assert_eq!(target_type(".metro"), None);
+        assert_eq!(target_type("android/app/src"), None);
+    }
+
+    #[test]
+    fn test_deep_cache_finds_nested_cache_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        for name in [
+            "src/components/.cache/babel",
+            "tools/.cache",
+            "node_modules/pkg/.cache",
+        ] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+
+        let cache_targets = |config: &Config| {
+            let projects = Scanner::new(config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects;
+            let mut paths: Vec<_> = projects[0]
+                .detected_targets
+                .iter()
+                .filter(|t| t.target_type == TargetType::CacheDir)
+                .map(|t| t.path.clone())
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert!(cache_targets(&Config::default()).is_empty());
+
+        let config = Config {
+            deep_cache: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            cache_targets(&config),
+            [
+                dir.path().join("src/components/.cache"),
+                dir.path().join("tools/.cache"),
+            ]
+        );
+
+        // max_depth 同时限制查找深度
+        let config = Config {
+            deep_cache: true,
+            max_depth: Some(2),
+            ..Config::default()
+        };
+        assert_eq!(cache_targets(&config), [dir.path().join("tools/.cache")]);
+    }
+
+    #[test]
+    fn test_unified_targets_scans_cache_and_coverage_with_build_flag() {
+        use crate::cli::CliArgs;
+        use clap::Parser;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("app");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("package.json"), "{}").unwrap();
+        for name in ["dist", ".cache", "coverage"] {
+            fs::create_dir_all(project.join(name)).unwrap();
+        }
+        let config_path = dir.path().join(".npmcleanrc.yml");
+        fs::write(&config_path, "{}\n").unwrap();
+
+        let config = crate::config::load_config(&CliArgs::parse_from([
+            "npmclean",
+            "--config",
+            config_path.to_str().unwrap(),
+            "--build",
+            "--unified-targets",
+        ]))
+        .unwrap();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[project.as_path()])
+            .unwrap()
+            .projects;
+        let target_types: Vec<_> = projects[0]
+            .detected_targets
+            .iter()
+            .map(|t| (t.path.clone(), t.target_type.clone()))
+            .collect();
+
+        assert!(target_types.contains(&(project.join("dist"), TargetType::BuildDir)));
+        assert!(target_types.contains(&(project.join(".cache"), TargetType::CacheDir)));
+        assert!(target_types.contains(&(project.join("coverage"), TargetType::Coverage)));
+    }
+
+    #[test]
+    fn test_turbo_and_nx_caches_are_cache_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let turbo = dir.path().join("turbo-repo");
+        let nx = dir.path().join("nx-repo");
+        for (root, config_file) in [(&turbo, "turbo.json"), (&nx, "nx.json")] {
+            fs::create_dir_all(root).unwrap();
+            fs::write(root.join("package.json"), "{}").unwrap();
+            fs::write(root.join(config_file), "{}").unwrap();
+        }
+        for path in [
+            turbo.join(".turbo"),
+            nx.join(".nx/cache"),
+            nx.join("node_modules/.cache/nx"),
+        ] {
+            fs::create_dir_all(path).unwrap();
+        }
+
+        // 保留 node_modules 时，Nx 在 node_modules 中的缓存也作为独立目标清理
+        let config = Config {
+            clean_node_modules: false,
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        let cache_targets: Vec<_> = projects
+            .iter()
+            .flat_map(|p| &p.detected_targets)
+            .filter(|t| t.target_type == TargetType::CacheDir)
+            .map(|t| t.path.clone())
+            .collect();
+
+        assert!(cache_targets.contains(&turbo.join(".turbo")));
+        assert!(cache_targets.contains(&nx.join(".nx/cache")));
+        // 默认的 node_modules/.cache 目标已包含 Nx 的缓存，不再重复注册
+        assert!(
+            cache_targets
+                .iter()
+                .any(|path| nx.join("node_modules/.cache/nx").starts_with(path))
+        );
+    }
+
+    #[test]
+    fn test_playwright_test_results_is_coverage_target() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"devDependencies": {"@playwright/test": "^1.40.0", "vitest": "^1.0.0"}}"#,
+        )
+        .unwrap();
+        for name in ["test-results", "playwright-report", "node_modules/.vitest"] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+
+        let config = Config::default();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        let target_type = |name: &str| {
+            projects[0]
+                .detected_targets
+                .iter()
+                .find(|t| t.path == dir.path().join(name))
+                .map(|t| t.target_type.clone())
+        };
+        assert_eq!(target_type("test-results"), Some(TargetType::Coverage));
+        assert_eq!(target_type("playwright-report"), Some(TargetType::Coverage));
+
+        // 保留 node_modules 时，Vitest 的缓存作为 node_modules 内部的缓存目标清理
+        let config = Config {
+            clean_node_modules: false,
+            ..Config::default()
+        };
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+        assert!(projects[0].detected_targets.iter().any(|t| {
+            t.path == dir.path().join("node_modules/.vitest")
+                && t.target_type == TargetType::CacheDir
+        }));
+    }
+
+    #[test]
+    fn test_shared_store_savings_for_identical_lockfiles() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, lockfile, size) in [
+            ("app-a", "lockfileVersion: 3 (shared)", 1000),
+            ("app-b", "lockfileVersion: 3 (shared)", 1000),
+            ("app-c", "lockfileVersion: 3 (different)", 700),
+        ] {
+            let project = dir.path().join(name);
+            fs::create_dir_all(project.join("node_modules/pkg")).unwrap();
+            fs::write(project.join("package.json"), "{}").unwrap();
+            fs::write(project.join("package-lock.json"), lockfile).unwrap();
+            fs::write(project.join("node_modules/pkg/index.js"), vec![0u8; size]).unwrap();
+        }
+
+        let scan = |dedupe_by_lockfile| {
+            let config = Config {
+                stats: true,
+                recursive: true,
+                dedupe_by_lockfile,
+                ..Config::default()
+            };
+            Scanner::new(&config)
+                .unwrap()
+                .scan(&[dir.path()])
+                .unwrap()
+                .projects
+        };
+        // 未开启时不读取 lockfile
+        assert_eq!(shared_store_savings(&scan(false)), 0);
+
+        let projects = scan(true);
+        assert_eq!(projects.len(), 3);
+
+        let one_project = projects
+            .iter()
+            .find(|p| p.path.ends_with("app-a"))
+            .and_then(|p| p.size_info.as_ref())
+            .unwrap()
+            .node_modules_size;
+        assert_eq!(one_project, 1000);
+        assert_eq!(shared_store_savings(&projects), one_project);
+    }
+
+    #[test]
+    fn test_scan_multiple_roots_deduplicates_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let work = dir.path().join("work");
+        let personal = dir.path().join("personal");
+        for project in [work.join("api"), work.join("web"), personal.join("blog")] {
+            fs::create_dir_all(project.join("node_modules")).unwrap();
+            fs::write(project.join("package.json"), "{}").unwrap();
+        }
+
+        let config = Config {
+            recursive: true,
+            ..Config::default()
+        };
+        // work/web 与 work 重叠，其中的项目只出现一次
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[work.clone(), personal.clone(), work.join("web")])
+            .unwrap()
+            .projects;
+
+        let mut found: Vec<PathBuf> = projects.iter().map(|p| p.path.clone()).collect();
+        found.sort();
+        let mut expected = vec![work.join("api"), work.join("web"), personal.join("blog")];
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_invalid_match_regex_errors_at_construction() {
+        let config = Config {
+            match_regex: Some("(unclosed".to_string()),
+            ..Config::default()
+        };
+        let err = Scanner::new(&config).err().unwrap();
+        assert!(err.to_string().contains("--match-regex"));
+    }
+
+    #[test]
+    fn test_framework_detected_from_package_json() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"name": "app", "dependencies": {"react": "^18.2.0", "react-dom": "^18.2.0"}}"#,
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let projects = Scanner::new(&config)
+            .unwrap()
+            .scan(&[dir.path()])
+            .unwrap()
+            .projects;
+
+        assert_eq!(projects[0].project_type, ProjectType::React);
+        assert_eq!(projects[0].package_info.as_ref().unwrap().name, "app");
+    }
+
+    #[test]
+    fn test_parse_packages_manifest_shapes() {
+        let base = Path::new("/repo");
+
+        let pnpm = r#"[
+            {"name": "web", "version": "1.0.0", "path": "/repo/apps/web", "private": true},
+            {"name": "ui", "version": "1.0.0", "path": "/repo/packages/ui", "private": false}
+        ]"#;
+        assert_eq!(
+            parse_packages_manifest(pnpm, base).unwrap(),
+            vec![
+                PathBuf::from("/repo/apps/web"),
+                PathBuf::from("/repo/packages/ui")
+            ]
+        );
+
+        let lerna = r#"[
+            {"name": "ui", "version": "1.0.0", "private": false, "location": "packages/ui"},
+            {"name": "ui-dup", "version": "1.0.0", "private": false, "location": "packages/ui"}
+        ]"#;
+        assert_eq!(
+            parse_packages_manifest(lerna, base).unwrap(),
+            vec![PathBuf::from("/repo/packages/ui")]
+        );
+
+        assert!(parse_packages_manifest(r#"{"name": "x"}"#, base).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! `--check-update`：查询是否有更新的发布版本，只提示，不自动更新
+//!
+//! 只有显式启用时才会访问网络。查询结果缓存一段时间，避免每次运行都请求发布接口。
+
+use anyhow::Result;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 默认查询的发布接口（crates.io）
+pub const DEFAULT_UPDATE_URL: &str = "https://crates.io/api/v1/crates/npmclean";
+
+/// 缓存的查询结果在这段时间内直接使用，不再请求接口
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 请求发布接口的超时时间
+#[cfg(feature = "update-check")]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 版本查询的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateStatus {
+    UpToDate,
+    NewerAvailable(String),
+}
+
+/// 缓存上次查询时间和最新版本的文件
+pub fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("npmclean").join("last-update-check"))
+}
+
+/// 查询 `url` 给出的最新版本并与 `current` 比较
+///
+/// `cache` 中同一接口的结果未过期时不访问网络；查询成功后更新缓存，缓存写入失败被忽略。
+pub fn check_for_update(url: &str, current: &str, cache: Option<&Path>) -> Result<UpdateStatus> {
+    let latest = match cache.and_then(|cache| read_cache(cache, url)) {
+        Some(latest) => latest,
+        None => {
+            let latest = fetch_latest_version(url)?;
+            if let Some(cache) = cache {
+                let _ = write_cache(cache, url, &latest);
+            }
+            latest
+        }
+    };
+
+    Ok(if is_newer(&latest, current) {
+        UpdateStatus::NewerAvailable(latest)
+    } else {
+        UpdateStatus::UpToDate
+    })
+}
+
+/// 读取 `url` 未过期的缓存版本，格式为 `<unix 秒数>\n<接口地址>\n<版本>`；
+/// 缓存来自其他接口时视为没有缓存
+fn read_cache(cache: &Path, url: &str) -> Option<String> {
+    let content = fs::read_to_string(cache).ok()?;
+    let mut lines = content.lines();
+    let (checked_at, cached_url, version) = (lines.next()?, lines.next()?, lines.next()?);
+    if cached_url.trim() != url {
+        return None;
+    }
+    let checked_at = UNIX_EPOCH + Duration::from_secs(checked_at.trim().parse().ok()?);
+    let age = SystemTime::now().duration_since(checked_at).ok()?;
+    (age < CHECK_INTERVAL).then(|| version.trim().to_string())
+}
+
+fn write_cache(cache: &Path, url: &str, latest: &str) -> Result<()> {
+    if let Some(parent) = cache.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    fs::write(cache, format!("{}\n{}\n{}\n", now, url, latest))?;
+    Ok(())
+}
+
+#[cfg(feature = "update-check")]
+fn fetch_latest_version(url: &str) -> Result<String> {
+    use anyhow::Context;
+
+    let body = ureq::get(url)
+        .set(
+            "User-Agent",
+            concat!("npmclean/", env!("CARGO_PKG_VERSION"), " (update check)"),
+        )
+        .timeout(REQUEST_TIMEOUT)
+        .call()
+        .context(format!("Failed to query {}", url))?
+        .into_string()?;
+    parse_latest_version(&body).context(format!("No version found in the response from {}", url))
+}
+
+#[cfg(not(feature = "update-check"))]
+fn fetch_latest_version(_url: &str) -> Result<String> {
+    anyhow::bail!("npmclean was built without the update-check feature")
+}
+
+/// 从接口响应中读取最新版本：crates.io 的 `crate.max_stable_version`，
+/// 或自定义接口的顶层 `version`
+#[cfg_attr(not(feature = "update-check"), allow(dead_code))]
+fn parse_latest_version(body: &str) -> Option<String> {
+    let response: Value = serde_json::from_str(body).ok()?;
+    [
+        &response["crate"]["max_stable_version"],
+        &response["crate"]["max_version"],
+        &response["version"],
+    ]
+    .into_iter()
+    .find_map(|value| value.as_str().map(str::to_string))
+}
+
+/// `latest` 是否比 `current` 新，按 `主.次.修订` 数字比较，忽略预发布后缀
+fn is_newer(latest: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Option<(u64, u64, u64)> {
+        let core = version.trim().trim_start_matches('v');
+        let core = core.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        Some((
+            parts.next()??,
+            parts.next()??,
+            parts.next().unwrap_or(Some(0))?,
+        ))
+    }
+
+    matches!((parse(latest), parse(current)), (Some(latest), Some(current)) if latest > current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(is_newer("0.2.0", "0.1.0"));
+        assert!(is_newer("v1.0.0", "0.9.12"));
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta.1", "0.1.0"));
+        assert!(!is_newer("not-a-version", "0.1.0"));
+    }
+
+    #[test]
+    fn test_cache_is_keyed_by_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("last-update-check");
+        write_cache(&cache, DEFAULT_UPDATE_URL, "9.1.0").unwrap();
+
+        assert_eq!(
+            read_cache(&cache, DEFAULT_UPDATE_URL),
+            Some("9.1.0".to_string())
+        );
+        assert_eq!(read_cache(&cache, "http://127.0.0.1:1/mirror"), None);
+
+        // 旧格式（没有接口地址）的缓存被忽略
+        fs::write(&cache, "1700000000\n9.1.0\n").unwrap();
+        assert_eq!(read_cache(&cache, DEFAULT_UPDATE_URL), None);
+    }
+
+    #[cfg(feature = "update-check")]
+    #[test]
+    fn test_newer_version_reported_from_mock_endpoint_and_cached() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/api/v1/crates/npmclean",
+            listener.local_addr().unwrap()
+        );
+        // 只接受一个请求：第二次查询必须来自缓存
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            let body = r#"{"crate": {"max_stable_version": "9.1.0", "max_version": "9.2.0-rc.1"}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("last-update-check");
+        let status = check_for_update(&url, "0.1.0", Some(&cache)).unwrap();
+        assert_eq!(status, UpdateStatus::NewerAvailable("9.1.0".to_string()));
+        assert!(
+            crate::cli::format_update_status(&status, "0.1.0")
+                .starts_with("A newer version of npmclean is available: 9.1.0")
+        );
+        server.join().unwrap();
+
+        let status = check_for_update(&url, "9.1.0", Some(&cache)).unwrap();
+        assert_eq!(status, UpdateStatus::UpToDate);
+    }
+}
@@ -0,0 +1,175 @@
+use std::env;
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use thiserror::Error;
+
+/// 运行外部命令时的错误
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("'{program}' was not found in PATH")]
+    NotFound { program: String },
+
+    #[error("Failed to start '{program}': {source}")]
+    Spawn {
+        program: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("'{program}' exited with {status}: {stderr}")]
+    Failed {
+        program: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+}
+
+/// 运行外部命令并返回标准输出
+///
+/// 所有外部命令都应通过这里调用：直接启动进程而不经过 shell（最小化容器中可能没有 shell），
+/// 启动前先在 PATH 中查找可执行文件，找不到时返回 `CommandError::NotFound`。
+pub fn run_command<I, S>(program: &str, args: I, cwd: Option<&Path>) -> Result<String, CommandError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    run_command_with_env(program, args, cwd, &[])
+}
+
+/// 与 `run_command` 相同，额外为子进程设置环境变量
+pub fn run_command_with_env<I, S>(
+    program: &str,
+    args: I,
+    cwd: Option<&Path>,
+    envs: &[(String, String)],
+) -> Result<String, CommandError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let executable = find_executable(program).ok_or_else(|| CommandError::NotFound {
+        program: program.to_string(),
+    })?;
+
+    let mut command = Command::new(&executable);
+    command
+        .args(args)
+        .envs(envs.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null());
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+
+    let output = command.output().map_err(|source| CommandError::Spawn {
+        program: program.to_string(),
+        source,
+    })?;
+    if !output.status.success() {
+        return Err(CommandError::Failed {
+            program: program.to_string(),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 运行外部命令的方式，便于在测试中替换
+pub trait CommandRunner {
+    /// 运行命令并返回标准输出
+    fn run(
+        &self,
+        program: &str,
+        args: &[String],
+        envs: &[(String, String)],
+    ) -> Result<String, CommandError>;
+}
+
+/// 通过 `run_command_with_env` 启动真实进程
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(
+        &self,
+        program: &str,
+        args: &[String],
+        envs: &[(String, String)],
+    ) -> Result<String, CommandError> {
+        run_command_with_env(program, args, None, envs)
+    }
+}
+
+/// 在 PATH 中查找可执行文件，类似 `which`
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    // 包含路径分隔符时直接检查该路径
+    if name.contains(std::path::MAIN_SEPARATOR) || name.contains('/') {
+        let path = PathBuf::from(name);
+        return is_executable(&path).then_some(path);
+    }
+
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths).find_map(|dir| {
+        executable_candidates(&dir, name)
+            .into_iter()
+            .find(|candidate| is_executable(candidate))
+    })
+}
+
+/// Windows 上需要尝试 PATHEXT 中的扩展名
+fn executable_candidates(dir: &Path, name: &str) -> Vec<PathBuf> {
+    let mut candidates = vec![dir.join(name)];
+
+    if cfg!(windows) {
+        let exts = env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+        candidates.extend(
+            exts.split(';')
+                .filter(|ext| !ext.is_empty())
+                .map(|ext| dir.join(format!("{}{}", name, ext))),
+        );
+    }
+
+    candidates
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_binary_is_reported_as_not_found() {
+        let err = run_command("npmclean-no-such-binary", ["--version"], None).unwrap_err();
+
+        assert!(matches!(err, CommandError::NotFound { .. }));
+        assert_eq!(
+            err.to_string(),
+            "'npmclean-no-such-binary' was not found in PATH"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_command_without_shell() {
+        // 参数原样传递，不会被 shell 展开
+        assert_eq!(run_command("echo", ["$HOME *"], None).unwrap(), "$HOME *\n");
+        assert!(matches!(
+            run_command("false", std::iter::empty::<&str>(), None),
+            Err(CommandError::Failed { .. })
+        ));
+    }
+}
@@ -1,30 +1,30 @@
 use anyhow::{Context, Result};
+use ignore::WalkBuilder;
 use log::debug;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use walkdir::WalkDir;
 
 /// 递归计算目录大小
+///
+/// 各子目录并行遍历，单个很大的 node_modules 也能利用多个线程。不跟随符号链接
+/// （包括根目录本身），避免重复计算或统计到目录树之外的内容；符号链接形式的
+/// node_modules 因此大小为 0，删除它也只会删除链接本身。
 pub fn calculate_directory_size(path: &Path) -> Result<u64> {
     if !path.exists() {
         debug!("Path does not exist: {}", path.display());
         return Ok(0);
     }
-
-    let mut total_size = 0;
-    let walker = WalkDir::new(path).min_depth(1).into_iter();
-
-    // 使用walkdir，更可靠地处理深层次目录结构
-    for entry in walker.filter_map(|e| e.ok()) {
-        if let Ok(metadata) = entry.metadata() {
-            if metadata.is_file() {
-                let file_size = metadata.len();
-                total_size += file_size;
-                debug!("File: {} Size: {} bytes", entry.path().display(), file_size);
-            }
-        }
+    if is_symlink(path) {
+        debug!("Not following symlink {}", path.display());
+        return Ok(0);
     }
 
+    let total_size = parallel_directory_size(path, &HashSet::new(), &Mutex::default());
+
     debug!(
         "Directory {} total size: {} bytes",
         path.display(),
@@ -33,16 +33,349 @@ pub fn calculate_directory_size(path: &Path) -> Result<u64> {
     Ok(total_size)
 }
 
+/// 一次遍历同时计算多个目录的大小，嵌套在其他目录中的目录不会被重复遍历
+///
+/// 返回的大小与逐个调用 `calculate_directory_size` 的结果一致；
+/// 遍历中未遇到的路径（例如符号链接或普通文件）单独计算。
+pub fn calculate_directory_sizes(paths: &[&Path]) -> Result<HashMap<PathBuf, u64>> {
+    let wanted: HashSet<PathBuf> = paths.iter().map(|path| path.to_path_buf()).collect();
+    // 只遍历不位于其他目标之内的最外层目录
+    let roots: Vec<&PathBuf> = wanted
+        .iter()
+        .filter(|path| {
+            !wanted
+                .iter()
+                .any(|other| other != *path && path.starts_with(other))
+        })
+        .filter(|path| path.is_dir() && !is_symlink(path))
+        .collect();
+
+    let sizes = Mutex::new(HashMap::new());
+    roots.par_iter().for_each(|root| {
+        parallel_directory_size(root, &wanted, &sizes);
+    });
+    let mut sizes = sizes.into_inner().unwrap_or_else(|e| e.into_inner());
+
+    for path in &wanted {
+        if !sizes.contains_key(path) {
+            sizes.insert(path.clone(), calculate_directory_size(path)?);
+        }
+    }
+
+    Ok(sizes)
+}
+
+/// 目录中文件大小之和，子目录交给 rayon 并行处理；无法读取的条目被忽略
+///
+/// 遍历到 `wanted` 中的目录时，把该目录的大小记录到 `sizes` 中。
+fn parallel_directory_size(
+    dir: &Path,
+    wanted: &HashSet<PathBuf>,
+    sizes: &Mutex<HashMap<PathBuf, u64>>,
+) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        debug!("Failed to read directory {}", dir.display());
+        return 0;
+    };
+
+    let mut files_size = 0;
+    let mut subdirs = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        // DirEntry::file_type 不跟随符号链接
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            subdirs.push(entry.path());
+        } else if file_type.is_file() {
+            files_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+
+    let total = files_size
+        + subdirs
+            .par_iter()
+            .map(|subdir| parallel_directory_size(subdir, wanted, sizes))
+            .sum::<u64>();
+
+    if wanted.contains(dir) {
+        sizes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(dir.to_path_buf(), total);
+    }
+    total
+}
+
+/// 将字节数格式化为人类可读的大小（二进制单位），例如 `1.5 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// 路径本身是否是符号链接（不跟随链接）
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// 只删除符号链接本身，不触及它指向的目录
+///
+/// Windows 上目录符号链接和 junction 都是重解析点，必须用 `remove_dir` 删除，
+/// 这同样只删除链接而不会删除目标目录中的内容；指向文件的链接则需要 `remove_file`。
+fn remove_link(path: &Path) -> Result<()> {
+    let removed = if cfg!(windows) {
+        fs::remove_dir(path).or_else(|_| fs::remove_file(path))
+    } else {
+        fs::remove_file(path)
+    };
+    removed.context(format!("Failed to remove symlink: {}", path.display()))
+}
+
 /// 递归删除目录，具有更好的错误处理和性能优化
+///
+/// 目录本身是符号链接（例如链接到共享位置的 node_modules）时只删除链接。
 pub fn remove_directory(path: &Path) -> Result<()> {
+    if is_symlink(path) {
+        debug!("Removing symlink {} without following it", path.display());
+        return remove_link(path);
+    }
+
     // 尝试使用 remove_dir_all 库（一个更可靠的跨平台实现）
     remove_dir_all::remove_dir_all(path)
         .context(format!("Failed to remove directory: {}", path.display()))
 }
 
+/// 递归删除目录，同时统计实际删除的文件字节数
+///
+/// 避免为了统计大小而在删除前额外遍历一次目录树。逐项删除失败时回退到
+/// `remove_directory` 删除剩余内容，此时返回的字节数只包含已统计的部分。
+pub fn remove_directory_counting(path: &Path) -> Result<u64> {
+    if is_symlink(path) {
+        remove_link(path)?;
+        return Ok(0);
+    }
+
+    let mut freed = 0;
+
+    // contents_first 保证先删除子项再删除目录本身；目录树中的符号链接作为普通条目删除，不进入其目标
+    for entry in WalkDir::new(path).follow_links(false).contents_first(true) {
+        let removed = entry.map_err(anyhow::Error::from).and_then(|entry| {
+            if entry.file_type().is_dir() {
+                fs::remove_dir(entry.path())?;
+                Ok(0)
+            } else {
+                let size = if entry.file_type().is_file() {
+                    entry.metadata().map(|m| m.len()).unwrap_or(0)
+                } else {
+                    0
+                };
+                fs::remove_file(entry.path())?;
+                Ok(size)
+            }
+        });
+
+        match removed {
+            Ok(size) => freed += size,
+            Err(e) => {
+                debug!(
+                    "Incremental removal of {} failed ({}), falling back",
+                    path.display(),
+                    e
+                );
+                remove_directory(path)?;
+                break;
+            }
+        }
+    }
+
+    Ok(freed)
+}
+
+/// 两阶段删除时目标被重命名后默认附加的后缀
+pub const DEFAULT_TRASH_SUFFIX: &str = ".npmclean-trash";
+
+/// 两阶段删除：先把目录重命名为 `<name><trash_suffix>`，再删除重命名后的目录
+///
+/// 重命名是原子操作，项目路径会立即变得干净；真正的删除即使中途失败，
+/// 剩下的也只是带后缀的残留目录。返回重命名后的路径和删除的字节数。
+pub fn remove_directory_two_phase(
+    path: &Path,
+    trash_suffix: &str,
+    counting: bool,
+) -> Result<(PathBuf, u64)> {
+    let trash_path = trash_path(path, trash_suffix)?;
+
+    fs::rename(path, &trash_path).context(format!(
+        "Failed to rename {} to {}",
+        path.display(),
+        trash_path.display()
+    ))?;
+
+    let freed = if counting {
+        remove_directory_counting(&trash_path)?
+    } else {
+        remove_directory(&trash_path)?;
+        0
+    };
+
+    Ok((trash_path, freed))
+}
+
+/// 两阶段删除时重命名的目标路径：已有同名残留目录时改用 `<name>-<n><trash_suffix>`，
+/// 编号放在后缀之前，残留目录仍能按后缀识别
+fn trash_path(path: &Path, trash_suffix: &str) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .context(format!("Invalid directory path: {}", path.display()))?;
+
+    (0..)
+        .map(|n| {
+            let mut trash = name.to_os_string();
+            if n > 0 {
+                trash.push(format!("-{}", n));
+            }
+            trash.push(trash_suffix);
+            path.with_file_name(trash)
+        })
+        .find(|trash| fs::symlink_metadata(trash).is_err())
+        .context("No free trash path")
+}
+
+/// 查找 `root` 之下两阶段删除中断后留下的残留目录（名称以 `trash_suffix` 结尾）
+///
+/// 最多遍历 `max_depth` 层；`respect_gitignore` 时跳过被 `.gitignore` 等规则忽略的目录。
+/// 不进入 node_modules、.git 和残留目录本身。
+pub fn find_trash_dirs(
+    root: &Path,
+    trash_suffix: &str,
+    max_depth: usize,
+    respect_gitignore: bool,
+) -> Vec<PathBuf> {
+    if trash_suffix.is_empty() {
+        return Vec::new();
+    }
+
+    let suffix = trash_suffix.to_string();
+    let is_trash = move |name: &std::ffi::OsStr| {
+        name.to_str()
+            .is_some_and(|name| name.len() > suffix.len() && name.ends_with(&suffix))
+    };
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .parents(respect_gitignore)
+        .git_ignore(respect_gitignore)
+        .git_global(respect_gitignore)
+        .git_exclude(respect_gitignore)
+        .require_git(false)
+        .max_depth(Some(max_depth))
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry({
+            let is_trash = is_trash.clone();
+            move |entry| {
+                if entry.depth() == 0 {
+                    return true;
+                }
+                // 残留目录本身要返回，但不进入其中
+                let inside_trash = entry
+                    .path()
+                    .parent()
+                    .and_then(|parent| parent.file_name())
+                    .is_some_and(&is_trash);
+                entry.file_type().is_some_and(|ft| ft.is_dir())
+                    && entry.file_name() != "node_modules"
+                    && entry.file_name() != ".git"
+                    && !inside_trash
+            }
+        })
+        .build();
+
+    walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.depth() > 0 && is_trash(entry.file_name()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// 删除目标目录的方式，便于在测试中替换
+pub trait DirectoryRemover: Send + Sync {
+    /// 删除目录，`counting` 时返回实际删除的字节数，否则返回 0
+    fn remove(&self, path: &Path, counting: bool) -> Result<u64>;
+}
+
+/// 在文件系统上删除目录
+pub struct FsRemover {
+    /// 先重命名为临时名称再删除
+    pub two_phase: bool,
+    /// 两阶段删除时附加到目录名后的后缀
+    pub trash_suffix: String,
+}
+
+impl DirectoryRemover for FsRemover {
+    fn remove(&self, path: &Path, counting: bool) -> Result<u64> {
+        if self.two_phase {
+            remove_directory_two_phase(path, &self.trash_suffix, counting).map(|(_, freed)| freed)
+        } else if counting {
+            remove_directory_counting(path)
+        } else {
+            remove_directory(path).map(|_| 0)
+        }
+    }
+}
+
+/// 查询路径所在卷的可用空间
+pub trait FreeSpaceProbe {
+    /// 返回可用字节数，无法查询时返回 None
+    fn available_space(&self, path: &Path) -> Option<u64>;
+}
+
+/// 通过操作系统查询可用空间
+pub struct SystemFreeSpace;
+
+impl FreeSpaceProbe for SystemFreeSpace {
+    fn available_space(&self, path: &Path) -> Option<u64> {
+        match fs2::available_space(path) {
+            Ok(space) => Some(space),
+            Err(e) => {
+                debug!("Failed to query free space for {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+/// 路径所在卷的标识，用于同一卷只查询一次；不支持的平台返回 None
+pub fn volume_id(path: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        fs::metadata(path).ok().map(|m| m.dev())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
 /// 递归删除目录，但用深度优先策略，适用于包含大量小文件的深层目录结构
 #[allow(dead_code)]
 pub fn remove_directory_deep_first(path: &Path) -> Result<()> {
+    if is_symlink(path) {
+        return remove_link(path);
+    }
     if !path.exists() {
         return Ok(());
     }
@@ -71,7 +404,15 @@ pub fn remove_directory_deep_first(path: &Path) -> Result<()> {
             for entry in entries.filter_map(Result::ok) {
                 let entry_path = entry.path();
 
-                if entry_path.is_file() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_symlink() {
+                    // 只删除链接，不进入链接指向的目录
+                    if let Err(e) = remove_link(&entry_path) {
+                        eprintln!("Warning: {}", e);
+                    }
+                } else if file_type.is_file() {
                     // 直接删除文件
                     if let Err(e) = fs::remove_file(&entry_path) {
                         eprintln!(
@@ -80,7 +421,7 @@ pub fn remove_directory_deep_first(path: &Path) -> Result<()> {
                             e
                         );
                     }
-                } else if entry_path.is_dir() {
+                } else if file_type.is_dir() {
                     // 将目录添加到栈中
                     stack.push(entry_path);
                 }
@@ -120,3 +461,141 @@ pub fn is_empty_dir(path: &Path) -> bool {
         Err(_) => false, // 如果无法读取目录，则视为非空
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size_boundaries() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KB");
+        assert_eq!(format_size(512 * 1024), "512.0 KB");
+        assert_eq!(format_size(1024 * 1024 * 1024 * 3 / 2), "1.5 GB");
+        assert_eq!(format_size(1024u64.pow(4) * 2), "2.0 TB");
+    }
+
+    #[test]
+    fn test_parallel_size_matches_serial_walk() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("node_modules");
+        // 40 个包，每个包含嵌套目录，共 3000 个大小不同的文件
+        for package in 0..40 {
+            for sub in ["", "lib", "lib/internal"] {
+                let sub_dir = root.join(format!("pkg-{}", package)).join(sub);
+                fs::create_dir_all(&sub_dir).unwrap();
+                for file in 0..25 {
+                    let size = (package * 31 + file * 7) % 500;
+                    fs::write(sub_dir.join(format!("f{}.js", file)), vec![0u8; size]).unwrap();
+                }
+            }
+        }
+
+        // 串行的参考实现
+        let serial: u64 = WalkDir::new(&root)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.metadata().unwrap().len())
+            .sum();
+
+        let started = std::time::Instant::now();
+        let parallel = calculate_directory_size(&root).unwrap();
+        debug!("Parallel size of 3000 files took {:?}", started.elapsed());
+
+        assert!(serial > 0);
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn test_remove_directory_counting_matches_tree_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("node_modules");
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        fs::write(root.join("index.js"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("a/pkg.json"), vec![0u8; 250]).unwrap();
+        fs::write(root.join("a/b/c/deep.js"), vec![0u8; 4096]).unwrap();
+
+        let expected = calculate_directory_size(&root).unwrap();
+        let freed = remove_directory_counting(&root).unwrap();
+
+        assert_eq!(freed, expected);
+        assert_eq!(freed, 4446);
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_remove_directory_two_phase() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("dist");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("bundle.js"), vec![0u8; 64]).unwrap();
+
+        let (trash_path, freed) =
+            remove_directory_two_phase(&root, DEFAULT_TRASH_SUFFIX, true).unwrap();
+
+        assert_eq!(trash_path, dir.path().join("dist.npmclean-trash"));
+        assert_eq!(freed, 64);
+        assert!(!root.exists());
+        assert!(!trash_path.exists());
+    }
+
+    #[test]
+    fn test_remove_directory_two_phase_avoids_existing_leftover() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("dist");
+        let leftover = dir.path().join("dist.npmclean-trash");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("bundle.js"), vec![0u8; 64]).unwrap();
+        fs::create_dir_all(&leftover).unwrap();
+        fs::write(leftover.join("old.js"), "x").unwrap();
+
+        let (trash_path, freed) =
+            remove_directory_two_phase(&root, DEFAULT_TRASH_SUFFIX, true).unwrap();
+
+        assert_eq!(trash_path, dir.path().join("dist-1.npmclean-trash"));
+        assert_eq!(freed, 64);
+        assert!(!root.exists());
+        // 之前的残留目录保持原样，留给启动时的清扫处理
+        assert!(leftover.join("old.js").exists());
+        assert_eq!(
+            find_trash_dirs(dir.path(), DEFAULT_TRASH_SUFFIX, 2, false),
+            [leftover]
+        );
+    }
+
+    #[test]
+    fn test_find_trash_dirs_skips_node_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "app/node_modules.npmclean-trash/pkg",
+            "app/packages/web/dist.npmclean-trash",
+            "app/node_modules/pkg/dist.npmclean-trash",
+            "app/dist",
+        ] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+
+        assert_eq!(
+            find_trash_dirs(dir.path(), DEFAULT_TRASH_SUFFIX, 8, true),
+            [
+                dir.path().join("app/node_modules.npmclean-trash"),
+                dir.path().join("app/packages/web/dist.npmclean-trash"),
+            ]
+        );
+        assert_eq!(
+            find_trash_dirs(dir.path(), DEFAULT_TRASH_SUFFIX, 2, true),
+            [dir.path().join("app/node_modules.npmclean-trash")]
+        );
+        assert!(find_trash_dirs(dir.path(), "", 8, true).is_empty());
+
+        // 被 .gitignore 忽略的目录中的残留目录不会被找到
+        fs::write(dir.path().join("app/.gitignore"), "packages/\n").unwrap();
+        assert_eq!(
+            find_trash_dirs(dir.path(), DEFAULT_TRASH_SUFFIX, 8, true),
+            [dir.path().join("app/node_modules.npmclean-trash")]
+        );
+    }
+}
@@ -1 +1,4 @@
+pub mod command;
 pub mod fs_utils;
+pub mod parse;
+pub mod priority;
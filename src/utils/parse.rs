@@ -0,0 +1,327 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Deserializer, Serializer};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+/// 解析人类可读的时长，例如 `90s`、`30m`、`1h`、`7d`、`2w`，纯数字按秒处理
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("Duration must not be empty");
+    }
+
+    let split_at = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split_at);
+
+    let value: f64 = match number.parse() {
+        Ok(value) => value,
+        Err(_) => bail!("Invalid duration '{}': expected a number like 30m", input),
+    };
+
+    let seconds_per_unit = match unit.trim().to_lowercase().as_str() {
+        "" | "s" | "sec" | "secs" => 1.0,
+        "m" | "min" | "mins" => 60.0,
+        "h" | "hr" | "hrs" => 3600.0,
+        "d" | "day" | "days" => 86_400.0,
+        "w" | "week" | "weeks" => 604_800.0,
+        other => bail!(
+            "Invalid duration unit '{}' in '{}': use s, m, h, d or w",
+            other,
+            input
+        ),
+    };
+
+    match Duration::try_from_secs_f64(value * seconds_per_unit) {
+        Ok(duration) => Ok(duration),
+        Err(_) => bail!("Duration '{}' is too large", input),
+    }
+}
+
+/// 解析人类可读的大小，例如 `100MB`、`1.5GiB`、`10k`，纯数字按字节处理
+///
+/// `KB`/`MB`/`GB`/`TB`（以及 `k`/`m`/`g`/`t`）是十进制单位，
+/// `KiB`/`MiB`/`GiB`/`TiB` 是二进制单位。结果不是整数字节（如 `1.5`）时报错。
+pub fn parse_size(input: &str) -> Result<u64> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("Size must not be empty");
+    }
+
+    let split_at = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split_at);
+
+    // 按十进制整数运算，避免 `2.01KB` 这类数值因浮点误差被误判为非整数字节
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (integer.is_empty() && fraction.is_empty()) || !is_digits(integer) || !is_digits(fraction) {
+        bail!("Invalid size '{}': expected a number like 100MB", input);
+    }
+
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "t" | "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        other => bail!(
+            "Invalid size unit '{}' in '{}': use B, KB, MB, GB, TB or KiB, MiB, GiB, TiB",
+            other,
+            input
+        ),
+    };
+
+    let too_large = || anyhow::anyhow!("Size '{}' is too large", input);
+    let integer: u128 = match integer {
+        "" => 0,
+        digits => digits.parse().map_err(|_| too_large())?,
+    };
+    let fraction = fraction.trim_end_matches('0');
+    let not_whole = || anyhow::anyhow!("Invalid size '{}': not a whole number of bytes", input);
+
+    let mut bytes = integer
+        .checked_mul(u128::from(multiplier))
+        .ok_or_else(too_large)?;
+    if !fraction.is_empty() {
+        let scale = u32::try_from(fraction.len())
+            .ok()
+            .and_then(|digits| 10u128.checked_pow(digits))
+            .ok_or_else(not_whole)?;
+        let numerator = fraction
+            .parse::<u128>()
+            .ok()
+            .and_then(|fraction| fraction.checked_mul(u128::from(multiplier)))
+            .ok_or_else(not_whole)?;
+        if numerator % scale != 0 {
+            return Err(not_whole());
+        }
+        bytes += numerator / scale;
+    }
+
+    u64::try_from(bytes).map_err(|_| too_large())
+}
+
+/// 将命令行字符串拆分为程序和参数，支持单引号和双引号，不做任何 shell 展开
+pub fn parse_command_line(input: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        bail!("Unterminated quote in command '{}'", input);
+    }
+    if in_word {
+        words.push(current);
+    }
+    if words.is_empty() {
+        bail!("Command must not be empty");
+    }
+    Ok(words)
+}
+
+/// 配置文件中的时长，可以写成 `"7d"` 这样的字符串或表示秒数的整数
+pub fn deserialize_optional_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawDuration {
+        Seconds(u64),
+        Text(String),
+    }
+
+    match Option::<RawDuration>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(RawDuration::Seconds(secs)) => Ok(Some(Duration::from_secs(secs))),
+        Some(RawDuration::Text(text)) => parse_duration(&text)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+/// 配置文件中的大小原始值，可以写成 `"100MB"` 这样的字符串或字节数
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSize {
+    Bytes(u64),
+    Text(String),
+}
+
+impl RawSize {
+    fn into_bytes<E: serde::de::Error>(self) -> Result<u64, E> {
+        match self {
+            RawSize::Bytes(bytes) => Ok(bytes),
+            RawSize::Text(text) => parse_size(&text).map_err(E::custom),
+        }
+    }
+}
+
+/// 配置文件中的可选大小，见 `parse_size`
+pub fn deserialize_optional_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<RawSize>::deserialize(deserializer)?
+        .map(RawSize::into_bytes)
+        .transpose()
+}
+
+/// 配置文件中值为大小的映射，见 `parse_size`
+pub fn deserialize_size_map<'de, D, K>(deserializer: D) -> Result<HashMap<K, u64>, D::Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de> + Eq + Hash,
+{
+    HashMap::<K, RawSize>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, size)| Ok((key, size.into_bytes()?)))
+        .collect()
+}
+
+/// 将时长序列化为秒数，与 `deserialize_optional_duration` 对应
+pub fn serialize_optional_duration<S>(
+    value: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(duration) => serializer.serialize_some(&duration.as_secs()),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert_eq!(parse_size("512B").unwrap(), 512);
+        assert_eq!(parse_size("10k").unwrap(), 10_000);
+        assert_eq!(parse_size("10KiB").unwrap(), 10_240);
+        assert_eq!(parse_size("100MB").unwrap(), 100_000_000);
+        assert_eq!(parse_size(" 100 mb ").unwrap(), 100_000_000);
+        assert_eq!(parse_size("1.5GB").unwrap(), 1_500_000_000);
+        assert_eq!(parse_size("1.5GiB").unwrap(), 1_610_612_736);
+        assert_eq!(parse_size("2TiB").unwrap(), 2 << 40);
+        // 浮点运算下这些数值会出现舍入误差
+        assert_eq!(parse_size("2.01KB").unwrap(), 2_010);
+        assert_eq!(parse_size("2.05MB").unwrap(), 2_050_000);
+        assert_eq!(parse_size("1.07GB").unwrap(), 1_070_000_000);
+        assert_eq!(parse_size("0.5KiB").unwrap(), 512);
+        assert_eq!(parse_size("2.500kb").unwrap(), 2_500);
+    }
+
+    #[test]
+    fn test_parse_size_rejects_invalid_input() {
+        for input in [
+            "",
+            "abc",
+            "MB",
+            "-1",
+            "1.2.3GB",
+            "10XB",
+            "10 M B",
+            "1.5",
+            "0.5B",
+            "1e3",
+            ".",
+            "1.0001KB",
+            "99999999999TB",
+            "99999999999999999999999999999999999999999",
+        ] {
+            assert!(parse_size(input).is_err(), "{:?} should be rejected", input);
+        }
+        let error = parse_size("10XB").unwrap_err().to_string();
+        assert!(error.contains("KiB"), "{}", error);
+    }
+
+    #[test]
+    fn test_sizes_in_config_accept_units() {
+        #[derive(Deserialize)]
+        struct Sizes {
+            #[serde(default, deserialize_with = "deserialize_optional_size")]
+            min_size: Option<u64>,
+            #[serde(default, deserialize_with = "deserialize_size_map")]
+            by_type: HashMap<String, u64>,
+        }
+
+        let sizes: Sizes =
+            serde_yaml::from_str("min_size: 100MB\nby_type:\n  cache: 1GiB\n  build: 2048\n")
+                .unwrap();
+        assert_eq!(sizes.min_size, Some(100_000_000));
+        assert_eq!(sizes.by_type["cache"], 1 << 30);
+        assert_eq!(sizes.by_type["build"], 2048);
+
+        let sizes: Sizes = serde_yaml::from_str("min_size: null").unwrap();
+        assert_eq!(sizes.min_size, None);
+        assert!(serde_yaml::from_str::<Sizes>("min_size: lots").is_err());
+    }
+
+    #[test]
+    fn test_parse_command_line() {
+        assert_eq!(
+            parse_command_line(r#"notify-send "npmclean done" 'freed $X' ''"#).unwrap(),
+            ["notify-send", "npmclean done", "freed $X", ""]
+        );
+        assert!(parse_command_line("echo 'oops").is_err());
+        assert!(parse_command_line("   ").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(2_592_000)
+        );
+        assert_eq!(
+            parse_duration("2W").unwrap(),
+            Duration::from_secs(1_209_600)
+        );
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("10y").is_err());
+        // 超出 Duration 范围时报错而不是 panic
+        assert!(parse_duration("99999999999999999999w").is_err());
+        assert!(parse_duration(&"9".repeat(400)).is_err());
+    }
+}
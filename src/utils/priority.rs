@@ -0,0 +1,97 @@
+use anyhow::Result;
+
+/// 低优先级模式下的目标 nice 值
+#[cfg(unix)]
+const LOW_PRIORITY_NICE: i32 = 10;
+
+/// 降低当前进程的 CPU 和 IO 优先级，避免影响同一台机器上正在进行的构建
+///
+/// 平台支持：
+/// - Linux：提高 nice 值，并把 IO 优先级设为 best-effort 最低级（相当于 `ionice -c2 -n7`）
+/// - 其他 Unix：只提高 nice 值
+/// - Windows：进入后台处理模式，同时降低 CPU 和 IO 优先级
+/// - 其他平台：不支持，返回错误
+///
+/// Linux 上优先级按线程生效，新线程继承创建者的设置，因此需要在创建线程池之前调用。
+pub fn lower_process_priority() -> Result<()> {
+    #[cfg(unix)]
+    {
+        lower_nice()?;
+        #[cfg(target_os = "linux")]
+        lower_io_priority();
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Threading::{
+            GetCurrentProcess, PROCESS_MODE_BACKGROUND_BEGIN, SetPriorityClass,
+        };
+
+        // SAFETY: GetCurrentProcess 返回伪句柄，无需关闭
+        let ok = unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) };
+        if ok == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        anyhow::bail!("Lowering process priority is not supported on this platform")
+    }
+}
+
+/// 把 nice 值提高到 `LOW_PRIORITY_NICE`；已经更低优先级时保持不变
+#[cfg(unix)]
+fn lower_nice() -> Result<()> {
+    // getpriority 可能合法地返回 -1，这里只需要和目标值比较，所以不必区分错误
+    // SAFETY: 只查询和设置调用者自身的优先级
+    let current = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+    if current >= LOW_PRIORITY_NICE {
+        return Ok(());
+    }
+
+    // SAFETY: 同上
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, LOW_PRIORITY_NICE) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// 把 IO 优先级设为 best-effort 最低级，失败时只记录日志
+#[cfg(target_os = "linux")]
+fn lower_io_priority() {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_BE: libc::c_long = 2;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    const IOPRIO_LOWEST_LEVEL: libc::c_long = 7;
+
+    let ioprio = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | IOPRIO_LOWEST_LEVEL;
+    // SAFETY: ioprio_set 只修改调用者自身的 IO 优先级
+    let ret = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) };
+    if ret != 0 {
+        log::debug!(
+            "Failed to lower IO priority: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_lower_process_priority_raises_niceness() {
+        // Linux 上优先级按线程生效，在单独的线程里测试以免影响其他测试
+        std::thread::spawn(|| {
+            lower_process_priority().unwrap();
+            let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+            assert!(nice >= LOW_PRIORITY_NICE);
+        })
+        .join()
+        .unwrap();
+    }
+}